@@ -0,0 +1,301 @@
+//! Areas known to the API, and static tables of their names.
+//!
+//! The names here follow the ONS area lookup used by the dashboard, and are
+//! lowercase to match the form expected by `FilterValue::AreaName`. Only the
+//! closed sets of area (the overview, nations, regions, and NHS regions) are
+//! bundled; the much larger and more frequently revised UTLA and LTLA sets
+//! are not.
+
+use std::fmt;
+
+use time::Date;
+
+use crate::{AreaType, Error, FilterValue};
+#[cfg(feature = "validate")]
+pub(crate) use table::validate;
+
+/// The four nations of the UK, for filtering requests by nation without 
+/// spelling out their names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nation {
+    England,
+    Scotland,
+    Wales,
+    NorthernIreland,
+}
+impl Nation {
+    /// All four nations.
+    pub const ALL: [Nation; 4] = [Nation::England, Nation::Scotland, Nation::Wales, Nation::NorthernIreland];
+
+    /// The periods for which a national stay-at-home order (a "lockdown") 
+    /// was in force in the nation, oldest first, as the first and last days 
+    /// of each, inclusive.
+    /// 
+    /// Local restrictions, and the lighter tiers between lockdowns, aren't 
+    /// included.
+    pub fn lockdowns(&self) -> Vec<(Date, Date)> {
+        let periods = match self {
+            Nation::England => ENGLAND_LOCKDOWNS,
+            Nation::Scotland => SCOTLAND_LOCKDOWNS,
+            Nation::Wales => WALES_LOCKDOWNS,
+            Nation::NorthernIreland => NORTHERN_IRELAND_LOCKDOWNS,
+        };
+        let date = |(y, m, d)| Date::try_from_ymd(y, m, d).unwrap();
+        periods.iter().map(|(start, end)| (date(*start), date(*end))).collect()
+    }
+}
+impl fmt::Display for Nation {
+    /// Formats the nation with its area name as used by the API.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Nation::England => "england",
+            Nation::Scotland => "scotland",
+            Nation::Wales => "wales",
+            Nation::NorthernIreland => "northern ireland",
+        })
+    }
+}
+impl From<Nation> for FilterValue {
+    fn from(nation: Nation) -> FilterValue {
+        FilterValue::AreaName(nation.to_string())
+    }
+}
+
+type Period = ((i32, u8, u8), (i32, u8, u8));
+
+const ENGLAND_LOCKDOWNS: &[Period] = &[
+    ((2020, 3, 23), (2020, 7, 3)),
+    ((2020, 11, 5), (2020, 12, 1)),
+    ((2021, 1, 6), (2021, 3, 28)),
+];
+
+const SCOTLAND_LOCKDOWNS: &[Period] = &[
+    ((2020, 3, 23), (2020, 7, 2)),
+    ((2021, 1, 5), (2021, 4, 1)),
+];
+
+const WALES_LOCKDOWNS: &[Period] = &[
+    ((2020, 3, 23), (2020, 7, 5)),
+    ((2020, 10, 23), (2020, 11, 8)),
+    ((2020, 12, 20), (2021, 3, 12)),
+];
+
+const NORTHERN_IRELAND_LOCKDOWNS: &[Period] = &[
+    ((2020, 3, 28), (2020, 6, 7)),
+    ((2020, 11, 27), (2020, 12, 10)),
+    ((2020, 12, 26), (2021, 4, 11)),
+];
+
+/// An ONS area code, such as `E08000035`, for filtering requests by area 
+/// code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AreaCode(String);
+impl AreaCode {
+    /// Creates an AreaCode, checking that it has the form of an ONS area 
+    /// code: a country letter (`E`, `W`, `S`, `N`, or `K` for UK-wide areas) 
+    /// followed by 8 digits.
+    /// 
+    /// ## Errors
+    /// 
+    /// Returns `Error::InvalidAreaCode` if the code is malformed. Well-formed 
+    /// codes are not checked against the areas known to the API.
+    pub fn new(code: &str) -> Result<AreaCode, Error> {
+        let mut chars = code.chars();
+        let well_formed = matches!(chars.next(), Some('E') | Some('W') | Some('S') | Some('N') | Some('K'))
+            && code.len() == 9
+            && chars.all(|c| c.is_ascii_digit());
+
+        if well_formed {
+            Ok(AreaCode(code.to_string()))
+        } else {
+            Err(Error::InvalidAreaCode(code.to_string()))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the code has the prefix of the ONS codes used for areas of 
+    /// the AreaType (such as `E92` for England, as a nation), so could name 
+    /// one of them.
+    pub fn could_be(&self, area_type: &AreaType) -> bool {
+        let prefixes: &[&str] = match area_type {
+            AreaType::Overview => &["K02"],
+            AreaType::Nation => &["E92", "N92", "S92", "W92"],
+            AreaType::Region => &["E12"],
+            AreaType::NHSRegion => &["E40"],
+            AreaType::UTLA => &["E06", "E08", "E09", "E10", "N09", "S12", "W06"],
+            AreaType::LTLA => &["E06", "E07", "E08", "E09", "N09", "S12", "W06"],
+        };
+        prefixes.iter().any(|p| self.0.starts_with(p))
+    }
+}
+impl fmt::Display for AreaCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+impl From<AreaCode> for FilterValue {
+    fn from(code: AreaCode) -> FilterValue {
+        FilterValue::AreaCode(code)
+    }
+}
+
+#[cfg(feature = "validate")]
+mod table {
+    use crate::{AreaType, Error, Filter, FilterValue};
+
+    const OVERVIEW: &[&str] = &["united kingdom"];
+
+    const NATIONS: &[&str] = &["england", "northern ireland", "scotland", "wales"];
+
+    const REGIONS: &[&str] = &[
+        "east midlands",
+        "east of england",
+        "london",
+        "north east",
+        "north west",
+        "south east",
+        "south west",
+        "west midlands",
+        "yorkshire and the humber",
+    ];
+
+    const NHS_REGIONS: &[&str] = &[
+        "east of england",
+        "london",
+        "midlands",
+        "north east and yorkshire",
+        "north west",
+        "south east",
+        "south west",
+    ];
+
+    /// Returns the names of every area of the given type, or `None` if the 
+    /// areas of that type are not bundled.
+    fn names_for(area_type: &AreaType) -> Option<&'static [&'static str]> {
+        match area_type {
+            AreaType::Overview => Some(OVERVIEW),
+            AreaType::Nation => Some(NATIONS),
+            AreaType::Region => Some(REGIONS),
+            AreaType::NHSRegion => Some(NHS_REGIONS),
+            AreaType::UTLA | AreaType::LTLA => None,
+        }
+    }
+
+    /// Checks that any AreaName Filter names an area of the requested AreaType.
+    ///
+    /// Names for area types which aren't bundled are always accepted.
+    pub(crate) fn validate(filters: &[Filter]) -> Result<(), Error> {
+        let area_type = filters.iter().find_map(|f| match &f.value {
+            FilterValue::AreaType(t) => Some(t),
+            _ => None,
+        });
+        let area_type = match area_type {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+
+        if let Some(names) = names_for(area_type) {
+            for filter in filters {
+                if let FilterValue::AreaName(n) = &filter.value {
+                    if !names.contains(&n.to_lowercase().as_str()) {
+                        return Err(Error::InvalidAreaForType {
+                            area_type: area_type.clone(),
+                            area_name: n.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn ltla_name_for_nation() {
+            let filters = vec![
+                Filter::new(FilterValue::AreaType(AreaType::Nation)),
+                Filter::new(FilterValue::AreaName(String::from("hackney"))),
+            ];
+
+            match validate(&filters) {
+                Err(Error::InvalidAreaForType { area_type, area_name }) => {
+                    assert_eq!(area_type, AreaType::Nation);
+                    assert_eq!(area_name, "hackney");
+                }
+                r => panic!("expected InvalidAreaForType, got {:?}", r),
+            }
+
+            let filters = vec![
+                Filter::new(FilterValue::AreaType(AreaType::Nation)),
+                Filter::new(FilterValue::AreaName(String::from("england"))),
+            ];
+            assert!(validate(&filters).is_ok());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nation_names() {
+        assert_eq!(Nation::England.to_string(), "england");
+        assert_eq!(FilterValue::from(Nation::England), FilterValue::AreaName(String::from("england")));
+        assert_eq!(FilterValue::from(Nation::Scotland), FilterValue::AreaName(String::from("scotland")));
+        assert_eq!(FilterValue::from(Nation::Wales), FilterValue::AreaName(String::from("wales")));
+        assert_eq!(
+            FilterValue::from(Nation::NorthernIreland),
+            FilterValue::AreaName(String::from("northern ireland"))
+        );
+    }
+
+    #[test]
+    fn lockdown_periods() {
+        for nation in &Nation::ALL {
+            let periods = nation.lockdowns();
+            assert!(!periods.is_empty());
+            assert!(periods.iter().all(|(start, end)| start <= end));
+            assert!(periods.windows(2).all(|w| w[0].1 < w[1].0));
+        }
+        assert_eq!(
+            Nation::England.lockdowns()[1],
+            (Date::try_from_ymd(2020, 11, 5).unwrap(), Date::try_from_ymd(2020, 12, 1).unwrap())
+        );
+    }
+
+    #[test]
+    fn area_codes() {
+        let code = AreaCode::new("E08000035").unwrap();
+        assert_eq!(code.as_str(), "E08000035");
+        assert_eq!(FilterValue::from(code.clone()), FilterValue::AreaCode(code));
+        assert!(AreaCode::new("K02000001").is_ok());
+    }
+
+    #[test]
+    fn area_code_types() {
+        let hackney = AreaCode::new("E09000012").unwrap();
+        assert!(hackney.could_be(&AreaType::LTLA));
+        assert!(hackney.could_be(&AreaType::UTLA));
+        assert!(!hackney.could_be(&AreaType::Nation));
+        assert!(AreaCode::new("W92000004").unwrap().could_be(&AreaType::Nation));
+        assert!(!AreaCode::new("E07000008").unwrap().could_be(&AreaType::UTLA));
+    }
+
+    #[test]
+    fn malformed_area_codes() {
+        for code in &["", "E0800003", "E080000355", "X08000035", "e08000035", "E0800003O"] {
+            match AreaCode::new(code) {
+                Err(Error::InvalidAreaCode(c)) => assert_eq!(c, *code),
+                r => panic!("expected InvalidAreaCode for {:?}, got {:?}", code, r),
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! A command-line interface for quick queries of the API, printing the 
+//! result as CSV or JSON. Requires the `cli` feature.
+//!
+//! ```text
+//! covid --area nation --name england --metric date --metric cumCasesByPublishDate --format csv
+//! ```
+
+use std::io::Write;
+use std::process;
+
+use covid19_uk_rs::export::{self, DateFormat};
+use covid19_uk_rs::{AreaCode, AreaType, Error, Filter, FilterValue, MetricKind, Request};
+
+const USAGE: &str = "usage: covid --area <type> [--name <name>] [--code <code>] --metric <metric>... \
+[--format csv|json] [--base-url <url>]";
+
+/// The options given on the command line.
+#[derive(Debug, Default)]
+struct Options {
+    area: Option<AreaType>,
+    name: Option<String>,
+    code: Option<AreaCode>,
+    metrics: Vec<MetricKind>,
+    json: bool,
+    base_url: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} requires a value", arg));
+        match arg.as_str() {
+            "--area" => options.area = Some(value()?.parse().map_err(|e| format!("{:?}", e))?),
+            "--name" => options.name = Some(value()?.to_lowercase()),
+            "--code" => options.code = Some(AreaCode::new(&value()?).map_err(|e| format!("{:?}", e))?),
+            "--metric" => options.metrics.push(value()?.parse().unwrap()),
+            "--format" => {
+                options.json = match value()?.as_str() {
+                    "csv" => false,
+                    "json" => true,
+                    f => return Err(format!("unknown format: {}", f)),
+                }
+            }
+            "--base-url" => options.base_url = Some(value()?),
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+
+    if options.area.is_none() || options.metrics.is_empty() {
+        return Err(String::from("--area and at least one --metric are required"));
+    }
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<(), Error> {
+    let mut req = Request::with_metrics(options.area.unwrap(), &options.metrics);
+    if let Some(name) = options.name {
+        req.add_filter(Filter::new(FilterValue::AreaName(name)));
+    }
+    if let Some(code) = options.code {
+        req.add_filter(Filter::new(code.into()));
+    }
+    if let Some(url) = options.base_url {
+        req.set_base_url(&url);
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    if options.json {
+        let json = export::to_json(&req.get()?, &DateFormat::Iso8601)?;
+        writeln!(out, "{}", json).map_err(Error::IoError)
+    } else {
+        req.write_csv(&mut out)
+    }
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(options) {
+        eprintln!("error: {:?}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,755 @@
+//! A Client for sending many requests with shared configuration.
+//!
+//! Requests sent through a Client are retried after transient failures (rate
+//! limiting, server errors, and connection errors). All retries made by a 
+//! Client draw from a single budget, so a batch of requests against a 
+//! struggling API can't multiply the number of requests made by retrying 
+//! independently.
+//!
+//! With the `async` feature, requests may also be sent asynchronously with 
+//! `Client::get_async`. Concurrent asynchronous fetches of the same page 
+//! share a single request to the API.
+
+#[cfg(feature = "async")]
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use futures::future::{BoxFuture, FutureExt, Shared};
+
+#[cfg(feature = "async")]
+use crate::transport::{AsyncTransport, HttpAsyncTransport};
+use crate::transport::{HttpTransport, PoolConfig, Response, Transport, METHOD};
+use crate::{AreaType, Data, Date, Error, Metric, MetricKind, Request};
+
+/// A page fetch which may be awaited by several requests at once.
+#[cfg(feature = "async")]
+type SharedFetch = Shared<BoxFuture<'static, Result<Response, Arc<Error>>>>;
+
+/// Identifies an in-flight fetch by its URL and the headers it is sent with.
+#[cfg(feature = "async")]
+type FetchKey = (String, Vec<(String, String)>);
+
+/// Sends requests to the API, retrying transient failures.
+/// 
+/// Clients are `Send` and `Sync`, and clones share their retry budget (and, 
+/// with the `async` feature, their in-flight fetches), so a Client may be 
+/// shared between threads or cloned into each of them.
+#[derive(Debug, Clone)]
+pub struct Client {
+    transport: Arc<dyn Transport>,
+    max_retries: usize,
+    retry_delay: Duration,
+    budget: Arc<AtomicUsize>,
+    default_headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    rate_limit: Option<Arc<RateLimit>>,
+    #[cfg(feature = "async")]
+    async_transport: Arc<dyn AsyncTransport>,
+    #[cfg(feature = "async")]
+    in_flight: Arc<Mutex<HashMap<FetchKey, SharedFetch>>>,
+}
+impl Client {
+    /// Creates a new Client sending requests over HTTP.
+    /// 
+    /// By default, each page is retried up to 3 times, waiting 1 second 
+    /// longer before each successive retry, and the Client makes at most 20 
+    /// retries in total.
+    pub fn new() -> Client {
+        Client::with_transport(Arc::new(HttpTransport::new()))
+    }
+
+    /// Creates a new Client sending requests over HTTP, with the provided 
+    /// connection pool settings (see `PoolConfig` for the defaults), for 
+    /// tuning the connections kept alive for large concurrent batches such 
+    /// as `get_for_areas` across many areas.
+    pub fn with_pool(pool: PoolConfig) -> Client {
+        let client = Client::with_transport(Arc::new(HttpTransport::with_pool(pool.clone())));
+        #[cfg(feature = "async")]
+        let client = Client {
+            async_transport: Arc::new(HttpAsyncTransport::with_pool(pool)),
+            ..client
+        };
+        client
+    }
+
+    /// Creates a new Client sending requests with the provided Transport.
+    pub fn with_transport(transport: Arc<dyn Transport>) -> Client {
+        Client {
+            transport,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+            budget: Arc::new(AtomicUsize::new(20)),
+            default_headers: vec![],
+            timeout: None,
+            rate_limit: None,
+            #[cfg(feature = "async")]
+            async_transport: Arc::new(HttpAsyncTransport::new()),
+            #[cfg(feature = "async")]
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sets the AsyncTransport used by `get_async`, in place of the default 
+    /// `HttpAsyncTransport`.
+    #[cfg(feature = "async")]
+    pub fn set_async_transport(&mut self, transport: Arc<dyn AsyncTransport>) {
+        self.async_transport = transport;
+    }
+
+    /// Sends asynchronous requests with the provided reqwest client, as with 
+    /// `Request::with_client`. This replaces any AsyncTransport set with 
+    /// `set_async_transport`.
+    #[cfg(feature = "async")]
+    pub fn with_async_client(&mut self, client: reqwest::Client) {
+        self.async_transport = Arc::new(HttpAsyncTransport::with_client(client));
+    }
+
+    /// Sets the maximum number of times each page may be retried.
+    pub fn set_max_retries(&mut self, max_retries: usize) {
+        self.max_retries = max_retries;
+    }
+
+    /// Sets the delay before the first retry of a page; each subsequent 
+    /// retry of the same page waits this much longer than the last.
+    pub fn set_retry_delay(&mut self, retry_delay: Duration) {
+        self.retry_delay = retry_delay;
+    }
+
+    /// Sets the total number of retries which may be made by this Client 
+    /// (and its clones), shared between all the requests it sends. Once the 
+    /// budget is spent, failures are returned without retrying.
+    pub fn set_retry_budget(&mut self, retries: usize) {
+        self.budget.store(retries, Ordering::SeqCst);
+    }
+
+    /// Adds a header to be sent with every request sent through this Client 
+    /// (such as a `User-Agent` or `Authorization`), unless the request sets 
+    /// the same header itself.
+    /// 
+    /// Other configuration shared by every request, such as proxies, may be 
+    /// set on a reqwest client used through `HttpTransport::with_client`.
+    pub fn add_default_header(&mut self, name: &str, value: &str) {
+        self.default_headers.push((name.to_string(), value.to_string()));
+    }
+
+    /// Sets how long each page request sent through this Client may take 
+    /// before it fails with a timeout (which is retried as any other request 
+    /// error), in place of the transport's own timeout. By default, no 
+    /// timeout is set.
+    /// 
+    /// The timeout is passed to the Transport (or, for `get_async`, the 
+    /// AsyncTransport) with `get_with_timeout`, which `HttpTransport` and 
+    /// `HttpAsyncTransport` apply.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    /// Limits the requests sent through this Client (and its clones) to at 
+    /// most `requests` in each `per`, spaced evenly, waiting before sending 
+    /// any request which would exceed it. Retries count towards the limit, 
+    /// and a fetch shared by several `get_async` calls counts once. By 
+    /// default, requests aren't limited.
+    /// 
+    /// ## Panics
+    /// 
+    /// This function will panic if `requests` is zero.
+    pub fn set_rate_limit(&mut self, requests: u32, per: Duration) {
+        assert!(requests > 0, "a rate limit must allow at least one request");
+        self.rate_limit = Some(Arc::new(RateLimit {
+            interval: per / requests,
+            next: Mutex::new(None),
+        }));
+    }
+
+    /// Returns the number of retries remaining in the Client's budget.
+    pub fn retry_budget(&self) -> usize {
+        self.budget.load(Ordering::SeqCst)
+    }
+
+    /// Executes the request through this Client, as with `Request::get`.
+    /// 
+    /// If the request has its own Transport (set with `set_transport` or 
+    /// `with_client`), it is sent through that Transport, with this Client's 
+    /// retries and defaults; otherwise, it is sent through the Client's.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error once retries were exhausted.
+    pub fn get(&self, req: &Request) -> Result<Data, Error> {
+        self.prepare(req).get()
+    }
+
+    /// Executes the request once for each of the provided area names, 
+    /// concurrently, returning the results in the same order as the names.
+    /// 
+    /// The `areaCode` metric is added to each request if it isn't already 
+    /// present, so that the days can be combined and regrouped with 
+    /// `data::group_by_area`. All the fetches share this Client's retry 
+    /// budget.
+    pub fn get_for_areas(&self, req: &Request, area_names: &[&str]) -> Vec<Result<Data, Error>> {
+        let mut req = self.prepare(req);
+        req.ensure_metric(MetricKind::AreaCode);
+
+        let clones: Vec<Request> = area_names.iter().map(|name| req.with_area_name(name)).collect();
+        let results = thread::scope(|s| {
+            let handles: Vec<_> = clones.iter().map(|req| s.spawn(move || req.get())).collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        req.record_clones(&clones);
+        results
+    }
+
+    /// Checks that the API is available, with a single minimal request (the 
+    /// latest date of the UK overview), so that a batch of requests can fail 
+    /// fast. The check is not retried and doesn't draw from the retry budget.
+    /// 
+    /// ## Errors
+    /// 
+    /// Returns `Error::TooManyRequests` if the API is rate limiting requests, 
+    /// `Error::APIServerError` for any server error (5xx), or an Error as 
+    /// returned by `Request::get` for any other failure.
+    pub fn healthcheck(&self) -> Result<(), Error> {
+        let req = Request::new(AreaType::Overview, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        if let Some(limit) = &self.rate_limit {
+            thread::sleep(limit.wait());
+        }
+        let res = self.transport.get_with_timeout(
+            &req.construct_url(&Some(MetricKind::Date), &1),
+            &with_defaults(&req.headers(), &self.default_headers),
+            self.timeout,
+        )?;
+
+        match res.status {
+            200 => Ok(()),
+            s if s >= 500 => Err(Error::APIServerError),
+            _ => req.parse_response(res).map(|_| ()),
+        }
+    }
+
+    /// Executes the request asynchronously, as with `Request::get`.
+    /// 
+    /// If another call is already fetching the same page with the same 
+    /// headers, this call waits for and shares its response rather than 
+    /// sending a duplicate request; if that shared fetch fails, the error is 
+    /// returned wrapped in `Error::Shared`. The error of a fetch which wasn't 
+    /// shared is returned as it is. Asynchronous requests are not retried, 
+    /// and the request's own Transport is not used.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    #[cfg(feature = "async")]
+    pub async fn get_async(&self, req: &Request) -> Result<Data, Error> {
+        req.check()?;
+
+        let mut data = vec![];
+        let mut page = 1;
+
+        loop {
+            let url = req.construct_url(&None, &page);
+            let headers: Vec<(String, String)> = with_defaults(&req.headers(), &self.default_headers)
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+
+            let resp = match self.fetch_shared(url, headers).await {
+                Ok(res) => req.parse_response(res),
+                Err(e) => Err(Arc::try_unwrap(e).unwrap_or_else(Error::Shared)),
+            };
+            let resp = match resp {
+                Ok(r) => r,
+                Err(e) if req.config.partial_ok && page > 1 => {
+                    log::warn!(
+                        "Returning partial data ({} pages): fetching page {} failed: {:?}",
+                        page - 1,
+                        page,
+                        e
+                    );
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+
+            data.extend(req.parse_data(&resp)?);
+
+            if req.config.single_page || resp["pagination"]["next"].is_null() {
+                break;
+            } else {
+                page += 1
+            }
+        }
+
+        req.finish(&mut data);
+        Ok(data)
+    }
+
+    /// Fetches the URL, joining any fetch of the same URL with the same 
+    /// headers already in flight.
+    #[cfg(feature = "async")]
+    fn fetch_shared(&self, url: String, mut headers: Vec<(String, String)>) -> SharedFetch {
+        headers.sort();
+        let key = (url, headers);
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(fetch) = in_flight.get(&key) {
+            return fetch.clone();
+        }
+
+        let transport = self.async_transport.clone();
+        let finished = self.in_flight.clone();
+        let (url, headers) = key.clone();
+        let (timeout, rate_limit) = (self.timeout, self.rate_limit.clone());
+        let fetch = async move {
+            if let Some(limit) = rate_limit {
+                tokio::time::delay_for(limit.wait()).await;
+            }
+            let sent: Vec<(&str, &str)> = headers.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect();
+            let res = transport.get_with_timeout(&url, &sent, timeout).await.map_err(Arc::new);
+            finished.lock().unwrap().remove(&(url, headers));
+            res
+        }
+        .boxed()
+        .shared();
+
+        in_flight.insert(key, fetch.clone());
+        fetch
+    }
+
+    /// Returns a copy of the request which sends through this Client, 
+    /// wrapping the request's own Transport if it has one.
+    fn prepare(&self, req: &Request) -> Request {
+        let mut req = req.derived();
+        let inner = req.config.transport.clone().unwrap_or_else(|| self.transport.clone());
+        req.set_transport(Arc::new(RetryingTransport {
+            inner,
+            max_retries: self.max_retries,
+            retry_delay: self.retry_delay,
+            budget: self.budget.clone(),
+            default_headers: self.default_headers.clone(),
+            timeout: self.timeout,
+            rate_limit: self.rate_limit.clone(),
+        }));
+        req
+    }
+}
+impl Default for Client {
+    fn default() -> Client {
+        Client::new()
+    }
+}
+
+/// Wraps a Transport, retrying transient failures while the shared budget 
+/// allows.
+/// 
+/// Only GET requests are retried, as only they are safe to repeat; requests 
+/// are sent with the method `transport::METHOD`, which is currently GET, but 
+/// this guards against retrying requests if it is ever changed.
+#[derive(Debug)]
+struct RetryingTransport {
+    inner: Arc<dyn Transport>,
+    max_retries: usize,
+    retry_delay: Duration,
+    budget: Arc<AtomicUsize>,
+    default_headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    rate_limit: Option<Arc<RateLimit>>,
+}
+impl RetryingTransport {
+    /// Returns the reason a request with the provided method may be retried 
+    /// after the provided result, or `None` if it may not be.
+    fn retry_reason(method: &reqwest::Method, res: &Result<Response, Error>) -> Option<String> {
+        if method != reqwest::Method::GET {
+            return None;
+        }
+        match res {
+            Ok(r) if r.status == 429 || r.status >= 500 => Some(format!("status {}", r.status)),
+            Err(Error::RequestErr(e)) => Some(e.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Takes a retry from the budget, returning false if it is spent.
+    fn take_retry(&self) -> bool {
+        self.budget
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+    }
+}
+impl Transport for RetryingTransport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        let headers = with_defaults(headers, &self.default_headers);
+        let mut retries = 0;
+        loop {
+            if let Some(limit) = &self.rate_limit {
+                thread::sleep(limit.wait());
+            }
+            let res = self.inner.get_with_timeout(url, &headers, self.timeout);
+            let reason = match RetryingTransport::retry_reason(&METHOD, &res) {
+                Some(reason) if retries < self.max_retries && self.take_retry() => reason,
+                _ => return res,
+            };
+
+            retries += 1;
+            log::warn!(
+                "Retrying {} {} (retry {} of {}) after {}",
+                METHOD,
+                url,
+                retries,
+                self.max_retries,
+                reason
+            );
+            thread::sleep(self.retry_delay * retries as u32);
+        }
+    }
+}
+
+/// Spaces the requests sent through a Client, as set with 
+/// `Client::set_rate_limit`.
+#[derive(Debug)]
+struct RateLimit {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+impl RateLimit {
+    /// Reserves the next slot for a request, returning how long to wait 
+    /// before sending it.
+    fn wait(&self) -> Duration {
+        let now = Instant::now();
+        let mut next = self.next.lock().unwrap();
+        let slot = next.map_or(now, |n| n.max(now));
+        *next = Some(slot + self.interval);
+        slot - now
+    }
+}
+
+/// Adds the default headers to a request's headers, except those which the 
+/// request sets itself.
+fn with_defaults<'a>(headers: &[(&'a str, &'a str)], defaults: &'a [(String, String)]) -> Vec<(&'a str, &'a str)> {
+    let mut headers = headers.to_vec();
+    for (name, value) in defaults {
+        if !headers.iter().any(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            headers.push((name, value));
+        }
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+    use crate::{Filter, FilterValue, MetricValue};
+
+    /// A Transport which is always rate-limited.
+    #[derive(Debug, Default)]
+    struct RateLimitedTransport {
+        requests: AtomicUsize,
+    }
+    impl Transport for RateLimitedTransport {
+        fn get(&self, _url: &str, _headers: &[(&str, &str)]) -> Result<Response, Error> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            Ok(Response::new(429, ""))
+        }
+    }
+
+    /// A Transport whose gateway is always unavailable.
+    #[derive(Debug, Default)]
+    struct UnavailableTransport {
+        requests: AtomicUsize,
+    }
+    impl Transport for UnavailableTransport {
+        fn get(&self, _url: &str, _headers: &[(&str, &str)]) -> Result<Response, Error> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            Ok(Response::new(503, "Service Unavailable"))
+        }
+    }
+
+    /// An AsyncTransport which responds to each request after a delay, so 
+    /// that concurrent requests overlap.
+    #[cfg(feature = "async")]
+    #[derive(Debug, Default)]
+    struct SlowAsyncTransport {
+        requests: AtomicUsize,
+    }
+    #[cfg(feature = "async")]
+    impl AsyncTransport for SlowAsyncTransport {
+        fn get<'a>(
+            &'a self,
+            _url: &'a str,
+            _headers: &'a [(&'a str, &'a str)],
+        ) -> BoxFuture<'a, Result<Response, Error>> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async {
+                tokio::time::delay_for(Duration::from_millis(50)).await;
+                Ok(crate::transport::MockTransport::page("[12]", false))
+            })
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn concurrent_identical_requests_coalesce() {
+        let transport = Arc::new(SlowAsyncTransport::default());
+        let mut client = Client::with_transport(Arc::new(RateLimitedTransport::default()));
+        client.set_async_transport(transport.clone());
+
+        let req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let results = futures::future::join_all((0..8).map(|_| client.get_async(&req))).await;
+
+        assert_eq!(results.len(), 8);
+        for data in results {
+            assert_eq!(data.unwrap(), vec![vec![Metric::NewCasesByPublishDate(MetricValue::Int(12))]]);
+        }
+        assert_eq!(transport.requests.load(Ordering::SeqCst), 1);
+        assert!(client.in_flight.lock().unwrap().is_empty());
+    }
+
+    /// An AsyncTransport whose requests fail after a delay, so that 
+    /// concurrent requests overlap.
+    #[cfg(feature = "async")]
+    #[derive(Debug, Default)]
+    struct FailingAsyncTransport;
+    #[cfg(feature = "async")]
+    impl AsyncTransport for FailingAsyncTransport {
+        fn get<'a>(
+            &'a self,
+            _url: &'a str,
+            _headers: &'a [(&'a str, &'a str)],
+        ) -> BoxFuture<'a, Result<Response, Error>> {
+            Box::pin(async {
+                tokio::time::delay_for(Duration::from_millis(50)).await;
+                Err(Error::TlsUnavailable)
+            })
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn only_shared_errors_wrapped() {
+        let mut client = Client::with_transport(Arc::new(RateLimitedTransport::default()));
+        client.set_async_transport(Arc::new(FailingAsyncTransport));
+        let req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+
+        assert!(matches!(client.get_async(&req).await, Err(Error::TlsUnavailable)));
+
+        let results = futures::future::join_all((0..2).map(|_| client.get_async(&req))).await;
+        for result in results {
+            match result {
+                Err(Error::Shared(e)) => assert!(matches!(*e, Error::TlsUnavailable)),
+                r => panic!("expected a shared error, got {:?}", r),
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn requests_with_different_headers_not_coalesced() {
+        let transport = Arc::new(SlowAsyncTransport::default());
+        let mut client = Client::with_transport(Arc::new(RateLimitedTransport::default()));
+        client.set_async_transport(transport.clone());
+
+        let english = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let mut welsh = english.clone();
+        welsh.set_accept_language("cy-GB");
+        let (english, welsh) = futures::future::join(client.get_async(&english), client.get_async(&welsh)).await;
+
+        assert!(english.is_ok() && welsh.is_ok());
+        assert_eq!(transport.requests.load(Ordering::SeqCst), 2);
+        assert!(client.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn shared_retry_budget() {
+        let transport = Arc::new(RateLimitedTransport::default());
+        let mut client = Client::with_transport(transport.clone());
+        client.set_max_retries(5);
+        client.set_retry_delay(Duration::from_millis(0));
+        client.set_retry_budget(6);
+
+        let req = Request::new(AreaType::LTLA, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let areas = ["hackney", "camden", "islington", "lambeth"];
+        let results = client.get_for_areas(&req, &areas);
+
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(|r| matches!(r, Err(Error::TooManyRequests(_)))));
+        // One request per area, plus at most the budgeted retries.
+        assert_eq!(transport.requests.load(Ordering::SeqCst), 4 + 6);
+        assert_eq!(client.retry_budget(), 0);
+    }
+
+    #[test]
+    fn request_transport_kept() {
+        let client = Client::with_transport(Arc::new(UnavailableTransport::default()));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page("[12]", false)]));
+        req.set_transport(transport.clone());
+
+        assert_eq!(
+            client.get(&req).unwrap(),
+            vec![vec![Metric::NewCasesByPublishDate(MetricValue::Int(12))]]
+        );
+        assert_eq!(transport.urls.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn server_errors_returned() {
+        let transport = Arc::new(UnavailableTransport::default());
+        let mut client = Client::with_transport(transport.clone());
+        client.set_max_retries(1);
+        client.set_retry_delay(Duration::from_millis(0));
+
+        let req = Request::new(AreaType::LTLA, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let results = client.get_for_areas(&req, &["hackney", "camden"]);
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| matches!(r, Err(Error::APIServerError))));
+        assert_eq!(transport.requests.load(Ordering::SeqCst), 2 * 2);
+    }
+
+    #[test]
+    fn client_timeout() {
+        let url = crate::transport::serve(vec![(
+            Duration::from_millis(500),
+            String::from("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"),
+        )]);
+        let mut client = Client::new();
+        client.set_max_retries(0);
+        client.set_timeout(Duration::from_millis(100));
+
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_base_url(&url);
+        match client.get(&req) {
+            Err(Error::RequestErr(e)) => assert!(e.is_timeout()),
+            r => panic!("expected a timeout, got {:?}", r),
+        }
+    }
+
+    #[cfg(feature = "record")]
+    #[test]
+    fn client_timeout_when_recording() {
+        let url = crate::transport::serve(vec![(
+            Duration::from_millis(500),
+            String::from("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"),
+        )]);
+        let dir = std::env::temp_dir().join(format!("covid19_uk_rs-timeout-{}", std::process::id()));
+        let mut client = Client::new();
+        client.set_max_retries(0);
+        client.set_timeout(Duration::from_millis(100));
+
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_base_url(&url);
+        req.set_transport(Arc::new(crate::record::RecordingTransport::new(
+            Arc::new(HttpTransport::new()),
+            &dir,
+        )));
+        match client.get(&req) {
+            Err(Error::RequestErr(e)) => assert!(e.is_timeout()),
+            r => panic!("expected a timeout, got {:?}", r),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_client_timeout() {
+        let url = crate::transport::serve(vec![(
+            Duration::from_millis(500),
+            String::from("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"),
+        )]);
+        let mut client = Client::with_transport(Arc::new(RateLimitedTransport::default()));
+        client.set_async_transport(Arc::new(HttpAsyncTransport::new()));
+        client.set_timeout(Duration::from_millis(100));
+
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_base_url(&url);
+        match client.get_async(&req).await {
+            Err(Error::RequestErr(e)) => assert!(e.is_timeout()),
+            r => panic!("expected a timeout, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn rate_limit() {
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page("[1]", false); 4]));
+        let mut client = Client::with_transport(transport.clone());
+        client.set_rate_limit(2, Duration::from_millis(200));
+
+        let req = Request::new(AreaType::LTLA, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let start = Instant::now();
+        let results = client.get_for_areas(&req, &["hackney", "camden", "islington", "lambeth"]);
+
+        assert!(results.iter().all(Result::is_ok));
+        // The requests are spaced 100ms apart, so the last waits 300ms.
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert_eq!(transport.urls.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn healthcheck_statuses() {
+        let client = |status| {
+            Client::with_transport(Arc::new(MockTransport::new(vec![if status == 200 {
+                MockTransport::page(r#"["2021-01-01"]"#, false)
+            } else {
+                Response::new(status, "")
+            }])))
+        };
+
+        assert!(client(200).healthcheck().is_ok());
+        assert!(matches!(client(429).healthcheck(), Err(Error::TooManyRequests(_))));
+        assert!(matches!(client(503).healthcheck(), Err(Error::APIServerError)));
+    }
+
+    #[test]
+    fn default_headers() {
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page("[12]", false),
+            MockTransport::page("[12]", false),
+        ]));
+        let mut client = Client::with_transport(transport.clone());
+        client.add_default_header("User-Agent", "covid-dashboard/1.0");
+        client.add_default_header("Accept-Language", "en-GB");
+
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        client.get(&req).unwrap();
+        req.set_accept_language("cy");
+        client.get(&req).unwrap();
+
+        let headers = transport.headers.lock().unwrap();
+        let header = |i: usize, name: &str| -> Vec<String> {
+            headers[i].iter().filter(|(n, _)| n == name).map(|(_, v)| v.clone()).collect()
+        };
+        assert_eq!(header(0, "User-Agent"), vec!["covid-dashboard/1.0"]);
+        assert_eq!(header(0, "Accept-Language"), vec!["en-GB"]);
+        assert_eq!(header(1, "User-Agent"), vec!["covid-dashboard/1.0"]);
+        assert_eq!(header(1, "Accept-Language"), vec!["cy"]);
+    }
+
+    #[test]
+    fn retries_logged() {
+        crate::tests::capture_warnings();
+
+        let transport = Arc::new(RateLimitedTransport::default());
+        let mut client = Client::with_transport(transport.clone());
+        client.set_max_retries(2);
+        client.set_retry_delay(Duration::from_millis(0));
+
+        let mut req = Request::new(AreaType::LTLA, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("retries_logged"))));
+        assert!(matches!(client.get(&req), Err(Error::TooManyRequests(_))));
+        assert_eq!(transport.requests.load(Ordering::SeqCst), 3);
+
+        let warnings = crate::tests::warnings("retries_logged");
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Retrying GET https://"));
+        assert!(warnings[1].ends_with("(retry 2 of 2) after status 429"));
+
+        let rate_limited = Ok(Response::new(429, ""));
+        assert!(RetryingTransport::retry_reason(&METHOD, &rate_limited).is_some());
+        assert!(RetryingTransport::retry_reason(&reqwest::Method::POST, &rate_limited).is_none());
+    }
+}
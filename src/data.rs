@@ -0,0 +1,570 @@
+//! Helpers for working with the `Data` returned by a request.
+//!
+//! Most of these pair each day's value for a metric with its date, so 
+//! require the `date` metric to have been requested; days without a date are 
+//! skipped.
+
+use std::collections::BTreeMap;
+
+use time::Date;
+
+use crate::export::{self, DateFormat};
+use crate::{Data, Datum, Metric, MetricKind};
+
+/// Returns the first Metric of the provided kind in the Datum.
+pub(crate) fn find<'a>(day: &'a Datum, kind: &MetricKind) -> Option<&'a Metric> {
+    day.iter().find(|m| m.kind() == *kind)
+}
+
+/// Returns the date of the Datum, if the `date` metric was requested.
+pub(crate) fn date_of(day: &Datum) -> Option<Date> {
+    day.iter().find_map(|m| match m {
+        Metric::Date(d) => Some(*d),
+        _ => None,
+    })
+}
+
+/// Returns the `areaCode` of the Datum, or its `areaName` if the code 
+/// wasn't requested.
+fn area_of(day: &Datum) -> Option<&String> {
+    day.iter()
+        .find_map(|m| match m {
+            Metric::AreaCode(code) => Some(code),
+            _ => None,
+        })
+        .or_else(|| {
+            day.iter().find_map(|m| match m {
+                Metric::AreaName(name) => Some(name),
+                _ => None,
+            })
+        })
+}
+
+/// Groups the days of Data covering several areas by area, keyed by the 
+/// `areaCode` metric, or by `areaName` if the code wasn't requested.
+///
+/// Days without either are left out, so one of them must be requested (as 
+/// `Request::get_by_area` and `Client::get_for_areas` ensure). Each area's 
+/// days keep the order of the Data.
+pub fn group_by_area(data: &Data) -> BTreeMap<String, Data> {
+    let mut areas: BTreeMap<String, Data> = BTreeMap::new();
+    for day in data {
+        if let Some(key) = area_of(day) {
+            areas.entry(key.clone()).or_default().push(day.clone());
+        }
+    }
+    areas
+}
+
+/// The value of a single Metric, whatever its type, as held by each of the 
+/// Metrics with a value (such as `Metric::NewCasesByPublishDate`), returned 
+/// by `Metric::value`, and in the rows of `to_long`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricValue {
+    /// The API reported no value for the day.
+    Null,
+    Int(i64),
+    Float(f64),
+    Text(String),
+    /// Structured values, such as age bands, in the form returned by the API.
+    Json(json::JsonValue),
+}
+impl MetricValue {
+    /// Returns the value as an integer, if it is one, or a float or numeric 
+    /// text with an integral value; `None` otherwise.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            MetricValue::Int(v) => Some(*v),
+            MetricValue::Float(v) if v.fract() == 0.0 && v.abs() < i64::MAX as f64 => Some(*v as i64),
+            MetricValue::Text(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a float, if it is numeric (including integers and 
+    /// numeric text); `None` otherwise.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MetricValue::Int(v) => Some(*v as f64),
+            MetricValue::Float(v) => Some(*v),
+            MetricValue::Text(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the API reported no value.
+    pub fn is_null(&self) -> bool {
+        *self == MetricValue::Null
+    }
+
+    /// Returns the value as JSON, as it would appear in a response.
+    pub fn to_json(&self) -> json::JsonValue {
+        match self {
+            MetricValue::Null => json::JsonValue::Null,
+            MetricValue::Int(v) => (*v).into(),
+            MetricValue::Float(v) => (*v).into(),
+            MetricValue::Text(s) => s.as_str().into(),
+            MetricValue::Json(v) => v.clone(),
+        }
+    }
+}
+impl From<&Metric> for MetricValue {
+    fn from(metric: &Metric) -> MetricValue {
+        match metric {
+            Metric::MaleCases(bands)
+            | Metric::FemaleCases(bands)
+            | Metric::HospitalCasesByAge(bands)
+            | Metric::CovidOccupiedMechanicalVentilatorBedsByAge(bands) => {
+                MetricValue::Json(export::age_bands_json(bands))
+            }
+            m => match m.wrapped() {
+                Some(v) => v.clone(),
+                None => export::metric_text(m, &DateFormat::Iso8601).map_or(MetricValue::Null, MetricValue::Text),
+            },
+        }
+    }
+}
+impl From<i32> for MetricValue {
+    fn from(v: i32) -> MetricValue {
+        MetricValue::Int(v.into())
+    }
+}
+impl From<i64> for MetricValue {
+    fn from(v: i64) -> MetricValue {
+        MetricValue::Int(v)
+    }
+}
+impl From<f64> for MetricValue {
+    fn from(v: f64) -> MetricValue {
+        MetricValue::Float(v)
+    }
+}
+impl From<&str> for MetricValue {
+    fn from(s: &str) -> MetricValue {
+        MetricValue::Text(s.to_string())
+    }
+}
+impl<T: Into<MetricValue>> From<Option<T>> for MetricValue {
+    /// Converts `None` to `MetricValue::Null`.
+    fn from(v: Option<T>) -> MetricValue {
+        v.map_or(MetricValue::Null, Into::into)
+    }
+}
+
+/// Parses a raw value from a response into a MetricValue by its JSON type, 
+/// for values whose type isn't known in advance (such as those of 
+/// `Metric::Generic`).
+///
+/// Integral numbers are `Int`, other numbers `Float`, and strings `Text`, 
+/// even if they hold a number (which `MetricValue::as_i64` and `as_f64` will 
+/// still read); anything else, such as an array, is kept as `Json`.
+pub fn parse_value(value: &json::JsonValue) -> MetricValue {
+    if value.is_null() {
+        MetricValue::Null
+    } else if let Some(s) = value.as_str() {
+        MetricValue::Text(s.to_string())
+    } else if let Some(i) = value.as_i64() {
+        MetricValue::Int(i)
+    } else if let Some(f) = value.as_f64() {
+        MetricValue::Float(f)
+    } else {
+        MetricValue::Json(value.clone())
+    }
+}
+
+/// A single metric's value for a day and area, as a row of `to_long`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LongRow {
+    pub date: Option<Date>,
+    /// The `areaCode` of the day, or its `areaName` if the code wasn't 
+    /// requested.
+    pub area: Option<String>,
+    /// The API name of the metric.
+    pub metric: String,
+    pub value: MetricValue,
+}
+
+/// Unpivots the Data into tidy long format, with one row for each metric of 
+/// each day, in the order of the Data, as input for analysis tools expecting 
+/// one observation per row.
+///
+/// The `date`, `areaCode`, `areaName`, and `areaType` metrics identify each 
+/// row rather than being rows of their own.
+pub fn to_long(data: &Data) -> Vec<LongRow> {
+    data.iter()
+        .flat_map(|day| {
+            let date = date_of(day);
+            let area = area_of(day).cloned();
+            day.iter()
+                .filter(|m| {
+                    !matches!(m, Metric::Date(_) | Metric::AreaCode(_) | Metric::AreaName(_) | Metric::AreaType(_))
+                })
+                .map(move |m| LongRow {
+                    date,
+                    area: area.clone(),
+                    metric: m.kind().name().to_string(),
+                    value: m.into(),
+                })
+        })
+        .collect()
+}
+
+/// Splits the Data into aligned columns of dates and values for a numeric 
+/// metric, in the order of the Data.
+///
+/// Values are `None` where the API reported no value for the day, or where 
+/// the metric wasn't requested or isn't numeric.
+pub fn columns_i32(data: &Data, metric: MetricKind) -> (Vec<Date>, Vec<Option<i32>>) {
+    data.iter()
+        .filter_map(|day| {
+            let date = date_of(day)?;
+            Some((date, find(day, &metric).and_then(Metric::as_i32)))
+        })
+        .unzip()
+}
+
+/// Returns the earliest and latest dates covered by the Data, or `None` if 
+/// the Data is empty or the `date` metric wasn't requested.
+pub fn date_range(data: &Data) -> Option<(Date, Date)> {
+    let mut dates = data.iter().filter_map(date_of);
+    let first = dates.next()?;
+
+    Some(dates.fold((first, first), |(earliest, latest), d| (earliest.min(d), latest.max(d))))
+}
+
+/// Returns the test positivity rate for each day, as the fraction of the 
+/// day's tests which were positive cases.
+///
+/// The rate is `None` where either value is missing or no tests were 
+/// reported.
+pub fn positivity(data: &Data, cases: MetricKind, tests: MetricKind) -> Vec<(Date, Option<f64>)> {
+    data.iter()
+        .filter_map(|day| {
+            let date = date_of(day)?;
+            let cases = find(day, &cases).and_then(Metric::as_i64);
+            let tests = find(day, &tests).and_then(Metric::as_i64);
+            let rate = match (cases, tests) {
+                (Some(c), Some(t)) if t != 0 => Some(c as f64 / t as f64),
+                _ => None,
+            };
+            Some((date, rate))
+        })
+        .collect()
+}
+
+/// Returns the value of a numeric metric on the most recent day which has 
+/// one, along with that day's date.
+///
+/// The newest day's figures are often yet to be published, so this is the 
+/// "latest figure" a dashboard would show.
+pub fn latest_non_null_i32(data: &Data, metric: MetricKind) -> Option<(Date, i32)> {
+    data.iter()
+        .filter_map(|day| Some((date_of(day)?, find(day, &metric).and_then(Metric::as_i32)?)))
+        .max_by_key(|(date, _)| *date)
+}
+
+/// Returns a numeric metric's values on the `n` most recent days, oldest 
+/// first if `ascending` or else newest first, for fixed-length displays such 
+/// as sparklines.
+///
+/// Values are `None` where the API reported no value for the day. If the 
+/// Data has fewer than `n` dated days, the window is padded with `None` 
+/// before its oldest day, so it is always `n` long.
+pub fn recent_window_i32(data: &Data, metric: MetricKind, n: usize, ascending: bool) -> Vec<Option<i32>> {
+    let mut days: Vec<(Date, Option<i32>)> = data
+        .iter()
+        .filter_map(|day| Some((date_of(day)?, find(day, &metric).and_then(Metric::as_i32))))
+        .collect();
+    days.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+    days.truncate(n);
+
+    let mut window: Vec<Option<i32>> = days.into_iter().map(|(_, value)| value).collect();
+    window.resize(n, None);
+    if ascending {
+        window.reverse();
+    }
+    window
+}
+
+/// Returns only the days on which a numeric metric's value satisfies the 
+/// predicate, such as those with more than a given number of new cases, in 
+/// the order of the Data.
+///
+/// Days with no value for the metric are dropped. Unlike most of these 
+/// helpers, the `date` metric isn't needed.
+pub fn filter_by_threshold<F>(data: &Data, metric: MetricKind, predicate: F) -> Data
+where
+    F: Fn(i32) -> bool,
+{
+    data.iter()
+        .filter(|day| find(day, &metric).and_then(Metric::as_i32).is_some_and(&predicate))
+        .cloned()
+        .collect()
+}
+
+/// Combines two numeric metrics into a derived value for each day, such as 
+/// their difference, with `op`.
+///
+/// The value is `None` where either metric has no value for the day, or 
+/// where `op` returns `None` (such as on overflow, for checked arithmetic).
+pub fn combine<F>(data: &Data, a: MetricKind, b: MetricKind, op: F) -> Vec<(Date, Option<i32>)>
+where
+    F: Fn(i32, i32) -> Option<i32>,
+{
+    data.iter()
+        .filter_map(|day| {
+            let date = date_of(day)?;
+            let a = find(day, &a).and_then(Metric::as_i32);
+            let b = find(day, &b).and_then(Metric::as_i32);
+            Some((date, a.zip(b).and_then(|(a, b)| op(a, b))))
+        })
+        .collect()
+}
+
+/// Returns `a - b` for each day, as with `combine`.
+pub fn derived_diff(data: &Data, a: MetricKind, b: MetricKind) -> Vec<(Date, Option<i32>)> {
+    combine(data, a, b, i32::checked_sub)
+}
+
+/// Returns the share of each metric in the day's total across the metrics, 
+/// for each day, in the order the metrics are given (as for a stacked area 
+/// chart).
+///
+/// The total is of the metrics with a value for the day; a metric's share is 
+/// `None` where it has no value, or the total is zero.
+pub fn shares(data: &Data, metrics: &[MetricKind]) -> Vec<(Date, Vec<Option<f64>>)> {
+    data.iter()
+        .filter_map(|day| {
+            let date = date_of(day)?;
+            let values: Vec<Option<f64>> = metrics
+                .iter()
+                .map(|metric| find(day, metric).and_then(Metric::as_f64))
+                .collect();
+            let total: f64 = values.iter().flatten().sum();
+            let shares = values
+                .into_iter()
+                .map(|v| v.filter(|_| total != 0.0).map(|v| v / total))
+                .collect();
+            Some((date, shares))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligned_columns() {
+        let data = vec![
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Int(3))],
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Null)],
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Int(1))],
+        ];
+
+        let (dates, values) = columns_i32(&data, MetricKind::NewCasesByPublishDate);
+        assert_eq!(dates.len(), values.len());
+        assert_eq!(dates, vec![
+            Date::try_from_ymd(2020, 10, 3).unwrap(),
+            Date::try_from_ymd(2020, 10, 2).unwrap(),
+            Date::try_from_ymd(2020, 10, 1).unwrap(),
+        ]);
+        assert_eq!(values, vec![Some(3), None, Some(1)]);
+    }
+
+    #[test]
+    fn recent_windows() {
+        let data = vec![
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Int(2))],
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Null)],
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Int(1))],
+        ];
+        let metric = MetricKind::NewCasesByPublishDate;
+
+        assert_eq!(recent_window_i32(&data, metric.clone(), 2, false), vec![None, Some(2)]);
+        assert_eq!(recent_window_i32(&data, metric.clone(), 2, true), vec![Some(2), None]);
+        assert_eq!(recent_window_i32(&data, metric.clone(), 5, false), vec![None, Some(2), Some(1), None, None]);
+        assert_eq!(recent_window_i32(&data, metric, 5, true), vec![None, None, Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn days_above_threshold() {
+        let day = |d, cases: Option<i32>| {
+            vec![
+                Metric::Date(Date::try_from_ymd(2020, 10, d).unwrap()),
+                Metric::NewCasesByPublishDate(MetricValue::from(cases)),
+            ]
+        };
+        let data = vec![day(4, Some(900)), day(3, None), day(2, Some(1500)), day(1, Some(1001))];
+
+        assert_eq!(
+            filter_by_threshold(&data, MetricKind::NewCasesByPublishDate, |cases| cases > 1000),
+            vec![day(2, Some(1500)), day(1, Some(1001))]
+        );
+    }
+
+    #[test]
+    fn long_format() {
+        let day = |d, cases: Option<i32>, rate: Option<f64>| {
+            vec![
+                Metric::AreaName(String::from("England")),
+                Metric::Date(Date::try_from_ymd(2020, 10, d).unwrap()),
+                Metric::NewCasesByPublishDate(MetricValue::from(cases)),
+                Metric::TransmissionRateMax(MetricValue::from(rate)),
+            ]
+        };
+        let data = vec![day(2, Some(12), Some(1.4)), day(1, None, Some(1.2))];
+        let row = |d, metric: &str, value| LongRow {
+            date: Some(Date::try_from_ymd(2020, 10, d).unwrap()),
+            area: Some(String::from("England")),
+            metric: metric.to_string(),
+            value,
+        };
+
+        assert_eq!(to_long(&data), vec![
+            row(2, "newCasesByPublishDate", MetricValue::Int(12)),
+            row(2, "transmissionRateMax", MetricValue::Float(1.4)),
+            row(1, "newCasesByPublishDate", MetricValue::Null),
+            row(1, "transmissionRateMax", MetricValue::Float(1.2)),
+        ]);
+    }
+
+    #[test]
+    fn uniform_values() {
+        let parsed = |raw: &str| parse_value(&json::parse(raw).unwrap());
+
+        assert_eq!(parsed("120"), MetricValue::Int(120));
+        assert_eq!(parsed("1.25"), MetricValue::Float(1.25));
+        assert_eq!(parsed(r#""High""#), MetricValue::Text(String::from("High")));
+        assert_eq!(parsed("null"), MetricValue::Null);
+        assert!(matches!(parsed("[1, 2]"), MetricValue::Json(_)));
+
+        assert_eq!(parsed("120").as_f64(), Some(120.0));
+        assert_eq!(parsed("2.0").as_i64(), Some(2));
+        assert_eq!(parsed(r#"" 42""#).as_i64(), Some(42));
+        assert!(parsed("null").is_null());
+
+        // A value of the wrong type reads as no value, rather than panicking.
+        assert_eq!(parsed("1.25").as_i64(), None);
+        assert_eq!(parsed(r#""High""#).as_f64(), None);
+        assert_eq!(parsed("null").as_i64(), None);
+
+        assert_eq!(Metric::NewCasesByPublishDate(MetricValue::Int(7)).value(), MetricValue::Int(7));
+        assert_eq!(Metric::TransmissionRateMax(MetricValue::Float(1.1)).value(), MetricValue::Float(1.1));
+        assert_eq!(Metric::NewCasesByPublishDate(MetricValue::Null).value(), MetricValue::Null);
+        assert_eq!(Metric::Generic(String::from("x"), 3.into()).value().as_i64(), Some(3));
+    }
+
+    #[test]
+    fn earliest_and_latest_dates() {
+        let data = vec![
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap())],
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap())],
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap())],
+        ];
+        assert_eq!(
+            date_range(&data),
+            Some((Date::try_from_ymd(2020, 10, 1).unwrap(), Date::try_from_ymd(2020, 10, 3).unwrap()))
+        );
+
+        assert_eq!(date_range(&vec![]), None);
+        assert_eq!(date_range(&vec![vec![Metric::NewCasesByPublishDate(MetricValue::Int(1))]]), None);
+    }
+
+    #[test]
+    fn positivity_rates() {
+        let day = |d, cases: Option<i32>, tests: Option<i32>| {
+            vec![
+                Metric::Date(Date::try_from_ymd(2020, 10, d).unwrap()),
+                Metric::NewCasesByPublishDate(MetricValue::from(cases)),
+                Metric::NewTestsByPublishDate(MetricValue::from(tests)),
+            ]
+        };
+        let data = vec![day(3, Some(50), Some(1000)), day(2, Some(5), Some(0)), day(1, None, Some(800))];
+
+        assert_eq!(
+            positivity(&data, MetricKind::NewCasesByPublishDate, MetricKind::NewTestsByPublishDate),
+            vec![
+                (Date::try_from_ymd(2020, 10, 3).unwrap(), Some(0.05)),
+                (Date::try_from_ymd(2020, 10, 2).unwrap(), None),
+                (Date::try_from_ymd(2020, 10, 1).unwrap(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn latest_value_skips_null() {
+        let data = vec![
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Null)],
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Int(2))],
+            vec![Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Int(1))],
+        ];
+
+        assert_eq!(
+            latest_non_null_i32(&data, MetricKind::NewCasesByPublishDate),
+            Some((Date::try_from_ymd(2020, 10, 2).unwrap(), 2))
+        );
+        assert_eq!(latest_non_null_i32(&data, MetricKind::NewAdmissions), None);
+    }
+
+    #[test]
+    fn differences() {
+        let day = |d, hospital: Option<i32>, ventilated: Option<i32>| {
+            vec![
+                Metric::Date(Date::try_from_ymd(2020, 10, d).unwrap()),
+                Metric::HospitalCases(MetricValue::from(hospital)),
+                Metric::CovidOccupiedMechanicalVentilatorBeds(MetricValue::from(ventilated)),
+            ]
+        };
+        let data = vec![day(3, Some(500), Some(60)), day(2, None, Some(55)), day(1, Some(450), None)];
+
+        assert_eq!(
+            derived_diff(&data, MetricKind::HospitalCases, MetricKind::CovidOccupiedMechanicalVentilatorBeds),
+            vec![
+                (Date::try_from_ymd(2020, 10, 3).unwrap(), Some(440)),
+                (Date::try_from_ymd(2020, 10, 2).unwrap(), None),
+                (Date::try_from_ymd(2020, 10, 1).unwrap(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn shares_of_total() {
+        let metrics = [
+            MetricKind::NewCasesPCROnlyBySpecimenDate,
+            MetricKind::NewCasesLFDConfirmedPCRBySpecimenDate,
+            MetricKind::NewCasesLFDOnlyBySpecimenDate,
+        ];
+        let day = |d, pcr: Option<i32>, confirmed: Option<i32>, lfd: Option<i32>| {
+            vec![
+                Metric::Date(Date::try_from_ymd(2021, 3, d).unwrap()),
+                Metric::NewCasesPCROnlyBySpecimenDate(MetricValue::from(pcr)),
+                Metric::NewCasesLFDConfirmedPCRBySpecimenDate(MetricValue::from(confirmed)),
+                Metric::NewCasesLFDOnlyBySpecimenDate(MetricValue::from(lfd)),
+            ]
+        };
+        let data = vec![
+            day(3, Some(600), Some(300), Some(100)),
+            day(2, Some(70), None, Some(30)),
+            day(1, Some(0), Some(0), Some(0)),
+        ];
+
+        let result = shares(&data, &metrics);
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0], (Date::try_from_ymd(2021, 3, 3).unwrap(), vec![Some(0.6), Some(0.3), Some(0.1)]));
+        let total: f64 = result[0].1.iter().flatten().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert_eq!(result[1].1, vec![Some(0.7), None, Some(0.3)]);
+        assert_eq!(result[2].1, vec![None, None, None]);
+    }
+
+    #[test]
+    fn grouped_by_area() {
+        let day = |name: &str, cases| vec![Metric::AreaName(name.to_string()), Metric::NewCasesByPublishDate(MetricValue::from(cases))];
+        let data = vec![day("Hartlepool", 5), day("Middlesbrough", 7), day("Hartlepool", 4), vec![]];
+
+        let areas = group_by_area(&data);
+        assert_eq!(areas.keys().collect::<Vec<_>>(), vec!["Hartlepool", "Middlesbrough"]);
+        assert_eq!(areas["Hartlepool"], vec![day("Hartlepool", 5), day("Hartlepool", 4)]);
+    }
+}
@@ -0,0 +1,697 @@
+//! Helpers for exporting `Data` as CSV or JSON.
+//!
+//! Each Datum is exported as one row (or object), using the API names of its 
+//! Metrics for the column headers (or keys); numeric Metrics with no value 
+//! for the day are exported as empty cells (or `null`).
+
+use time::Date;
+
+use crate::{AgeBand, Data, Datum, Error, Metric, MetricKind, MetricValue};
+#[cfg(any(feature = "serde", feature = "parquet"))]
+use crate::ValueType;
+#[cfg(feature = "serde")]
+use crate::{FilterValue, Request};
+
+/// How Date Metrics are rendered when exporting.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum DateFormat {
+    /// ISO-8601 calendar dates, such as `2020-10-03`.
+    #[default]
+    Iso8601,
+    /// The number of days since 1970-01-01.
+    EpochDays,
+    /// A custom format string, such as `%d/%m/%Y`, using the specifiers 
+    /// supported by `time::Date::format`. Only date specifiers are valid.
+    Custom(String),
+}
+impl DateFormat {
+    /// Checks that a custom format string only uses valid date specifiers.
+    fn validate(&self) -> Result<(), Error> {
+        let format = match self {
+            DateFormat::Custom(f) => f,
+            _ => return Ok(()),
+        };
+
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                continue;
+            }
+            let mut specifier = chars.next();
+            if let Some('-') | Some('_') | Some('0') = specifier {
+                specifier = chars.next();
+            }
+            match specifier {
+                Some('a') | Some('A') | Some('b') | Some('B') | Some('C') | Some('d')
+                | Some('D') | Some('F') | Some('g') | Some('G') | Some('j') | Some('m')
+                | Some('u') | Some('U') | Some('V') | Some('w') | Some('W') | Some('y')
+                | Some('Y') | Some('%') => {}
+                _ => return Err(Error::InvalidDateFormat(format.to_string())),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render(&self, date: Date) -> String {
+        match self {
+            DateFormat::Iso8601 => date.format("%F"),
+            DateFormat::EpochDays => epoch_days(date).to_string(),
+            DateFormat::Custom(f) => date.format(f),
+        }
+    }
+}
+
+fn epoch_days(date: Date) -> i64 {
+    (date - Date::try_from_ymd(1970, 1, 1).unwrap()).whole_days()
+}
+
+/// Renders the value of a Metric as text, or `None` if it has no value.
+pub(crate) fn metric_text(metric: &Metric, date_format: &DateFormat) -> Option<String> {
+    match metric {
+        Metric::AreaType(t) => Some(t.name().to_string()),
+        Metric::AreaName(s) | Metric::AreaCode(s) | Metric::Hash(s) => Some(s.to_string()),
+        Metric::Date(d) => Some(date_format.render(*d)),
+        Metric::Week(w) => Some(w.to_string()),
+        Metric::MaleCases(bands)
+        | Metric::FemaleCases(bands)
+        | Metric::HospitalCasesByAge(bands)
+        | Metric::CovidOccupiedMechanicalVentilatorBedsByAge(bands) => Some(age_bands_json(bands).dump()),
+        m => match m.wrapped()? {
+            MetricValue::Null => None,
+            MetricValue::Int(v) => Some(v.to_string()),
+            MetricValue::Float(v) => Some(v.to_string()),
+            MetricValue::Text(s) => Some(s.clone()),
+            MetricValue::Json(v) => Some(v.dump()),
+        },
+    }
+}
+
+/// Exports age bands as a JSON array of objects, in the form returned by the 
+/// API; in CSV, this is written into a single cell.
+pub(crate) fn age_bands_json(bands: &[AgeBand]) -> json::JsonValue {
+    json::JsonValue::Array(
+        bands
+            .iter()
+            .map(|band| {
+                json::object! {
+                    "age" => band.age.as_str(),
+                    "value" => band.value,
+                    "rate" => band.rate,
+                }
+            })
+            .collect(),
+    )
+}
+
+fn csv_cell(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// The order of the columns of a CSV export.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ColumnOrder {
+    /// The order of the Metrics in each Datum, which is the order they were 
+    /// added to the request.
+    #[default]
+    Request,
+    /// The order of the dashboard's downloads: `areaCode`, `areaName`, 
+    /// `areaType`, and `date` first (where present), followed by the other 
+    /// Metrics in request order, for compatibility with tooling built 
+    /// around them.
+    Dashboard,
+}
+impl ColumnOrder {
+    /// Returns the Datum's Metrics in this order.
+    fn arrange<'a>(&self, day: &'a Datum) -> Vec<&'a Metric> {
+        let mut metrics: Vec<&Metric> = day.iter().collect();
+        if *self == ColumnOrder::Dashboard {
+            let leading = [MetricKind::AreaCode, MetricKind::AreaName, MetricKind::AreaType, MetricKind::Date];
+            metrics.sort_by_key(|m| {
+                let kind = m.kind();
+                leading.iter().position(|k| *k == kind).unwrap_or(leading.len())
+            });
+        }
+        metrics
+    }
+}
+
+/// Exports the Data as CSV, with a header row naming each Metric.
+///
+/// ## Errors
+///
+/// Returns `Error::InvalidDateFormat` if a custom DateFormat is invalid.
+pub fn to_csv(data: &Data, date_format: &DateFormat) -> Result<String, Error> {
+    to_csv_ordered(data, date_format, &ColumnOrder::Request)
+}
+
+/// Exports the Data as CSV as with `to_csv`, with the columns in the 
+/// provided order.
+///
+/// ## Errors
+///
+/// Returns `Error::InvalidDateFormat` if a custom DateFormat is invalid.
+pub fn to_csv_ordered(data: &Data, date_format: &DateFormat, order: &ColumnOrder) -> Result<String, Error> {
+    date_format.validate()?;
+
+    let mut csv = String::new();
+    if let Some(first) = data.first() {
+        csv.push_str(&csv_header(order.arrange(first).iter().map(|m| m.kind().name().to_string())));
+    }
+
+    for day in data {
+        let day: Datum = order.arrange(day).into_iter().cloned().collect();
+        csv.push_str(&csv_row(&day, date_format));
+    }
+
+    Ok(csv)
+}
+
+/// Returns the CSV header row with the provided column names.
+pub(crate) fn csv_header<I: IntoIterator<Item = String>>(names: I) -> String {
+    let header: Vec<String> = names.into_iter().map(|n| csv_cell(&n)).collect();
+    format!("{}\n", header.join(","))
+}
+
+/// Returns the CSV row for the day.
+pub(crate) fn csv_row(day: &Datum, date_format: &DateFormat) -> String {
+    let row: Vec<String> = day
+        .iter()
+        .map(|m| csv_cell(&metric_text(m, date_format).unwrap_or_default()))
+        .collect();
+    format!("{}\n", row.join(","))
+}
+
+/// Exports the Data as a JSON array, with one object per day keyed by the 
+/// name of each Metric.
+///
+/// Dates rendered as `DateFormat::EpochDays` are exported as numbers; all 
+/// other Dates are exported as strings.
+///
+/// ## Errors
+///
+/// Returns `Error::InvalidDateFormat` if a custom DateFormat is invalid.
+pub fn to_json(data: &Data, date_format: &DateFormat) -> Result<String, Error> {
+    date_format.validate()?;
+
+    let mut days = json::JsonValue::new_array();
+    for day in data {
+        let mut object = json::JsonValue::new_object();
+        for metric in day {
+            let value = match metric {
+                Metric::Date(d) if *date_format == DateFormat::EpochDays => epoch_days(*d).into(),
+                Metric::MaleCases(bands)
+                | Metric::FemaleCases(bands)
+                | Metric::HospitalCasesByAge(bands)
+                | Metric::CovidOccupiedMechanicalVentilatorBedsByAge(bands) => age_bands_json(bands),
+                m => match m.wrapped() {
+                    Some(v) => v.to_json(),
+                    None => metric_text(m, date_format).map_or(json::JsonValue::Null, json::JsonValue::from),
+                },
+            };
+            object[metric.kind().name()] = value;
+        }
+        days.push(object).unwrap();
+    }
+
+    Ok(days.dump())
+}
+
+/// Exports the Data in the columnar shape expected by charting frontends, as 
+/// `{"dates": [...], "series": {"metricName": [...]}}`, with a date and a 
+/// value of each metric for each day, in the order of the Data. Requires the 
+/// `serde` feature.
+///
+/// Dates are ISO-8601 strings. Values are `null` where the API reported no 
+/// value for the day, or the day doesn't include the metric.
+#[cfg(feature = "serde")]
+pub fn to_chart_json(data: &Data) -> serde_json::Value {
+    use crate::data::date_of;
+    use serde_json::Value;
+
+    let dates: Vec<Value> = data
+        .iter()
+        .map(|day| date_of(day).map_or(Value::Null, |d| Value::String(d.format("%F"))))
+        .collect();
+
+    let mut series = serde_json::Map::new();
+    for (i, day) in data.iter().enumerate() {
+        for metric in day.iter().filter(|m| !matches!(m, Metric::Date(_))) {
+            let values = series
+                .entry(metric.kind().name())
+                .or_insert_with(|| Value::Array(vec![Value::Null; data.len()]));
+            values[i] = value_json(metric);
+        }
+    }
+
+    serde_json::json!({ "dates": dates, "series": series })
+}
+
+/// Exports a single day as a JSON object keyed by the name of each Metric, 
+/// such as for logging a day compactly; the inverse of parsing a day from a 
+/// response with the named structure. Requires the `serde` feature.
+///
+/// Dates are ISO-8601 strings, and metrics with no value are `null`.
+#[cfg(feature = "serde")]
+pub fn datum_to_json(datum: &Datum) -> serde_json::Value {
+    serde_json::Value::Object(datum.iter().map(|m| (m.kind().name().to_string(), value_json(m))).collect())
+}
+
+/// Exports a request together with the Data it returned as a single JSON 
+/// object, for sharing an analysis reproducibly: the request's filters (as 
+/// sent to the API) and metric names, the days as exported by 
+/// `datum_to_json`, and the time of the export, in UTC. Requires the `serde` 
+/// feature.
+///
+/// A generic metric declared with a value type is exported as an object 
+/// naming the type as well, such as `{"name": "growthRate", "valueType": 
+/// "float"}`, so that it is read as the same type when imported.
+///
+/// Only the request's filters and metrics are exported; its other settings 
+/// (such as its Transport) are not.
+#[cfg(feature = "serde")]
+pub fn export_bundle(request: &Request, data: &Data) -> serde_json::Value {
+    serde_json::json!({
+        "filters": request.filters.iter().map(|f| f.to_string()).collect::<Vec<_>>(),
+        "metrics": request.config.metrics.iter().map(metric_json).collect::<Vec<_>>(),
+        "data": data.iter().map(datum_to_json).collect::<Vec<_>>(),
+        "exported": time::OffsetDateTime::now_utc().format("%FT%TZ"),
+    })
+}
+
+/// Reconstructs the request and Data from a bundle exported with 
+/// `export_bundle`. Requires the `serde` feature.
+///
+/// Each day of the Data has each of the request's metrics, in the order of 
+/// the request, as it would if returned by `Request::get`.
+///
+/// ## Errors
+///
+/// Returns `Error::ParseError` if the bundle isn't in the form exported by 
+/// `export_bundle`, or an error from parsing its filters (such as 
+/// `Error::UnknownName` for an unknown filter) or days.
+#[cfg(feature = "serde")]
+pub fn import_bundle(bundle: &serde_json::Value) -> Result<(Request, Data), Error> {
+    let malformed = |what: &str| Error::ParseError(format!("Bundle has no {}", what));
+    let filters = bundle["filters"]
+        .as_array()
+        .ok_or_else(|| malformed("filters"))?
+        .iter()
+        .map(|f| f.as_str().ok_or_else(|| malformed("filters"))?.parse())
+        .collect::<Result<Vec<crate::Filter>, Error>>()?;
+    let metrics = bundle["metrics"]
+        .as_array()
+        .ok_or_else(|| malformed("metrics"))?
+        .iter()
+        .map(|m| match m {
+            serde_json::Value::String(name) => Ok(name.parse().unwrap()),
+            _ => Ok(MetricKind::Generic {
+                name: m["name"].as_str().ok_or_else(|| malformed("metrics"))?.to_string(),
+                value_type: m["valueType"].as_str().ok_or_else(|| malformed("metrics"))?.parse()?,
+            }),
+        })
+        .collect::<Result<Vec<MetricKind>, Error>>()?;
+    let area_type = filters
+        .iter()
+        .find_map(|f| match &f.value {
+            FilterValue::AreaType(t) => Some(t.clone()),
+            _ => None,
+        })
+        .ok_or_else(|| malformed("areaType filter"))?;
+
+    let mut request = Request::with_metrics(area_type, &metrics);
+    for filter in filters.into_iter().filter(|f| !matches!(f.value, FilterValue::AreaType(_))) {
+        request.add_filter(filter);
+    }
+
+    // The days are keyed by metric name, as in a response with the named 
+    // structure, so are parsed as one.
+    let mut parser = request.clone();
+    parser.set_named_structure(true);
+    let days = json::parse(&bundle["data"].to_string()).map_err(|e| Error::ParseError(e.to_string()))?;
+    if !days.is_array() {
+        return Err(malformed("data"));
+    }
+    let data = parser.parse_data(&json::object! { "data" => days })?;
+
+    Ok((request, data))
+}
+
+/// Exports a request's metric for a bundle: by its name, or for a generic 
+/// metric with a declared type, as an object with its name and type.
+#[cfg(feature = "serde")]
+fn metric_json(kind: &MetricKind) -> serde_json::Value {
+    match kind {
+        MetricKind::Generic { name, value_type } if *value_type != ValueType::Json => {
+            serde_json::json!({ "name": name, "valueType": value_type.name() })
+        }
+        _ => kind.name().into(),
+    }
+}
+
+#[cfg(feature = "serde")]
+fn value_json(metric: &Metric) -> serde_json::Value {
+    use crate::data::MetricValue;
+    use serde_json::Value;
+
+    match MetricValue::from(metric) {
+        MetricValue::Null => Value::Null,
+        MetricValue::Int(v) => v.into(),
+        MetricValue::Float(v) => serde_json::Number::from_f64(v).map_or(Value::Null, Value::Number),
+        MetricValue::Text(s) => Value::String(s),
+        MetricValue::Json(v) => serde_json::from_str(&v.dump()).unwrap_or(Value::Null),
+    }
+}
+
+/// The Parquet column type for a metric.
+#[cfg(feature = "parquet")]
+enum ColumnType {
+    Date,
+    Int32,
+    Int64,
+    Float64,
+    Text,
+}
+#[cfg(feature = "parquet")]
+impl ColumnType {
+    fn of(kind: &MetricKind) -> ColumnType {
+        match kind {
+            MetricKind::Date => ColumnType::Date,
+            MetricKind::Generic { value_type: ValueType::Int, .. } => ColumnType::Int64,
+            MetricKind::Generic { value_type: ValueType::Float, .. } => ColumnType::Float64,
+            MetricKind::CumulativePillarOneTestsByPublishDate
+            | MetricKind::CumulativePillarTwoTestsByPublishDate
+            | MetricKind::CumulativePillarThreeTestsByPublishDate
+            | MetricKind::CumulativePillarFourTestsByPublishDate
+            | MetricKind::CumulativeTestsByPublishDate => ColumnType::Int64,
+            MetricKind::TransmissionRateMax
+            | MetricKind::TransmissionRateMin
+            | MetricKind::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage
+            | MetricKind::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage
+            | MetricKind::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage
+            | MetricKind::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage => ColumnType::Float64,
+            MetricKind::AlertLevelName
+            | MetricKind::AreaCode
+            | MetricKind::AreaName
+            | MetricKind::AreaType
+            | MetricKind::CovidOccupiedMechanicalVentilatorBedsByAge
+            | MetricKind::FemaleCases
+            | MetricKind::Generic { .. }
+            | MetricKind::Hash
+            | MetricKind::HospitalCasesByAge
+            | MetricKind::MaleCases
+            | MetricKind::Week => ColumnType::Text,
+            _ => ColumnType::Int32,
+        }
+    }
+}
+
+/// Exports the Data as Parquet, with a column for each Metric. Requires the 
+/// `parquet` feature.
+///
+/// As with `to_csv`, the columns are those of the first day. Dates are 
+/// exported as Parquet dates, numeric Metrics as integer or float columns, 
+/// and all other Metrics as text, as they would be written to CSV; days with 
+/// no value for a Metric have a null.
+///
+/// ## Errors
+///
+/// Returns `Error::ExportError` if the Parquet file could not be written.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: std::io::Write + Send>(data: &Data, w: &mut W) -> Result<(), Error> {
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, Date32Array, Float64Array, Int32Array, Int64Array, RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+
+    use crate::data::find;
+
+    // A Generic metric's type is that of its value, so is taken from the 
+    // first day with a typed value, rather than a null.
+    let typed = |kind: MetricKind| match kind {
+        MetricKind::Generic { value_type: ValueType::Json, .. } => data
+            .iter()
+            .filter_map(|day| find(day, &kind))
+            .map(Metric::kind)
+            .find(|k| !matches!(k, MetricKind::Generic { value_type: ValueType::Json, .. }))
+            .unwrap_or(kind),
+        kind => kind,
+    };
+    let kinds: Vec<MetricKind> = data.first().map(|day| day.iter().map(Metric::kind).map(typed).collect()).unwrap_or_default();
+
+    let mut fields = vec![];
+    let mut columns: Vec<ArrayRef> = vec![];
+    for kind in &kinds {
+        let values = || data.iter().map(move |day| find(day, kind));
+        let (data_type, column): (DataType, ArrayRef) = match ColumnType::of(kind) {
+            ColumnType::Date => (
+                DataType::Date32,
+                Arc::new(
+                    values()
+                        .map(|m| match m {
+                            Some(Metric::Date(d)) => Some(epoch_days(*d) as i32),
+                            _ => None,
+                        })
+                        .collect::<Date32Array>(),
+                ),
+            ),
+            ColumnType::Int32 => (
+                DataType::Int32,
+                Arc::new(values().map(|m| m.and_then(Metric::as_i32)).collect::<Int32Array>()),
+            ),
+            ColumnType::Int64 => (
+                DataType::Int64,
+                Arc::new(values().map(|m| m.and_then(Metric::as_i64)).collect::<Int64Array>()),
+            ),
+            ColumnType::Float64 => (
+                DataType::Float64,
+                Arc::new(values().map(|m| m.and_then(Metric::as_f64)).collect::<Float64Array>()),
+            ),
+            ColumnType::Text => (
+                DataType::Utf8,
+                Arc::new(
+                    values()
+                        .map(|m| m.and_then(|m| metric_text(m, &DateFormat::Iso8601)))
+                        .collect::<StringArray>(),
+                ),
+            ),
+        };
+        fields.push(Field::new(kind.name(), data_type, true));
+        columns.push(column);
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    let export_err = |e: &dyn std::fmt::Display| Error::ExportError(e.to_string());
+    let mut writer = parquet::arrow::ArrowWriter::try_new(w, schema.clone(), None).map_err(|e| export_err(&e))?;
+    if !columns.is_empty() {
+        let batch = RecordBatch::try_new(schema, columns).map_err(|e| export_err(&e))?;
+        writer.write(&batch).map_err(|e| export_err(&e))?;
+    }
+    writer.close().map_err(|e| export_err(&e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> Data {
+        vec![
+            vec![
+                Metric::AreaName(String::from("Bristol, City of")),
+                Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap()),
+                Metric::NewCasesByPublishDate(MetricValue::Int(12)),
+            ],
+            vec![
+                Metric::AreaName(String::from("Bristol, City of")),
+                Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap()),
+                Metric::NewCasesByPublishDate(MetricValue::Null),
+            ],
+        ]
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn chart_json() {
+        let mut data = data();
+        data[1].pop();
+
+        assert_eq!(
+            to_chart_json(&data),
+            serde_json::json!({
+                "dates": ["2020-10-03", "2020-10-02"],
+                "series": {
+                    "areaName": ["Bristol, City of", "Bristol, City of"],
+                    "newCasesByPublishDate": [12, null],
+                },
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn datum_json() {
+        let day = vec![
+            Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap()),
+            Metric::NewCasesByPublishDate(MetricValue::Int(12)),
+            Metric::TransmissionRateMax(MetricValue::Float(1.25)),
+            Metric::HospitalCases(MetricValue::Null),
+        ];
+
+        assert_eq!(
+            datum_to_json(&day),
+            serde_json::json!({
+                "date": "2020-10-03",
+                "newCasesByPublishDate": 12,
+                "transmissionRateMax": 1.25,
+                "hospitalCases": null,
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn bundle_round_trip() {
+        let mut req = Request::with_metrics(
+            crate::AreaType::Nation,
+            &[MetricKind::Date, MetricKind::NewCasesByPublishDate, MetricKind::TransmissionRateMax],
+        );
+        req.add_generic_metric("growthRate", ValueType::Float);
+        req.add_filter(crate::Filter::new(FilterValue::AreaName(String::from("england"))));
+        req.add_filter(crate::Filter::new(FilterValue::Date(Date::try_from_ymd(2020, 10, 3).unwrap())));
+        let data = vec![vec![
+            Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap()),
+            Metric::NewCasesByPublishDate(MetricValue::Int(12872)),
+            Metric::TransmissionRateMax(MetricValue::Null),
+            Metric::Generic(String::from("growthRate"), 0.25.into()),
+        ]];
+
+        let bundle = export_bundle(&req, &data);
+        assert_eq!(bundle["filters"], serde_json::json!(["areaType=nation", "areaName=england", "date=2020-10-03"]));
+        assert_eq!(bundle["metrics"][3], serde_json::json!({ "name": "growthRate", "valueType": "float" }));
+        assert!(bundle["exported"].is_string());
+
+        let (imported, imported_data) = import_bundle(&bundle).unwrap();
+        assert_eq!(imported.url(), req.url());
+        assert_eq!(imported_data, data);
+        assert!(matches!(
+            &imported.config.metrics[3],
+            MetricKind::Generic { value_type: ValueType::Float, .. }
+        ));
+
+        match import_bundle(&serde_json::json!({ "metrics": [] })) {
+            Err(Error::ParseError(_)) => {}
+            r => panic!("expected ParseError, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn dashboard_column_order() {
+        let data = vec![vec![
+            Metric::NewCasesByPublishDate(MetricValue::Int(12)),
+            Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap()),
+            Metric::CumulativeCasesByPublishDate(MetricValue::Int(400)),
+            Metric::AreaName(String::from("Bristol, City of")),
+            Metric::AreaCode(String::from("E06000023")),
+        ]];
+
+        assert_eq!(
+            to_csv_ordered(&data, &DateFormat::default(), &ColumnOrder::Dashboard).unwrap(),
+            "areaCode,areaName,date,newCasesByPublishDate,cumCasesByPublishDate\n\
+             E06000023,\"Bristol, City of\",2020-10-03,12,400\n"
+        );
+        assert!(to_csv_ordered(&data, &DateFormat::default(), &ColumnOrder::Request)
+            .unwrap()
+            .starts_with("newCasesByPublishDate,date,"));
+    }
+
+    #[test]
+    fn date_formats() {
+        assert_eq!(
+            to_csv(&data(), &DateFormat::default()).unwrap(),
+            "areaName,date,newCasesByPublishDate\n\
+             \"Bristol, City of\",2020-10-03,12\n\
+             \"Bristol, City of\",2020-10-02,\n"
+        );
+        assert_eq!(
+            to_csv(&data(), &DateFormat::Custom(String::from("%d/%m/%Y"))).unwrap(),
+            "areaName,date,newCasesByPublishDate\n\
+             \"Bristol, City of\",03/10/2020,12\n\
+             \"Bristol, City of\",02/10/2020,\n"
+        );
+
+        assert_eq!(
+            to_json(&data(), &DateFormat::default()).unwrap(),
+            r#"[{"areaName":"Bristol, City of","date":"2020-10-03","newCasesByPublishDate":12},{"areaName":"Bristol, City of","date":"2020-10-02","newCasesByPublishDate":null}]"#
+        );
+        assert_eq!(
+            to_json(&data(), &DateFormat::EpochDays).unwrap(),
+            r#"[{"areaName":"Bristol, City of","date":18538,"newCasesByPublishDate":12},{"areaName":"Bristol, City of","date":18537,"newCasesByPublishDate":null}]"#
+        );
+    }
+
+    #[test]
+    fn invalid_date_format() {
+        match to_csv(&data(), &DateFormat::Custom(String::from("%d/%m/%Y %H:%M"))) {
+            Err(Error::InvalidDateFormat(f)) => assert_eq!(f, "%d/%m/%Y %H:%M"),
+            r => panic!("expected InvalidDateFormat, got {:?}", r),
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_round_trip() {
+        use arrow_array::{Array, Date32Array, Int32Array, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut buf = Vec::new();
+        write_parquet(&data(), &mut buf).unwrap();
+
+        let mut reader = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(buf))
+            .unwrap()
+            .build()
+            .unwrap();
+        let batch = reader.next().unwrap().unwrap();
+        let schema = batch.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, vec!["areaName", "date", "newCasesByPublishDate"]);
+
+        let names = batch.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(names.value(0), "Bristol, City of");
+        let dates = batch.column(1).as_any().downcast_ref::<Date32Array>().unwrap();
+        assert_eq!(dates.value(0), epoch_days(Date::try_from_ymd(2020, 10, 3).unwrap()) as i32);
+        let cases = batch.column(2).as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(cases.value(0), 12);
+        assert!(cases.is_null(1));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_generic_types() {
+        use arrow_array::{Array, Float64Array};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let data = vec![
+            vec![Metric::Generic(String::from("growthRate"), MetricValue::Null)],
+            vec![Metric::Generic(String::from("growthRate"), MetricValue::Float(0.25))],
+        ];
+        let mut buf = Vec::new();
+        write_parquet(&data, &mut buf).unwrap();
+
+        let batch = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(buf))
+            .unwrap()
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let rates = batch.column(0).as_any().downcast_ref::<Float64Array>().unwrap();
+        assert!(rates.is_null(0));
+        assert_eq!(rates.value(1), 0.25);
+    }
+}
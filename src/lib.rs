@@ -7,23 +7,57 @@
 //! ```
 //! use covid19_uk_rs;
 //!
-//! let mut req = covid19_uk_rs::Request::new(covid19_uk_rs::AreaType::Nation, covid19_uk_rs::Metric::CumulativeCasesByPublishDate(0));
+//! let mut req = covid19_uk_rs::Request::new(covid19_uk_rs::AreaType::Nation, covid19_uk_rs::Metric::CumulativeCasesByPublishDate(covid19_uk_rs::MetricValue::Null));
 //! req.add_filter(covid19_uk_rs::Filter::new(covid19_uk_rs::FilterValue::AreaName(String::from("england"))));
 //!
 //! for day in req.get().unwrap() {
 //!     match day.get(0) {
 //!         Some(i) => match i {
-//!             covid19_uk_rs::Metric::CumulativeCasesByPublishDate(i) => println!("{}", i),
+//!             covid19_uk_rs::Metric::CumulativeCasesByPublishDate(v) => println!("{:?}", v),
 //!             _ => {},
 //!         },
 //!         None => {},
 //!     }
 //! }
 //! ```
-use reqwest;
-use time::Date;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// The date type used by Metrics and Filters, re-exported from the `time` 
+/// crate so that it can be used without depending on `time` directly.
+pub use time::Date;
+
+mod areas;
+pub mod client;
+pub mod data;
+pub mod export;
+#[cfg(feature = "record")]
+pub mod record;
+pub mod series;
+pub mod transport;
+mod week;
+
+pub use areas::{AreaCode, Nation};
+pub use client::Client;
+pub use data::{parse_value, MetricValue};
+pub use week::Week;
+
+use transport::{HttpTransport, Transport};
 
 const API_URL: &str = "https://api.coronavirus.data.gov.uk/v1/data";
+/// The dashboard's metric discovery endpoint, listing the metrics reported 
+/// for an area type.
+const METRICS_URL: &str = "https://api.coronavirus.data.gov.uk/generic/metric_availability";
+/// The dashboard's pre-generated downloads of whole datasets.
+const DOWNLOADS_URL: &str = "https://coronavirus.data.gov.uk/downloads/csv";
+/// The largest number of days the API will return in a single page.
+const MAX_PAGE_SIZE: u32 = 2500;
+/// The most metrics the API accepts in a single request's structure.
+const MAX_STRUCTURE_METRICS: usize = 5;
+/// The most dates for which `Request::get_on_dates` sends a request per date 
+/// rather than fetching the whole series.
+const MAX_PER_DATE_REQUESTS: usize = 4;
 
 #[derive(Debug)]
 pub enum Error {
@@ -33,14 +67,103 @@ pub enum Error {
     /// Returned if no data was returned by the API for the request (status 
     /// code 204).
     NoData,
-    /// Returned if the API is rate-limiting your client (status code 429).
-    TooManyRequests,
-    /// Returned if the API responded with status code 500 (Internal Server 
-    /// Error).
+    /// Returned if the API is rate-limiting your client (status code 429), 
+    /// with any hint the response body gave as to when the limit resets.
+    TooManyRequests(Option<String>),
+    /// Returned if the API responded with a server error status code (500 
+    /// or above, such as 500 Internal Server Error or 503 Service 
+    /// Unavailable).
     APIServerError,
+    /// Returned by `AreaCode::new` if the provided code is not in the form of 
+    /// an ONS area code.
+    InvalidAreaCode(String),
+    /// Returned by `Week::new` or when parsing a Week if the week doesn't 
+    /// exist, or isn't in the form `2021-W03`.
+    InvalidWeek(String),
+    /// Returned before sending a request whose AreaName Filter does not name
+    /// an area of the requested AreaType. Only returned when the `validate`
+    /// feature is enabled.
+    InvalidAreaForType {
+        area_type: AreaType,
+        area_name: String,
+    },
+    /// Returned when exporting Data with a custom DateFormat containing an 
+    /// invalid or non-date format specifier.
+    InvalidDateFormat(String),
+    /// Returned if the API responded with a body which could not be parsed,
+    /// such as an HTML error page served in place of the data. Includes the
+    /// start of the body.
+    ParseError(String),
+    /// Returned if the API responded in a form this library doesn't 
+    /// recognise, which likely means the API has changed in a way that is 
+    /// incompatible with this version of the library. Includes a description 
+    /// of what was unexpected.
+    IncompatibleApiVersion(String),
+    /// Returned if Data could not be exported, with a description of the 
+    /// failure.
+    ExportError(String),
+    /// Returned if writing fetched data out failed.
+    IoError(std::io::Error),
+    /// Returned if the response bodies of a request exceeded the limit set 
+    /// with `Request::set_max_bytes`.
+    ResponseTooLarge,
+    /// Returned when parsing an AreaType from a string which doesn't name 
+    /// one.
+    UnknownName(String),
+    /// Returned if the API rejected the request (e.g., because of an unknown 
+    /// metric name), or responded with any other unexpected status (such as a 
+    /// redirect from a gateway), with the HTTP status and the API's 
+    /// explanation.
+    ApiError { status: u16, message: String },
+    /// Returned if the API responded with status code 412 (Precondition 
+    /// Failed), as it may to a conditional request (such as one sent with an 
+    /// `If-Match` or `If-Unmodified-Since` header) whose condition no longer 
+    /// holds, because the data has changed since it was last fetched.
+    PreconditionFailed,
+    /// Returned if the API responded with status code 304 (Not Modified) to 
+    /// a conditional request (such as one sent with an `If-None-Match` 
+    /// default header through a `Client`) outside of `Request::poll`, which 
+    /// handles it itself.
+    NotModified,
+    /// Returned if the API refused the request for covering more records 
+    /// than it will serve, with the API's explanation. Narrow the request 
+    /// with more Filters (such as an AreaName or Date), fewer metrics, or 
+    /// fetch only the latest day with `get_latest_by_metric`.
+    QueryTooBroad(String),
+    /// Returned in place of `NoData` if the request's AreaName Filter may be 
+    /// misspelt, with the closest names of areas of the requested AreaType. 
+    /// Only returned if enabled with `Request::set_suggest_area_names`.
+    NoDataDidYouMean { tried: String, suggestions: Vec<String> },
+    /// Returned before sending a request in strict mode (see 
+    /// `Request::set_strict`) whose Date Filter is after today's date.
+    FutureDate(Date),
+    /// Returned before sending a request in strict mode (see 
+    /// `Request::set_strict`) whose Filters can't all match the same area, 
+    /// with a description of the conflict.
+    InconsistentFilters(String),
+    /// Returned if an HTTPS URL (such as the API's) was requested, but the 
+    /// crate was built with neither of the `native-tls` or `rustls` 
+    /// features, so has no TLS backend.
+    TlsUnavailable,
+    /// Returned by `Request::validate_all` for a request the API would 
+    /// reject (such as one with no metrics), with a description of the 
+    /// problem.
+    InvalidRequest(String),
+    /// Returned by `Client::get_async` when a fetch shared with other 
+    /// concurrent requests failed; wraps the error for the shared fetch.
+    Shared(Arc<Error>),
+    /// Returned by `Request::get` in place of another error if enabled with 
+    /// `Request::set_error_context`, wrapping the error with the area (its 
+    /// name or code if filtered by one, else its area type) and the names of 
+    /// the metrics of the request which failed.
+    WithContext {
+        area: String,
+        metrics: Vec<String>,
+        source: Box<Error>,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum AreaType {
     Overview,
     Nation,
@@ -49,18 +172,53 @@ pub enum AreaType {
     UTLA,
     LTLA,
 }
+impl AreaType {
+    /// Returns the name of the area type as used by the API.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AreaType::Overview => "overview",
+            AreaType::Nation => "nation",
+            AreaType::Region => "region",
+            AreaType::NHSRegion => "nhsRegion",
+            AreaType::UTLA => "utla",
+            AreaType::LTLA => "ltla",
+        }
+    }
+}
+
+impl std::str::FromStr for AreaType {
+    type Err = Error;
+
+    /// Parses an area type from its name as used by the API (e.g., `utla`).
+    fn from_str(s: &str) -> Result<AreaType, Error> {
+        [
+            AreaType::Overview,
+            AreaType::Nation,
+            AreaType::Region,
+            AreaType::NHSRegion,
+            AreaType::UTLA,
+            AreaType::LTLA,
+        ]
+        .iter()
+        .find(|t| t.name() == s)
+        .cloned()
+        .ok_or_else(|| Error::UnknownName(s.to_string()))
+    }
+}
 
 /// Valid filter types and their associated value for specific data requests
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FilterValue {
     AreaType(AreaType),
     /// AreaName's attached string must be lowercase.
     AreaName(String),
-    AreaCode(String),
+    AreaCode(AreaCode),
     Date(Date),
+    /// For metrics reported by ISO week.
+    Week(Week),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Filter {
     metric: String,
     value: FilterValue,
@@ -72,87 +230,870 @@ impl Filter {
             FilterValue::AreaName(_) => String::from("areaName"),
             FilterValue::AreaCode(_) => String::from("areaCode"),
             FilterValue::Date(_) => String::from("date"),
+            FilterValue::Week(_) => String::from("week"),
         };
 
         Filter { metric, value }
     }
 }
+impl std::fmt::Display for Filter {
+    /// Formats the Filter as the API expects it, as `metric=value` (e.g., 
+    /// `date=2021-01-15`).
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.value {
+            FilterValue::AreaType(t) => write!(f, "{}={}", self.metric, t.name()),
+            FilterValue::AreaName(n) => write!(f, "{}={}", self.metric, n),
+            FilterValue::AreaCode(c) => write!(f, "{}={}", self.metric, c),
+            FilterValue::Date(d) => write!(f, "{}={}", self.metric, d.format("%Y-%m-%d")),
+            FilterValue::Week(w) => write!(f, "{}={}", self.metric, w),
+        }
+    }
+}
+impl std::str::FromStr for Filter {
+    type Err = Error;
+
+    /// Parses a Filter from the form the API expects, as `metric=value` 
+    /// (e.g., `date=2021-01-15`).
+    fn from_str(s: &str) -> Result<Filter, Error> {
+        let value = match s.split_once('=') {
+            Some(("areaType", t)) => FilterValue::AreaType(t.parse()?),
+            Some(("areaName", n)) => FilterValue::AreaName(n.to_string()),
+            Some(("areaCode", c)) => FilterValue::AreaCode(AreaCode::new(c)?),
+            Some(("date", d)) => FilterValue::Date(
+                Date::parse(d, "%F").map_err(|e| Error::ParseError(format!("Invalid date in {}: {}", s, e)))?,
+            ),
+            Some(("week", w)) => FilterValue::Week(w.parse()?),
+            _ => return Err(Error::UnknownName(s.to_string())),
+        };
+        Ok(Filter::new(value))
+    }
+}
+
+/// A single age band of a demographic breakdown, such as `MaleCases`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgeBand {
+    /// The ages covered, as named by the API (e.g., `0_to_4` or `90+`).
+    pub age: String,
+    /// `None` where the API reported no value for the band.
+    pub value: Option<i32>,
+    /// The rate per 100,000 people in the band, if reported.
+    pub rate: Option<f64>,
+}
+impl AgeBand {
+    /// Parses an array of age bands, as returned by the API; anything else 
+    /// (such as `null`, on days with no breakdown) has no bands.
+    fn parse_all(value: &json::JsonValue) -> Vec<AgeBand> {
+        value
+            .members()
+            .map(|band| AgeBand {
+                age: band["age"].as_str().unwrap_or_default().to_string(),
+                value: band["value"].as_i32(),
+                rate: band["rate"].as_f64(),
+            })
+            .collect()
+    }
+}
 
 /// Valid metrics which may be requested from the NHS API.
 /// Any provided value will be ignored if providing a Metric for a 
 /// request; these values are *only* populated within responses.
-#[derive(Debug)]
+///
+/// Each value is a `MetricValue`, which is `MetricValue::Null` where the API 
+/// reported no value for that day. Counts are parsed as `MetricValue::Int` 
+/// and rates and percentages as `MetricValue::Float`; a value of another 
+/// type than the metric's (which would suggest the API has changed) is kept 
+/// as the type it was sent as, with a warning, rather than being discarded.
+/// 
+/// Cases split by test type (PCR or lateral flow) are only reported for 
+/// specimen dates since the dashboard began splitting them, in 2021; earlier 
+/// days have no value.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Metric {
     AreaType(AreaType),
     AreaName(String),
     AreaCode(String),
     Date(Date),
+    /// The ISO week of the day's data, for metrics reported weekly.
+    Week(Week),
     Hash(String),
-    NewCasesByPublishDate(i32),
-    CumulativeCasesByPublishDate(i32),
-    CumulativeCasesBySpecimenDateRange(i32),
-    NewCasesBySpecimenDate(i32),
-    MaleCases(i32),
-    FemaleCases(i32),
-    NewPillarOneTestsByPublishDate(i32),
-    CumulativePillarOneTestsByPublishDate(i32),
-    NewPillarTwoTestsByPublishDate(i32),
-    CumulativePillarTwoTestsByPublishDate(i32),
-    NewPillarThreeTestsByPublishDate(i32),
-    CumulativePillarThreeTestsByPublishDate(i32),
-    NewPillarFourTestsByPublishDate(i32),
-    CumulativePillarFourTestsByPublishDate(i32),
-    NewAdmissions(i32),
-    CumulativeAdmissions(i32),
-    CumulativeAdmissionsByAge(i32),
-    CumulativeTestsByPublishDate(i32),
-    NewTestsByPublishDate(i32),
-    CovidOccupiedMechanicalVentilatorBeds(i32),
-    HospitalCases(i32),
-    PlannedCapacityByPublishDate(i32),
-    NewDeathsWithin28DaysByPublishDate(i32),
-    CumulativeDeathsWithin28DaysByPublishDate(i32),
-}
-fn metric_to_str(metric: &Metric) -> &'static str {
-    match metric {
-        Metric::AreaCode(_) => "areaCode",
-        Metric::AreaName(_) => "areaName",
-        Metric::AreaType(_) => "areaType",
-        Metric::CovidOccupiedMechanicalVentilatorBeds(_) => "covidOccupiedMVBeds",
-        Metric::CumulativeAdmissions(_) => "cumAdmissions",
-        Metric::CumulativeAdmissionsByAge(_) => "cumAdmissionsByAge",
-        Metric::CumulativeCasesByPublishDate(_) => "cumCasesByPublishDate",
-        Metric::CumulativeCasesBySpecimenDateRange(_) => "cumCasesBySpecimenDateRange",
-        Metric::CumulativeDeathsWithin28DaysByPublishDate(_) => "cumDeaths28DaysByPublishDate",
-        Metric::CumulativePillarOneTestsByPublishDate(_) => "cumPillarOneTestsByPublishDate",
-        Metric::CumulativePillarTwoTestsByPublishDate(_) => "cumPillarTwoTestsByPublishDate",
-        Metric::CumulativePillarThreeTestsByPublishDate(_) => "cumPillarThreeTestsByPublishDate",
-        Metric::CumulativePillarFourTestsByPublishDate(_) => "cumPillarFourTestsByPublishDate",
-        Metric::CumulativeTestsByPublishDate(_) => "cumTestsByPublishDate",
-        Metric::Date(_) => "date",
-        Metric::FemaleCases(_) => "femaleCases",
-        Metric::Hash(_) => "hash",
-        Metric::HospitalCases(_) => "hospitalCases",
-        Metric::MaleCases(_) => "maleCases",
-        Metric::NewAdmissions(_) => "newAdmissions",
-        Metric::NewCasesByPublishDate(_) => "newCasesByPublishDate",
-        Metric::NewCasesBySpecimenDate(_) => "newCasesBySpecimenDate",
-        Metric::NewDeathsWithin28DaysByPublishDate(_) => "newDeaths28DaysByPublishDate",
-        Metric::NewPillarOneTestsByPublishDate(_) => "newPillarOneTestsByPublishDate",
-        Metric::NewPillarTwoTestsByPublishDate(_) => "newPillarTwoTestsByPublishDate",
-        Metric::NewPillarThreeTestsByPublishDate(_) => "newPillarThreeTestsByPublishDate",
-        Metric::NewPillarFourTestsByPublishDate(_) => "newPillarFourTestsByPublishDate",
-        Metric::NewTestsByPublishDate(_) => "newTestsByPublishDate",
-        Metric::PlannedCapacityByPublishDate(_) => "plannedCapacityByPublishDate",
+    NewCasesByPublishDate(MetricValue),
+    CumulativeCasesByPublishDate(MetricValue),
+    CumulativeCasesBySpecimenDateRange(MetricValue),
+    NewCasesBySpecimenDate(MetricValue),
+    /// Cases among males, by age band.
+    MaleCases(Vec<AgeBand>),
+    /// Cases among females, by age band.
+    FemaleCases(Vec<AgeBand>),
+    NewPillarOneTestsByPublishDate(MetricValue),
+    CumulativePillarOneTestsByPublishDate(MetricValue),
+    NewPillarTwoTestsByPublishDate(MetricValue),
+    CumulativePillarTwoTestsByPublishDate(MetricValue),
+    NewPillarThreeTestsByPublishDate(MetricValue),
+    CumulativePillarThreeTestsByPublishDate(MetricValue),
+    NewPillarFourTestsByPublishDate(MetricValue),
+    CumulativePillarFourTestsByPublishDate(MetricValue),
+    NewAdmissions(MetricValue),
+    CumulativeAdmissions(MetricValue),
+    CumulativeAdmissionsByAge(MetricValue),
+    CumulativeTestsByPublishDate(MetricValue),
+    NewTestsByPublishDate(MetricValue),
+    CovidOccupiedMechanicalVentilatorBeds(MetricValue),
+    /// Patients in mechanical ventilation beds with COVID-19, by age band.
+    CovidOccupiedMechanicalVentilatorBedsByAge(Vec<AgeBand>),
+    HospitalCases(MetricValue),
+    /// Patients in hospital with COVID-19, by age band.
+    HospitalCasesByAge(Vec<AgeBand>),
+    PlannedCapacityByPublishDate(MetricValue),
+    NewDeathsWithin28DaysByPublishDate(MetricValue),
+    CumulativeDeathsWithin28DaysByPublishDate(MetricValue),
+    /// Deaths within 28 days of a positive test, by the date of death rather 
+    /// than the date they were reported.
+    NewDeathsWithin28DaysByDeathDate(MetricValue),
+    CumulativeDeathsWithin28DaysByDeathDate(MetricValue),
+    /// Deaths within 60 days of a positive test, or with COVID-19 on the 
+    /// death certificate, by the date of death.
+    NewDeathsWithin60DaysByDeathDate(MetricValue),
+    CumulativeDeathsWithin60DaysByDeathDate(MetricValue),
+    /// New cases confirmed by PCR test alone.
+    NewCasesPCROnlyBySpecimenDate(MetricValue),
+    /// New cases first found by lateral flow test and confirmed by PCR.
+    NewCasesLFDConfirmedPCRBySpecimenDate(MetricValue),
+    /// New cases found by lateral flow test without a confirmatory PCR.
+    NewCasesLFDOnlyBySpecimenDate(MetricValue),
+    /// The local COVID alert level (tier) in force for the area.
+    AlertLevel(MetricValue),
+    /// The name of the alert level in force for the area (e.g., "High").
+    AlertLevelName(MetricValue),
+    /// The lower bound of the estimated reproduction number (R).
+    TransmissionRateMin(MetricValue),
+    /// The upper bound of the estimated reproduction number (R).
+    TransmissionRateMax(MetricValue),
+    /// The percentage of people aged 12 and over who have had a first dose 
+    /// of a vaccine.
+    CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage(MetricValue),
+    /// The percentage of people aged 12 and over who have had a second dose.
+    CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage(MetricValue),
+    /// The percentage of people aged 12 and over who have had a booster or 
+    /// third dose.
+    CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage(MetricValue),
+    /// The percentage of people aged 12 and over who have had a booster 
+    /// dose, as reported since the booster campaign.
+    CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage(MetricValue),
+    /// The number of people who had a booster or third dose on the day.
+    NewPeopleVaccinatedThirdInjectionByPublishDate(MetricValue),
+    /// The number of people who have had a booster or third dose.
+    CumulativePeopleVaccinatedThirdInjectionByPublishDate(MetricValue),
+    /// Any other metric, by its API name, for metrics not yet covered by 
+    /// this enum. The value is the JSON the API returned for it, read as the 
+    /// type declared in the metric's `MetricKind::Generic`.
+    Generic(String, MetricValue),
+}
+impl Metric {
+    /// Creates a Generic Metric with the provided API name, for requesting.
+    pub fn generic(name: &str) -> Metric {
+        Metric::Generic(name.to_string(), MetricValue::Null)
+    }
+
+    /// Returns the kind of this Metric, discarding its value.
+    /// 
+    /// The type of a Generic metric's kind is that of its value (integer, 
+    /// float, or text), or `ValueType::Json` for a null or structured value.
+    pub fn kind(&self) -> MetricKind {
+        match self {
+            Metric::AlertLevel(_) => MetricKind::AlertLevel,
+            Metric::AlertLevelName(_) => MetricKind::AlertLevelName,
+            Metric::AreaCode(_) => MetricKind::AreaCode,
+            Metric::AreaName(_) => MetricKind::AreaName,
+            Metric::AreaType(_) => MetricKind::AreaType,
+            Metric::CovidOccupiedMechanicalVentilatorBeds(_) => MetricKind::CovidOccupiedMechanicalVentilatorBeds,
+            Metric::CumulativeAdmissions(_) => MetricKind::CumulativeAdmissions,
+            Metric::CumulativeAdmissionsByAge(_) => MetricKind::CumulativeAdmissionsByAge,
+            Metric::CumulativeCasesByPublishDate(_) => MetricKind::CumulativeCasesByPublishDate,
+            Metric::CumulativeCasesBySpecimenDateRange(_) => MetricKind::CumulativeCasesBySpecimenDateRange,
+            Metric::CumulativeDeathsWithin28DaysByPublishDate(_) => MetricKind::CumulativeDeathsWithin28DaysByPublishDate,
+            Metric::NewDeathsWithin28DaysByDeathDate(_) => MetricKind::NewDeathsWithin28DaysByDeathDate,
+            Metric::CumulativeDeathsWithin28DaysByDeathDate(_) => MetricKind::CumulativeDeathsWithin28DaysByDeathDate,
+            Metric::NewDeathsWithin60DaysByDeathDate(_) => MetricKind::NewDeathsWithin60DaysByDeathDate,
+            Metric::CumulativeDeathsWithin60DaysByDeathDate(_) => MetricKind::CumulativeDeathsWithin60DaysByDeathDate,
+            Metric::CumulativePillarFourTestsByPublishDate(_) => MetricKind::CumulativePillarFourTestsByPublishDate,
+            Metric::CumulativePillarOneTestsByPublishDate(_) => MetricKind::CumulativePillarOneTestsByPublishDate,
+            Metric::CumulativePillarThreeTestsByPublishDate(_) => MetricKind::CumulativePillarThreeTestsByPublishDate,
+            Metric::CumulativePillarTwoTestsByPublishDate(_) => MetricKind::CumulativePillarTwoTestsByPublishDate,
+            Metric::CumulativeTestsByPublishDate(_) => MetricKind::CumulativeTestsByPublishDate,
+            Metric::Date(_) => MetricKind::Date,
+            Metric::Week(_) => MetricKind::Week,
+            Metric::FemaleCases(_) => MetricKind::FemaleCases,
+            Metric::Generic(name, value) => MetricKind::Generic {
+                name: name.clone(),
+                value_type: match value {
+                    MetricValue::Int(_) => ValueType::Int,
+                    MetricValue::Float(_) => ValueType::Float,
+                    MetricValue::Text(_) => ValueType::Text,
+                    _ => ValueType::Json,
+                },
+            },
+            Metric::Hash(_) => MetricKind::Hash,
+            Metric::HospitalCases(_) => MetricKind::HospitalCases,
+            Metric::MaleCases(_) => MetricKind::MaleCases,
+            Metric::HospitalCasesByAge(_) => MetricKind::HospitalCasesByAge,
+            Metric::CovidOccupiedMechanicalVentilatorBedsByAge(_) => MetricKind::CovidOccupiedMechanicalVentilatorBedsByAge,
+            Metric::NewAdmissions(_) => MetricKind::NewAdmissions,
+            Metric::NewCasesByPublishDate(_) => MetricKind::NewCasesByPublishDate,
+            Metric::NewCasesBySpecimenDate(_) => MetricKind::NewCasesBySpecimenDate,
+            Metric::NewDeathsWithin28DaysByPublishDate(_) => MetricKind::NewDeathsWithin28DaysByPublishDate,
+            Metric::NewPillarFourTestsByPublishDate(_) => MetricKind::NewPillarFourTestsByPublishDate,
+            Metric::NewPillarOneTestsByPublishDate(_) => MetricKind::NewPillarOneTestsByPublishDate,
+            Metric::NewPillarThreeTestsByPublishDate(_) => MetricKind::NewPillarThreeTestsByPublishDate,
+            Metric::NewPillarTwoTestsByPublishDate(_) => MetricKind::NewPillarTwoTestsByPublishDate,
+            Metric::NewTestsByPublishDate(_) => MetricKind::NewTestsByPublishDate,
+            Metric::PlannedCapacityByPublishDate(_) => MetricKind::PlannedCapacityByPublishDate,
+            Metric::NewCasesPCROnlyBySpecimenDate(_) => MetricKind::NewCasesPCROnlyBySpecimenDate,
+            Metric::NewCasesLFDConfirmedPCRBySpecimenDate(_) => MetricKind::NewCasesLFDConfirmedPCRBySpecimenDate,
+            Metric::NewCasesLFDOnlyBySpecimenDate(_) => MetricKind::NewCasesLFDOnlyBySpecimenDate,
+            Metric::TransmissionRateMax(_) => MetricKind::TransmissionRateMax,
+            Metric::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage(_) => MetricKind::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage,
+            Metric::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage(_) => MetricKind::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage,
+            Metric::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage(_) => MetricKind::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage,
+            Metric::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage(_) => MetricKind::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage,
+            Metric::NewPeopleVaccinatedThirdInjectionByPublishDate(_) => MetricKind::NewPeopleVaccinatedThirdInjectionByPublishDate,
+            Metric::CumulativePeopleVaccinatedThirdInjectionByPublishDate(_) => MetricKind::CumulativePeopleVaccinatedThirdInjectionByPublishDate,
+            Metric::TransmissionRateMin(_) => MetricKind::TransmissionRateMin,
+        }
+    }
+
+    /// Returns the value of this Metric, whatever its type, so that metrics 
+    /// can be handled uniformly without matching on their variants.
+    pub fn value(&self) -> data::MetricValue {
+        self.into()
+    }
+
+    /// Returns the value of this Metric if it is a numeric metric with a 
+    /// value for the day; `None` otherwise.
+    /// 
+    /// The value is also `None` if it doesn't fit in an `i32`, which some 
+    /// cumulative test totals may not; use `as_i64` for those.
+    pub fn as_i32(&self) -> Option<i32> {
+        self.as_i64().and_then(|v| i32::try_from(v).ok())
+    }
+
+    /// Returns the value of this Metric as an integer, as with 
+    /// `MetricValue::as_i64`; `None` for metrics without a value.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.wrapped()?.as_i64()
+    }
+
+    /// Returns the value of this Metric as a float, as with 
+    /// `MetricValue::as_f64`; `None` for metrics without a value.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.wrapped()?.as_f64()
+    }
+
+    /// Returns the MetricValue held by this Metric, for the metrics holding 
+    /// one (those other than the area, date, hash, and age band metrics).
+    pub(crate) fn wrapped(&self) -> Option<&MetricValue> {
+        match self {
+            Metric::AlertLevel(v)
+            | Metric::AlertLevelName(v)
+            | Metric::CovidOccupiedMechanicalVentilatorBeds(v)
+            | Metric::CumulativeAdmissions(v)
+            | Metric::CumulativeAdmissionsByAge(v)
+            | Metric::CumulativeCasesByPublishDate(v)
+            | Metric::CumulativeCasesBySpecimenDateRange(v)
+            | Metric::CumulativeDeathsWithin28DaysByPublishDate(v)
+            | Metric::NewDeathsWithin28DaysByDeathDate(v)
+            | Metric::CumulativeDeathsWithin28DaysByDeathDate(v)
+            | Metric::NewDeathsWithin60DaysByDeathDate(v)
+            | Metric::CumulativeDeathsWithin60DaysByDeathDate(v)
+            | Metric::CumulativePillarFourTestsByPublishDate(v)
+            | Metric::CumulativePillarOneTestsByPublishDate(v)
+            | Metric::CumulativePillarThreeTestsByPublishDate(v)
+            | Metric::CumulativePillarTwoTestsByPublishDate(v)
+            | Metric::CumulativeTestsByPublishDate(v)
+            | Metric::HospitalCases(v)
+            | Metric::NewAdmissions(v)
+            | Metric::NewCasesByPublishDate(v)
+            | Metric::NewCasesBySpecimenDate(v)
+            | Metric::NewDeathsWithin28DaysByPublishDate(v)
+            | Metric::NewPillarFourTestsByPublishDate(v)
+            | Metric::NewPillarOneTestsByPublishDate(v)
+            | Metric::NewPillarThreeTestsByPublishDate(v)
+            | Metric::NewPillarTwoTestsByPublishDate(v)
+            | Metric::NewTestsByPublishDate(v)
+            | Metric::PlannedCapacityByPublishDate(v)
+            | Metric::NewCasesPCROnlyBySpecimenDate(v)
+            | Metric::NewCasesLFDConfirmedPCRBySpecimenDate(v)
+            | Metric::NewCasesLFDOnlyBySpecimenDate(v)
+            | Metric::TransmissionRateMax(v)
+            | Metric::TransmissionRateMin(v)
+            | Metric::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage(v)
+            | Metric::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage(v)
+            | Metric::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage(v)
+            | Metric::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage(v)
+            | Metric::NewPeopleVaccinatedThirdInjectionByPublishDate(v)
+            | Metric::CumulativePeopleVaccinatedThirdInjectionByPublishDate(v)
+            | Metric::Generic(_, v) => Some(v),
+            Metric::AreaCode(_)
+            | Metric::AreaName(_)
+            | Metric::AreaType(_)
+            | Metric::CovidOccupiedMechanicalVentilatorBedsByAge(_)
+            | Metric::Date(_)
+            | Metric::Week(_)
+            | Metric::FemaleCases(_)
+            | Metric::Hash(_)
+            | Metric::HospitalCasesByAge(_)
+            | Metric::MaleCases(_) => None,
+        }
+    }
+
+    /// Returns the value of this Metric as an exact decimal if it is a 
+    /// numeric metric with a value for the day; `None` otherwise. Requires 
+    /// the `rust_decimal` feature.
+    /// 
+    /// Rates are converted from their shortest decimal form, so a value 
+    /// reported by the API as `12.34` is returned as exactly `12.34`.
+    #[cfg(feature = "rust_decimal")]
+    pub fn as_decimal(&self) -> Option<rust_decimal::Decimal> {
+        use std::str::FromStr;
+
+        let text = self.as_i64().map(|v| v.to_string()).or_else(|| self.as_f64().map(|v| v.to_string()))?;
+        rust_decimal::Decimal::from_str(&text)
+            .or_else(|_| rust_decimal::Decimal::from_scientific(&text))
+            .ok()
+    }
+}
+
+/// Identifies a Metric without carrying a value, for use where a metric is 
+/// being named (such as when building a request) rather than reported.
+/// 
+/// MetricKinds are compared (and hashed) by their API names, so a generic 
+/// metric is the same MetricKind whatever the type declared for its value.
+#[derive(Debug, Clone)]
+pub enum MetricKind {
+    AreaType,
+    AreaName,
+    AreaCode,
+    Date,
+    Week,
+    Hash,
+    NewCasesByPublishDate,
+    CumulativeCasesByPublishDate,
+    CumulativeCasesBySpecimenDateRange,
+    NewCasesBySpecimenDate,
+    MaleCases,
+    FemaleCases,
+    NewPillarOneTestsByPublishDate,
+    CumulativePillarOneTestsByPublishDate,
+    NewPillarTwoTestsByPublishDate,
+    CumulativePillarTwoTestsByPublishDate,
+    NewPillarThreeTestsByPublishDate,
+    CumulativePillarThreeTestsByPublishDate,
+    NewPillarFourTestsByPublishDate,
+    CumulativePillarFourTestsByPublishDate,
+    NewAdmissions,
+    CumulativeAdmissions,
+    CumulativeAdmissionsByAge,
+    CumulativeTestsByPublishDate,
+    NewTestsByPublishDate,
+    CovidOccupiedMechanicalVentilatorBeds,
+    CovidOccupiedMechanicalVentilatorBedsByAge,
+    HospitalCases,
+    HospitalCasesByAge,
+    PlannedCapacityByPublishDate,
+    NewDeathsWithin28DaysByPublishDate,
+    CumulativeDeathsWithin28DaysByPublishDate,
+    NewDeathsWithin28DaysByDeathDate,
+    CumulativeDeathsWithin28DaysByDeathDate,
+    NewDeathsWithin60DaysByDeathDate,
+    CumulativeDeathsWithin60DaysByDeathDate,
+    NewCasesPCROnlyBySpecimenDate,
+    NewCasesLFDConfirmedPCRBySpecimenDate,
+    NewCasesLFDOnlyBySpecimenDate,
+    AlertLevel,
+    AlertLevelName,
+    TransmissionRateMin,
+    TransmissionRateMax,
+    CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage,
+    CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage,
+    CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage,
+    CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage,
+    NewPeopleVaccinatedThirdInjectionByPublishDate,
+    CumulativePeopleVaccinatedThirdInjectionByPublishDate,
+    /// Any other metric, by its API name, with the type its value is read 
+    /// as: each value is read as the declared type (accepting numbers sent 
+    /// as numeric strings), and a value which can't be is a 
+    /// `Error::ParseError`, rather than being silently misread. 
+    /// `ValueType::Json` leaves the value as the API returned it.
+    Generic { name: String, value_type: ValueType },
+}
+impl PartialEq for MetricKind {
+    fn eq(&self, other: &MetricKind) -> bool {
+        self.name() == other.name()
+    }
+}
+impl Eq for MetricKind {}
+impl std::hash::Hash for MetricKind {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name().hash(state);
+    }
+}
+
+/// Every MetricKind other than `Generic`.
+const KNOWN_METRICS: &[MetricKind] = &[
+    MetricKind::AreaType,
+    MetricKind::AreaName,
+    MetricKind::AreaCode,
+    MetricKind::Date,
+    MetricKind::Week,
+    MetricKind::Hash,
+    MetricKind::NewCasesByPublishDate,
+    MetricKind::CumulativeCasesByPublishDate,
+    MetricKind::CumulativeCasesBySpecimenDateRange,
+    MetricKind::NewCasesBySpecimenDate,
+    MetricKind::MaleCases,
+    MetricKind::FemaleCases,
+    MetricKind::NewPillarOneTestsByPublishDate,
+    MetricKind::CumulativePillarOneTestsByPublishDate,
+    MetricKind::NewPillarTwoTestsByPublishDate,
+    MetricKind::CumulativePillarTwoTestsByPublishDate,
+    MetricKind::NewPillarThreeTestsByPublishDate,
+    MetricKind::CumulativePillarThreeTestsByPublishDate,
+    MetricKind::NewPillarFourTestsByPublishDate,
+    MetricKind::CumulativePillarFourTestsByPublishDate,
+    MetricKind::NewAdmissions,
+    MetricKind::CumulativeAdmissions,
+    MetricKind::CumulativeAdmissionsByAge,
+    MetricKind::CumulativeTestsByPublishDate,
+    MetricKind::NewTestsByPublishDate,
+    MetricKind::CovidOccupiedMechanicalVentilatorBeds,
+    MetricKind::CovidOccupiedMechanicalVentilatorBedsByAge,
+    MetricKind::HospitalCases,
+    MetricKind::HospitalCasesByAge,
+    MetricKind::PlannedCapacityByPublishDate,
+    MetricKind::NewDeathsWithin28DaysByPublishDate,
+    MetricKind::CumulativeDeathsWithin28DaysByPublishDate,
+    MetricKind::NewDeathsWithin28DaysByDeathDate,
+    MetricKind::CumulativeDeathsWithin28DaysByDeathDate,
+    MetricKind::NewDeathsWithin60DaysByDeathDate,
+    MetricKind::CumulativeDeathsWithin60DaysByDeathDate,
+    MetricKind::NewCasesPCROnlyBySpecimenDate,
+    MetricKind::NewCasesLFDConfirmedPCRBySpecimenDate,
+    MetricKind::NewCasesLFDOnlyBySpecimenDate,
+    MetricKind::AlertLevel,
+    MetricKind::AlertLevelName,
+    MetricKind::TransmissionRateMin,
+    MetricKind::TransmissionRateMax,
+    MetricKind::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage,
+    MetricKind::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage,
+    MetricKind::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage,
+    MetricKind::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage,
+    MetricKind::NewPeopleVaccinatedThirdInjectionByPublishDate,
+    MetricKind::CumulativePeopleVaccinatedThirdInjectionByPublishDate,
+];
+
+impl MetricKind {
+    /// Returns the name of the metric as used by the API.
+    pub fn name(&self) -> &str {
+        match self {
+            MetricKind::AlertLevel => "alertLevel",
+            MetricKind::AlertLevelName => "alertLevelName",
+            MetricKind::AreaCode => "areaCode",
+            MetricKind::AreaName => "areaName",
+            MetricKind::AreaType => "areaType",
+            MetricKind::CovidOccupiedMechanicalVentilatorBeds => "covidOccupiedMVBeds",
+            MetricKind::CovidOccupiedMechanicalVentilatorBedsByAge => "covidOccupiedMVBedsByAge",
+            MetricKind::CumulativeAdmissions => "cumAdmissions",
+            MetricKind::CumulativeAdmissionsByAge => "cumAdmissionsByAge",
+            MetricKind::CumulativeCasesByPublishDate => "cumCasesByPublishDate",
+            MetricKind::CumulativeCasesBySpecimenDateRange => "cumCasesBySpecimenDateRange",
+            MetricKind::CumulativeDeathsWithin28DaysByPublishDate => "cumDeaths28DaysByPublishDate",
+            MetricKind::NewDeathsWithin28DaysByDeathDate => "newDeaths28DaysByDeathDate",
+            MetricKind::CumulativeDeathsWithin28DaysByDeathDate => "cumDeaths28DaysByDeathDate",
+            MetricKind::NewDeathsWithin60DaysByDeathDate => "newDeaths60DaysByDeathDate",
+            MetricKind::CumulativeDeathsWithin60DaysByDeathDate => "cumDeaths60DaysByDeathDate",
+            MetricKind::CumulativePillarFourTestsByPublishDate => "cumPillarFourTestsByPublishDate",
+            MetricKind::CumulativePillarOneTestsByPublishDate => "cumPillarOneTestsByPublishDate",
+            MetricKind::CumulativePillarThreeTestsByPublishDate => "cumPillarThreeTestsByPublishDate",
+            MetricKind::CumulativePillarTwoTestsByPublishDate => "cumPillarTwoTestsByPublishDate",
+            MetricKind::CumulativeTestsByPublishDate => "cumTestsByPublishDate",
+            MetricKind::Date => "date",
+            MetricKind::Week => "week",
+            MetricKind::FemaleCases => "femaleCases",
+            MetricKind::Generic { name, .. } => name,
+            MetricKind::Hash => "hash",
+            MetricKind::HospitalCases => "hospitalCases",
+            MetricKind::HospitalCasesByAge => "hospitalCasesByAge",
+            MetricKind::MaleCases => "maleCases",
+            MetricKind::NewAdmissions => "newAdmissions",
+            MetricKind::NewCasesByPublishDate => "newCasesByPublishDate",
+            MetricKind::NewCasesBySpecimenDate => "newCasesBySpecimenDate",
+            MetricKind::NewDeathsWithin28DaysByPublishDate => "newDeaths28DaysByPublishDate",
+            MetricKind::NewPillarFourTestsByPublishDate => "newPillarFourTestsByPublishDate",
+            MetricKind::NewPillarOneTestsByPublishDate => "newPillarOneTestsByPublishDate",
+            MetricKind::NewPillarThreeTestsByPublishDate => "newPillarThreeTestsByPublishDate",
+            MetricKind::NewPillarTwoTestsByPublishDate => "newPillarTwoTestsByPublishDate",
+            MetricKind::NewTestsByPublishDate => "newTestsByPublishDate",
+            MetricKind::PlannedCapacityByPublishDate => "plannedCapacityByPublishDate",
+            MetricKind::NewCasesPCROnlyBySpecimenDate => "newCasesPCROnlyBySpecimenDate",
+            MetricKind::NewCasesLFDConfirmedPCRBySpecimenDate => "newCasesLFDConfirmedPCRBySpecimenDate",
+            MetricKind::NewCasesLFDOnlyBySpecimenDate => "newCasesLFDOnlyBySpecimenDate",
+            MetricKind::TransmissionRateMax => "transmissionRateMax",
+            MetricKind::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage => "cumVaccinationFirstDoseUptakeByPublishDatePercentage",
+            MetricKind::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage => "cumVaccinationSecondDoseUptakeByPublishDatePercentage",
+            MetricKind::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage => "cumVaccinationThirdInjectionUptakeByPublishDatePercentage",
+            MetricKind::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage => "cumVaccinationBoosterDoseUptakeByPublishDatePercentage",
+            MetricKind::NewPeopleVaccinatedThirdInjectionByPublishDate => "newPeopleVaccinatedThirdInjectionByPublishDate",
+            MetricKind::CumulativePeopleVaccinatedThirdInjectionByPublishDate => "cumPeopleVaccinatedThirdInjectionByPublishDate",
+            MetricKind::TransmissionRateMin => "transmissionRateMin",
+        }
+    }
+
+    /// Returns whether the API populates the metric for areas of the provided 
+    /// type.
+    /// 
+    /// Healthcare metrics are only reported for the UK overview, nations, and 
+    /// NHS regions; testing metrics only for the overview and nations; and 
+    /// case and death metrics for every area type except NHS regions. Alert 
+    /// levels are only set for local authorities, and R is only estimated for 
+    /// the UK overview, nations, and regions. The 
+    /// area and date metrics are available for every area type. This 
+    /// reflects the dashboard's coverage, and a metric may still be empty for 
+    /// some areas of a type it is available for.
+    pub fn available_for(&self, area: &AreaType) -> bool {
+        match self {
+            MetricKind::AreaCode
+            | MetricKind::AreaName
+            | MetricKind::AreaType
+            | MetricKind::Date
+            | MetricKind::Hash
+            | MetricKind::Week => true,
+            // Nothing is known of a generic metric's coverage.
+            MetricKind::Generic { .. } => true,
+            MetricKind::CovidOccupiedMechanicalVentilatorBeds
+            | MetricKind::CovidOccupiedMechanicalVentilatorBedsByAge
+            | MetricKind::CumulativeAdmissions
+            | MetricKind::CumulativeAdmissionsByAge
+            | MetricKind::HospitalCases
+            | MetricKind::HospitalCasesByAge
+            | MetricKind::NewAdmissions
+            | MetricKind::PlannedCapacityByPublishDate => matches!(
+                area,
+                AreaType::Overview | AreaType::Nation | AreaType::NHSRegion
+            ),
+            MetricKind::CumulativePillarOneTestsByPublishDate
+            | MetricKind::CumulativePillarTwoTestsByPublishDate
+            | MetricKind::CumulativePillarThreeTestsByPublishDate
+            | MetricKind::CumulativePillarFourTestsByPublishDate
+            | MetricKind::CumulativeTestsByPublishDate
+            | MetricKind::NewPillarOneTestsByPublishDate
+            | MetricKind::NewPillarTwoTestsByPublishDate
+            | MetricKind::NewPillarThreeTestsByPublishDate
+            | MetricKind::NewPillarFourTestsByPublishDate
+            | MetricKind::NewTestsByPublishDate => {
+                matches!(area, AreaType::Overview | AreaType::Nation)
+            }
+            MetricKind::CumulativeCasesByPublishDate
+            | MetricKind::CumulativeCasesBySpecimenDateRange
+            | MetricKind::CumulativeDeathsWithin28DaysByPublishDate
+            | MetricKind::NewDeathsWithin28DaysByDeathDate
+            | MetricKind::CumulativeDeathsWithin28DaysByDeathDate
+            | MetricKind::NewDeathsWithin60DaysByDeathDate
+            | MetricKind::CumulativeDeathsWithin60DaysByDeathDate
+            | MetricKind::FemaleCases
+            | MetricKind::MaleCases
+            | MetricKind::NewCasesByPublishDate
+            | MetricKind::NewCasesBySpecimenDate
+            | MetricKind::NewCasesPCROnlyBySpecimenDate
+            | MetricKind::NewCasesLFDConfirmedPCRBySpecimenDate
+            | MetricKind::NewCasesLFDOnlyBySpecimenDate
+            | MetricKind::NewDeathsWithin28DaysByPublishDate => *area != AreaType::NHSRegion,
+            MetricKind::AlertLevel | MetricKind::AlertLevelName => {
+                matches!(area, AreaType::UTLA | AreaType::LTLA)
+            }
+            MetricKind::TransmissionRateMax | MetricKind::TransmissionRateMin => {
+                matches!(area, AreaType::Overview | AreaType::Nation | AreaType::Region)
+            }
+            MetricKind::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage
+            | MetricKind::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage
+            | MetricKind::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage
+            | MetricKind::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage
+            | MetricKind::NewPeopleVaccinatedThirdInjectionByPublishDate
+            | MetricKind::CumulativePeopleVaccinatedThirdInjectionByPublishDate => true,
+        }
+    }
+
+    fn parse(&self, value: &json::JsonValue) -> Result<Metric, Error> {
+        // Some newer metrics wrap their value in an object, under `value` or 
+        // `payload`.
+        let number = if value.is_object() {
+            if value.has_key("value") {
+                &value["value"]
+            } else {
+                &value["payload"]
+            }
+        } else {
+            value
+        };
+
+        // Each value is parsed by its JSON type, then read as the metric's 
+        // type where it can be: some versions of the API have sent numbers 
+        // as numeric strings (such as `"123"`), and integers as floats (such 
+        // as `12.0`). A value which can't be (such as a fractional count) is 
+        // kept as it was sent, with a warning, as that suggests the metric's 
+        // type has changed.
+        let mismatch = |expected: &str, parsed: MetricValue| {
+            if !parsed.is_null() {
+                log::warn!("{} expects {}, but the API returned {}; keeping it as sent", self.name(), expected, number);
+            }
+            parsed
+        };
+        let integer = || match parse_value(number) {
+            MetricValue::Text(s) if s.trim().parse::<i64>().is_ok() => MetricValue::Int(s.trim().parse().unwrap()),
+            MetricValue::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => MetricValue::Int(f as i64),
+            v @ MetricValue::Int(_) => v,
+            v => mismatch("an integer", v),
+        };
+        let float = || match parse_value(number) {
+            MetricValue::Int(i) => MetricValue::Float(i as f64),
+            MetricValue::Text(s) if s.trim().parse::<f64>().is_ok() => MetricValue::Float(s.trim().parse().unwrap()),
+            v @ MetricValue::Float(_) => v,
+            v => mismatch("a number", v),
+        };
+        let text = || match parse_value(value) {
+            v @ MetricValue::Text(_) => v,
+            v => mismatch("text", v),
+        };
+
+        Ok(match self {
+            MetricKind::AlertLevel => Metric::AlertLevel(integer()),
+            MetricKind::AlertLevelName => Metric::AlertLevelName(text()),
+            MetricKind::AreaCode => Metric::AreaCode(value.to_string()),
+            MetricKind::AreaName => Metric::AreaName(value.to_string()),
+            MetricKind::AreaType => Metric::AreaType(match value.to_string().as_str() {
+                "overview" => AreaType::Overview,
+                "nation" => AreaType::Nation,
+                "region" => AreaType::Region,
+                "nhsRegion" => AreaType::NHSRegion,
+                "utla" => AreaType::UTLA,
+                "ltla" => AreaType::LTLA,
+                s => return Err(Error::IncompatibleApiVersion(format!("Unknown area type ({})", s))),
+            }),
+            MetricKind::CovidOccupiedMechanicalVentilatorBeds => Metric::CovidOccupiedMechanicalVentilatorBeds(integer()),
+            MetricKind::CumulativeAdmissions => Metric::CumulativeAdmissions(integer()),
+            MetricKind::CumulativeAdmissionsByAge => Metric::CumulativeAdmissionsByAge(integer()),
+            MetricKind::CumulativeCasesByPublishDate => Metric::CumulativeCasesByPublishDate(integer()),
+            MetricKind::CumulativeCasesBySpecimenDateRange => Metric::CumulativeCasesBySpecimenDateRange(integer()),
+            MetricKind::CumulativeDeathsWithin28DaysByPublishDate => Metric::CumulativeDeathsWithin28DaysByPublishDate(integer()),
+            MetricKind::NewDeathsWithin28DaysByDeathDate => Metric::NewDeathsWithin28DaysByDeathDate(integer()),
+            MetricKind::CumulativeDeathsWithin28DaysByDeathDate => Metric::CumulativeDeathsWithin28DaysByDeathDate(integer()),
+            MetricKind::NewDeathsWithin60DaysByDeathDate => Metric::NewDeathsWithin60DaysByDeathDate(integer()),
+            MetricKind::CumulativeDeathsWithin60DaysByDeathDate => Metric::CumulativeDeathsWithin60DaysByDeathDate(integer()),
+            MetricKind::CumulativePillarFourTestsByPublishDate => Metric::CumulativePillarFourTestsByPublishDate(integer()),
+            MetricKind::CumulativePillarOneTestsByPublishDate => Metric::CumulativePillarOneTestsByPublishDate(integer()),
+            MetricKind::CumulativePillarThreeTestsByPublishDate => Metric::CumulativePillarThreeTestsByPublishDate(integer()),
+            MetricKind::CumulativePillarTwoTestsByPublishDate => Metric::CumulativePillarTwoTestsByPublishDate(integer()),
+            MetricKind::CumulativeTestsByPublishDate => Metric::CumulativeTestsByPublishDate(integer()),
+            MetricKind::Date => Metric::Date(
+                Date::parse(value.to_string(), "%F")
+                    .map_err(|e| Error::ParseError(format!("Invalid date ({}): {}", value, e)))?,
+            ),
+            MetricKind::Week => Metric::Week(
+                value
+                    .to_string()
+                    .parse()
+                    .map_err(|_| Error::ParseError(format!("Invalid week: {}", value)))?,
+            ),
+            MetricKind::FemaleCases => Metric::FemaleCases(AgeBand::parse_all(value)),
+            MetricKind::Generic { name, value_type } => Metric::Generic(name.clone(), value_type.coerce(name, value)?),
+            MetricKind::Hash => Metric::Hash(value.to_string()),
+            MetricKind::HospitalCases => Metric::HospitalCases(integer()),
+            MetricKind::MaleCases => Metric::MaleCases(AgeBand::parse_all(value)),
+            MetricKind::HospitalCasesByAge => Metric::HospitalCasesByAge(AgeBand::parse_all(value)),
+            MetricKind::CovidOccupiedMechanicalVentilatorBedsByAge => {
+                Metric::CovidOccupiedMechanicalVentilatorBedsByAge(AgeBand::parse_all(value))
+            }
+            MetricKind::NewAdmissions => Metric::NewAdmissions(integer()),
+            MetricKind::NewCasesByPublishDate => Metric::NewCasesByPublishDate(integer()),
+            MetricKind::NewCasesBySpecimenDate => Metric::NewCasesBySpecimenDate(integer()),
+            MetricKind::NewDeathsWithin28DaysByPublishDate => Metric::NewDeathsWithin28DaysByPublishDate(integer()),
+            MetricKind::NewPillarFourTestsByPublishDate => Metric::NewPillarFourTestsByPublishDate(integer()),
+            MetricKind::NewPillarOneTestsByPublishDate => Metric::NewPillarOneTestsByPublishDate(integer()),
+            MetricKind::NewPillarThreeTestsByPublishDate => Metric::NewPillarThreeTestsByPublishDate(integer()),
+            MetricKind::NewPillarTwoTestsByPublishDate => Metric::NewPillarTwoTestsByPublishDate(integer()),
+            MetricKind::NewTestsByPublishDate => Metric::NewTestsByPublishDate(integer()),
+            MetricKind::PlannedCapacityByPublishDate => Metric::PlannedCapacityByPublishDate(integer()),
+            MetricKind::NewCasesPCROnlyBySpecimenDate => Metric::NewCasesPCROnlyBySpecimenDate(integer()),
+            MetricKind::NewCasesLFDConfirmedPCRBySpecimenDate => Metric::NewCasesLFDConfirmedPCRBySpecimenDate(integer()),
+            MetricKind::NewCasesLFDOnlyBySpecimenDate => Metric::NewCasesLFDOnlyBySpecimenDate(integer()),
+            MetricKind::TransmissionRateMax => Metric::TransmissionRateMax(float()),
+            MetricKind::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage => Metric::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage(float()),
+            MetricKind::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage => Metric::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage(float()),
+            MetricKind::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage => Metric::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage(float()),
+            MetricKind::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage => Metric::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage(float()),
+            MetricKind::NewPeopleVaccinatedThirdInjectionByPublishDate => Metric::NewPeopleVaccinatedThirdInjectionByPublishDate(integer()),
+            MetricKind::CumulativePeopleVaccinatedThirdInjectionByPublishDate => Metric::CumulativePeopleVaccinatedThirdInjectionByPublishDate(integer()),
+            MetricKind::TransmissionRateMin => Metric::TransmissionRateMin(float()),
+        })
+    }
+}
+impl std::str::FromStr for MetricKind {
+    type Err = std::convert::Infallible;
+
+    /// Parses a metric from its name as used by the API (e.g., 
+    /// `newCasesByPublishDate`); any name not known to this library is 
+    /// parsed as a `Generic` metric.
+    fn from_str(s: &str) -> Result<MetricKind, std::convert::Infallible> {
+        Ok(KNOWN_METRICS
+            .iter()
+            .find(|m| m.name() == s)
+            .cloned()
+            .unwrap_or_else(|| MetricKind::Generic {
+                name: s.to_string(),
+                value_type: ValueType::Json,
+            }))
     }
 }
 
 /// The data for the requested metrics for a specific day.
+///
+/// Metrics are in the order they were requested. A metric the API did not 
+/// return a value for is read as if its value were null (or its default set 
+/// with `set_null_default`), so every Datum has one Metric per requested 
+/// metric.
 pub type Datum = Vec<Metric>;
 /// The complete collection of days.
 pub type Data = Vec<Datum>;
 
+/// The order in which days are returned by `Request::get`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Order {
+    /// Newest day first, as returned by the API.
+    #[default]
+    ReverseChronological,
+    /// Oldest day first.
+    Chronological,
+}
+
+/// A vaccine dose, for `Request::vaccination_uptake`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dose {
+    First,
+    Second,
+    /// A booster or third dose.
+    Booster,
+}
+impl Dose {
+    /// Returns the metric reporting the uptake of the dose, as a percentage.
+    pub fn uptake_metric(&self) -> MetricKind {
+        match self {
+            Dose::First => MetricKind::CumulativeVaccinationFirstDoseUptakeByPublishDatePercentage,
+            Dose::Second => MetricKind::CumulativeVaccinationSecondDoseUptakeByPublishDatePercentage,
+            Dose::Booster => MetricKind::CumulativeVaccinationThirdInjectionUptakeByPublishDatePercentage,
+        }
+    }
+}
+
+/// The format in which the API is asked to send responses.
+/// 
+/// The v1 data endpoint serves `Json`, which is the default. `JsonL` is for 
+/// endpoints serving JSON lines, with one day per line and no pagination; 
+/// each line is parsed separately, so no single large array need be parsed. 
+/// Either can be processed a day at a time with `Request::for_each`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Format {
+    #[default]
+    Json,
+    JsonL,
+}
+impl Format {
+    /// Returns the name of the format as used by the API.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::JsonL => "jsonl",
+        }
+    }
+}
+
+/// How a request using the named structure treats a day of a response which 
+/// names a metric more than once, which the API shouldn't send.
+/// 
+/// The positional structure has no keys, so is unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DuplicateKeys {
+    /// The metric's last value in the day is used.
+    #[default]
+    Last,
+    /// The metric's first value in the day is used.
+    First,
+    /// The response is rejected with `Error::ParseError`.
+    Error,
+}
+
+/// The type of a generic metric's value, declared in `MetricKind::Generic` 
+/// so that it is read as reliably as the metrics this library knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    Int,
+    Float,
+    Text,
+    /// Any JSON value, left as the API returned it. This is the type of a 
+    /// generic metric named without declaring one, such as when parsed from 
+    /// its name.
+    Json,
+}
+impl ValueType {
+    const ALL: [ValueType; 4] = [ValueType::Int, ValueType::Float, ValueType::Text, ValueType::Json];
+
+    /// Returns the name of the type, as used in exported bundles.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueType::Int => "int",
+            ValueType::Float => "float",
+            ValueType::Text => "text",
+            ValueType::Json => "json",
+        }
+    }
+
+    /// Reads a raw value as this type, accepting numbers sent as numeric 
+    /// strings, as `MetricKind::parse` does. Nulls are left as they are.
+    fn coerce(&self, name: &str, value: &json::JsonValue) -> Result<MetricValue, Error> {
+        if value.is_null() {
+            return Ok(MetricValue::Null);
+        }
+        let coerced = match self {
+            ValueType::Int => value
+                .as_i64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .map(MetricValue::Int),
+            ValueType::Float => value
+                .as_f64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .map(MetricValue::Float),
+            ValueType::Text => value.as_str().map(|s| MetricValue::Text(s.to_string())),
+            ValueType::Json => Some(parse_value(value)),
+        };
+        coerced.ok_or_else(|| {
+            Error::ParseError(format!("{} was declared as {:?}, but the API returned {}", name, self, value.dump()))
+        })
+    }
+}
+impl std::str::FromStr for ValueType {
+    type Err = Error;
+
+    /// Parses a type from its name, as returned by `ValueType::name`.
+    fn from_str(s: &str) -> Result<ValueType, Error> {
+        ValueType::ALL
+            .iter()
+            .find(|t| t.name() == s)
+            .copied()
+            .ok_or_else(|| Error::UnknownName(s.to_string()))
+    }
+}
+
 /// A request to the API.
+/// 
+/// Requests are `Send` and `Sync` (Transports must be too), so a configured 
+/// request can be shared between threads, or cloned into each of them.
 ///
 /// A request is constructed and then submitted to the API. The request may be re-used and modified, if desired, but filters and metrics cannot be removed.
 ///
@@ -160,7 +1101,52 @@ pub type Data = Vec<Datum>;
 #[derive(Debug)]
 pub struct Request {
     filters: Vec<Filter>,
-    metrics: Vec<Metric>,
+    config: Arc<Config>,
+    diagnostics: Option<Arc<std::sync::Mutex<Vec<PageDiagnostic>>>>,
+    last_update: Arc<std::sync::Mutex<Option<String>>>,
+}
+impl Clone for Request {
+    /// Clones the request, with its own record of its fetches (see 
+    /// `last_diagnostics` and `last_update`), empty until the clone is 
+    /// fetched, so that requests fanned out from one (such as with 
+    /// `with_area_name`) don't report each other's fetches.
+    fn clone(&self) -> Request {
+        Request {
+            diagnostics: self.diagnostics.as_ref().map(|_| Arc::new(std::sync::Mutex::new(vec![]))),
+            last_update: Arc::new(std::sync::Mutex::new(None)),
+            ..self.derived()
+        }
+    }
+}
+
+/// The configuration of a Request besides its filters, shared between its 
+/// clones until one of them is modified, so that fanning a request out 
+/// across many areas doesn't copy it for each.
+#[derive(Debug, Clone)]
+struct Config {
+    metrics: Vec<MetricKind>,
+    transport: Option<Arc<dyn Transport>>,
+    partial_ok: bool,
+    named_structure: bool,
+    page_size: Option<u32>,
+    order: Order,
+    accept_language: Option<String>,
+    drop_trailing: usize,
+    format: Format,
+    max_bytes: Option<usize>,
+    null_defaults: Vec<(MetricKind, i32)>,
+    single_page: bool,
+    suggest_area_names: bool,
+    base_url: Option<String>,
+    fallbacks: Vec<(MetricKind, MetricKind)>,
+    aliases: Vec<(MetricKind, String)>,
+    strict: bool,
+    canonical_order: bool,
+    duplicate_keys: DuplicateKeys,
+    metric_priority: Option<Vec<MetricKind>>,
+    error_context: bool,
+    #[cfg(feature = "cookies")]
+    cookie_store: bool,
 }
 impl Request {
     /// Creates a new Request with the provided AreaType Filter (which is
@@ -169,239 +1155,5007 @@ impl Request {
     /// More Filters and Metrics may be added with the `add_filter` and 
     /// `add_metric` functions, respectively.
     pub fn new(area_type: AreaType, metric: Metric) -> Request {
-        Request {
+        Request::with_metrics(area_type, &[metric.kind()])
+    }
+
+    /// Creates a new Request with the provided AreaType Filter, requesting 
+    /// each of the provided metrics once, for when the metrics are only 
+    /// known by name (such as when parsed from user input).
+    pub fn with_metrics(area_type: AreaType, metrics: &[MetricKind]) -> Request {
+        let mut req = Request {
             filters: vec![Filter::new(FilterValue::AreaType(area_type))],
-            metrics: vec![metric],
+            config: Arc::new(Config {
+                metrics: vec![],
+                transport: None,
+                partial_ok: false,
+                named_structure: false,
+                page_size: None,
+                order: Order::ReverseChronological,
+                accept_language: None,
+                drop_trailing: 0,
+                format: Format::Json,
+                max_bytes: None,
+                null_defaults: vec![],
+                single_page: false,
+                suggest_area_names: false,
+                base_url: None,
+                fallbacks: vec![],
+                aliases: vec![],
+                strict: false,
+                canonical_order: false,
+                duplicate_keys: DuplicateKeys::Last,
+                metric_priority: None,
+                error_context: false,
+                #[cfg(feature = "cookies")]
+                cookie_store: false,
+            }),
+            diagnostics: None,
+            last_update: Arc::new(std::sync::Mutex::new(None)),
+        };
+        for metric in metrics {
+            req.ensure_metric(metric.clone());
         }
+        req
+    }
+
+    /// Creates a new Request for the provided Nation, requesting the 
+    /// provided Metric.
+    pub fn nation(nation: Nation, metric: Metric) -> Request {
+        let mut req = Request::new(AreaType::Nation, metric);
+        req.add_filter(Filter::new(nation.into()));
+        req
+    }
+
+    /// Returns a copy of the request filtered to the area with the provided 
+    /// name.
+    /// 
+    /// The copy shares the rest of this request's configuration, so fanning 
+    /// a request out to many areas costs the same however many metrics and 
+    /// options it has.
+    pub fn with_area_name(&self, name: &str) -> Request {
+        let mut req = self.clone();
+        req.add_filter(Filter::new(FilterValue::AreaName(name.to_string())));
+        req
     }
 
     /// Adds a Filter to the request; only data matching all request Filters 
     /// will be included in the response when executed.
+    /// 
+    /// The API doesn't reliably handle the same filter being given twice, so 
+    /// adding a Filter on a metric which is already filtered (e.g., a second 
+    /// `Date`) replaces the existing Filter rather than adding another.
     pub fn add_filter(&mut self, filter: Filter) {
-        self.filters.push(filter);
+        match self.filters.iter_mut().find(|f| f.metric == filter.metric) {
+            Some(existing) => *existing = filter,
+            None => self.filters.push(filter),
+        }
     }
 
     /// Adds a Metric to the request; data for this Metric will be included in
     /// the response when executed.
     pub fn add_metric(&mut self, metric: Metric) {
-        self.metrics.push(metric);
+        self.push_metric(metric.kind());
     }
 
-    /// Executes the request and returns the requested Metrics in a Vector, 
-    /// which is itself encapsulated in another Vector storing the Metric 
-    /// Vectors (called Datums) for each day.
-    /// 
-    /// ## Errors
-    /// 
-    /// This function may return an Error enum variant if the reqwest library 
-    /// or the API returned an error.
-    /// 
-    /// ## Panics
+    /// Adds a metric to the request under an alias of your choosing, which 
+    /// is used in place of the metric's API name as its key in the response 
+    /// (and as its column name in `write_csv`), such as `cases` for 
+    /// `cumCasesByPublishDate`.
     /// 
-    /// This function will panic if a status code other than 200, 204, 429, or 
-    /// 500 is returned by the API. This is not expected to occur and is likely 
-    /// a fault in the library if this does occur.
-    pub fn get(&self) -> Result<Data, Error> {
-        Ok(self.execute(Option::None)?)
+    /// Aliases are only sent with the named structure, so this enables it, 
+    /// as with `set_named_structure`.
+    pub fn add_metric_as(&mut self, kind: MetricKind, alias: &str) {
+        self.ensure_metric(kind.clone());
+        self.config_mut().aliases.retain(|(m, _)| *m != kind);
+        self.config_mut().aliases.push((kind, alias.to_string()));
+        self.config_mut().named_structure = true;
     }
 
-    /// Executes the request and returns the requested Metrics in a Vector, 
-    /// which is itself encapsulated in another Vector storing the Metric 
-    /// Vectors (called Datums) for each day. 
-    /// 
-    /// For this function, only one day's data will be provided, and will be 
-    /// that of the latest day available for the supplied Metric; thus, the 
-    /// outermost Vector will only contain one element.
+    /// Returns the key of the metric in named responses: its alias, if it 
+    /// was added with `add_metric_as`, or else its API name.
+    fn key<'a>(&'a self, metric: &'a MetricKind) -> &'a str {
+        self.config.aliases
+            .iter()
+            .find(|(m, _)| m == metric)
+            .map_or(metric.name(), |(_, alias)| alias.as_str())
+    }
+
+    /// Adds a metric to the request only if it is available for the 
+    /// request's area type, as reported by `MetricKind::available_for`, so 
+    /// that one set of desired metrics can be requested for any area type.
+    pub fn add_metric_if_available(&mut self, metric: MetricKind) {
+        if self.area_type().map_or(true, |t| metric.available_for(t)) {
+            self.push_metric(metric);
+        }
+    }
+
+    /// Adds a numeric metric to the request, with a fallback name to fetch 
+    /// it by for any days on which the primary has no value, such as where 
+    /// the API has renamed a metric and only the old name covers older days.
     /// 
-    /// ## Errors
+    /// If the primary metric has no value on some days, `get` fetches the 
+    /// request again with the fallback in its place, and fills those days 
+    /// (and any days only the fallback covers) with the fallback's values, 
+    /// reported as the primary metric. Days are matched by date, so the 
+    /// `date` metric is added to the request if it isn't already present.
+    pub fn add_metric_with_fallback(&mut self, primary: MetricKind, fallback: MetricKind) {
+        self.ensure_metric(primary.clone());
+        self.ensure_metric(MetricKind::Date);
+        self.config_mut().fallbacks.push((primary, fallback));
+    }
+
+    /// Sets whether the request uses the API's named structure (default: 
+    /// false).
     /// 
-    /// This function may return an Error enum variant if the reqwest library 
-    /// or the API returned an error.
+    /// By default, the structure is sent as a list of metric names and each 
+    /// day's values are read by their position. With the named structure, 
+    /// the structure is sent as an object naming each metric, so the API 
+    /// returns each day as an object and values are read by name.
+    pub fn set_named_structure(&mut self, named_structure: bool) {
+        self.config_mut().named_structure = named_structure;
+    }
+
+    /// Sets how a day of a `Format::Json` response which names a metric 
+    /// more than once is treated when using the named structure (default: 
+    /// `DuplicateKeys::Last`).
+    pub fn set_duplicate_key_policy(&mut self, policy: DuplicateKeys) {
+        self.config_mut().duplicate_keys = policy;
+    }
+
+    /// Sets the order in which `get` returns days (default: 
+    /// `Order::ReverseChronological`).
     /// 
-    /// ## Panics
+    /// Days can only be put in chronological order by their dates, so the 
+    /// `Date` metric is added to the request if it isn't already present.
+    pub fn set_order(&mut self, order: Order) {
+        if order == Order::Chronological {
+            self.ensure_metric(MetricKind::Date);
+        }
+        self.config_mut().order = order;
+    }
+
+    /// Sets the number of the newest days which `get` leaves out of its 
+    /// results (default: 0), as the latest figures are often incomplete and 
+    /// artificially low.
     /// 
-    /// This function will panic if a status code other than 200, 204, 429, or 
-    /// 500 is returned by the API. This is not expected to occur and is likely 
-    /// a fault in the library if this does occur.
-    pub fn get_latest_by_metric(&self, metric: Metric) -> Result<Data, Error> {
-        Ok(self.execute(Option::Some(metric))?)
+    /// Days are identified by their dates, so the `Date` metric is added to 
+    /// the request if it isn't already present.
+    pub fn set_drop_incomplete_trailing(&mut self, n: usize) {
+        if n > 0 {
+            self.ensure_metric(MetricKind::Date);
+        }
+        self.config_mut().drop_trailing = n;
     }
 
-    fn execute(&self, latest_by: Option<Metric>) -> Result<Data, Error> {
-        let client = reqwest::blocking::Client::new();
+    /// Sets whether only the first page of the request is fetched (default: 
+    /// false), for a quick look at the newest days without fetching the 
+    /// whole history.
+    pub fn set_single_page(&mut self, single_page: bool) {
+        self.config_mut().single_page = single_page;
+    }
 
-        let mut data = vec![];
-        let mut page = 1;
+    /// Sets whether, when `get` finds no data for the request's AreaName 
+    /// Filter, the areas of the requested AreaType are listed to suggest the 
+    /// names it may have meant (default: false), returning 
+    /// `Error::NoDataDidYouMean`. Listing the areas takes another request.
+    pub fn set_suggest_area_names(&mut self, suggest: bool) {
+        self.config_mut().suggest_area_names = suggest;
+    }
 
-        loop {
-            let url = self.construct_url(&latest_by, &page);
+    /// Sets whether errors returned by `get` are wrapped in 
+    /// `Error::WithContext`, naming the request's area and metrics (default: 
+    /// false), so that each failure of a batch of requests describes which 
+    /// request failed.
+    pub fn set_error_context(&mut self, enabled: bool) {
+        self.config_mut().error_context = enabled;
+    }
 
-            let req = client.get(&url)
-                            .header("Accepts", "application/json; application/xml; text/csv; application/vnd.PHE-COVID19.v1+json; application/vnd.PHE-COVID19.v1+xml")
-                            .header("Content-Type", "application/json");
+    /// Sets the URL of the API's data endpoint, in place of the public API 
+    /// (`https://api.coronavirus.data.gov.uk/v1/data`), such as for a mirror 
+    /// or a local mock server.
+    pub fn set_base_url(&mut self, url: &str) {
+        self.config_mut().base_url = Some(url.to_string());
+    }
 
-            let res = match req.send() {
-                Ok(r) => r,
-                Err(e) => return Result::Err(Error::RequestErr(e)),
-            };
-            let status_code = res.status().as_u16();
-            if status_code != 200 {
-                if status_code == 204 {
-                    return Result::Err(Error::NoData);
-                } else if status_code == 429 {
-                    return Result::Err(Error::TooManyRequests);
-                } else if status_code == 500 {
-                    return Result::Err(Error::APIServerError);
-                } else {
-                    panic!(
-                        "Error response from API ({}): {}",
-                        status_code,
-                        res.text().unwrap_or(String::from("No response text"))
-                    );
-                }
-            };
+    /// Sets a value to use for the numeric metric on days for which the API 
+    /// reported no value, in place of `None` (e.g., 0 for new cases). Other 
+    /// metrics are unaffected, and are still `None` on days without a value.
+    pub fn set_null_default(&mut self, metric: MetricKind, value: i32) {
+        self.config_mut().null_defaults.retain(|(m, _)| *m != metric);
+        self.config_mut().null_defaults.push((metric, value));
+    }
 
-            let body = res.text().unwrap();
+    /// Adds a metric unknown to this library to the request by its API name, 
+    /// as `MetricKind::Generic`, declaring the type of its value (replacing 
+    /// any type already declared for it).
+    /// 
+    /// Each value is read as the declared type, so the `Metric::Generic` 
+    /// returned holds a JSON number for `Int` and `Float`, and a string for 
+    /// `Text`; a value which can't be read as the declared type fails the 
+    /// request with `Error::ParseError`.
+    pub fn add_generic_metric(&mut self, name: &str, value_type: ValueType) {
+        let kind = MetricKind::Generic {
+            name: name.to_string(),
+            value_type,
+        };
+        match self.config.metrics.iter().position(|m| *m == kind) {
+            Some(i) => self.config_mut().metrics[i] = kind,
+            None => self.push_metric(kind),
+        }
+    }
 
-            let resp = match json::parse(&body) {
-                Ok(s) => s,
-                Err(e) => panic!("Error parsing JSON: {} (body: {})", e, body),
-            };
+    /// Sets a limit on the total size of the response bodies fetched by the 
+    /// request, across all of its pages (default: no limit).
+    /// 
+    /// The limit is checked as each page arrives; once it is exceeded, the 
+    /// request fails with `Error::ResponseTooLarge`, or, if partial data is 
+    /// allowed with `set_partial_ok` and the limit was exceeded after the 
+    /// first page, returns the pages within the limit.
+    pub fn set_max_bytes(&mut self, n: usize) {
+        self.config_mut().max_bytes = Some(n);
+    }
 
-            for day in resp["data"].members() {
-                let mut i = 0;
-                let mut datum = vec![];
-                for metric in &self.metrics {
-                    let m = match metric {
-                        Metric::AreaCode(_) => Metric::AreaCode(day[i].to_string()),
-                        Metric::AreaName(_) => Metric::AreaName(day[i].to_string()),
-                        Metric::AreaType(_) => Metric::AreaType(match day[i].to_string().as_str() {
-                            "overview" => AreaType::Overview,
-                            "nation" => AreaType::Nation,
-                            "region" => AreaType::Region,
-                            "nhsRegion" => AreaType::NHSRegion,
-                            "utla" => AreaType::UTLA,
-                            "ltla" => AreaType::LTLA,
-                            s => panic!("Unknown area type ({}) provided by API. This likely means the API is a different version and probably incompatible.", s),
-                        }),
-                        Metric::CovidOccupiedMechanicalVentilatorBeds(_) => Metric::CovidOccupiedMechanicalVentilatorBeds(day[i].as_i32().unwrap()),
-                        Metric::CumulativeAdmissions(_) => Metric::CumulativeAdmissions(day[i].as_i32().unwrap()),
-                        Metric::CumulativeAdmissionsByAge(_) => Metric::CumulativeAdmissionsByAge(day[i].as_i32().unwrap()),
-                        Metric::CumulativeCasesByPublishDate(_) => Metric::CumulativeCasesByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::CumulativeCasesBySpecimenDateRange(_) => Metric::CumulativeCasesBySpecimenDateRange(day[i].as_i32().unwrap()),
-                        Metric::CumulativeDeathsWithin28DaysByPublishDate(_) => Metric::CumulativeDeathsWithin28DaysByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::CumulativePillarOneTestsByPublishDate(_) => Metric::CumulativePillarOneTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::CumulativePillarTwoTestsByPublishDate(_) => Metric::CumulativePillarTwoTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::CumulativePillarThreeTestsByPublishDate(_) => Metric::CumulativePillarThreeTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::CumulativePillarFourTestsByPublishDate(_) => Metric::CumulativePillarFourTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::CumulativeTestsByPublishDate(_) => Metric::CumulativeTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::Date(_) => Metric::Date(Date::parse(day[i].to_string(), "%F").unwrap()),
-                        Metric::FemaleCases(_) => Metric::FemaleCases(day[i].as_i32().unwrap()),
-                        Metric::Hash(_) => Metric::Hash(day[i].to_string()),
-                        Metric::HospitalCases(_) => Metric::HospitalCases(day[i].as_i32().unwrap()),
-                        Metric::MaleCases(_) => Metric::MaleCases(day[i].as_i32().unwrap()),
-                        Metric::NewAdmissions(_) => Metric::NewAdmissions(day[i].as_i32().unwrap()),
-                        Metric::NewCasesByPublishDate(_) => Metric::NewCasesByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::NewCasesBySpecimenDate(_) => Metric::NewCasesBySpecimenDate(day[i].as_i32().unwrap()),
-                        Metric::NewDeathsWithin28DaysByPublishDate(_) => Metric::NewDeathsWithin28DaysByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::NewPillarOneTestsByPublishDate(_) => Metric::NewPillarOneTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::NewPillarTwoTestsByPublishDate(_) => Metric::NewPillarTwoTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::NewPillarThreeTestsByPublishDate(_) => Metric::NewPillarThreeTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::NewPillarFourTestsByPublishDate(_) => Metric::NewPillarFourTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::NewTestsByPublishDate(_) => Metric::NewTestsByPublishDate(day[i].as_i32().unwrap()),
-                        Metric::PlannedCapacityByPublishDate(_) => Metric::PlannedCapacityByPublishDate(day[i].as_i32().unwrap()),
-                    };
-                    datum.push(m);
-                }
-                data.push(datum);
-                i += 1;
-            }
+    /// Sets the format in which the API is asked to respond (default: 
+    /// `Format::Json`).
+    pub fn set_format(&mut self, format: Format) {
+        self.config_mut().format = format;
+    }
 
-            if resp["pagination"]["next"].is_null() {
-                break;
-            } else {
-                page += 1
-            }
+    /// Sets the language (e.g., `en-GB`) the request asks the API to respond 
+    /// in, sent as the `Accept-Language` header. By default, no language is 
+    /// requested.
+    /// 
+    /// This only affects content produced by the API; this library's own 
+    /// error messages are always in English.
+    pub fn set_accept_language(&mut self, lang: &str) {
+        self.config_mut().accept_language = Some(lang.to_string());
+    }
+
+    /// Adds a metric to the request if it isn't already present, such as a 
+    /// key column which later processing relies on, without duplicating it.
+    pub fn ensure_metric(&mut self, kind: MetricKind) {
+        if !self.config.metrics.contains(&kind) {
+            self.push_metric(kind);
         }
+    }
 
-        Ok(data)
+    /// Sets the priority of the request's metrics, from most to least 
+    /// important, so that metrics beyond the most the API accepts in a 
+    /// request (five) are dropped rather than sent.
+    /// 
+    /// While a priority is set, adding a metric beyond the limit drops the 
+    /// lowest-priority metric, logging a warning naming it; metrics not in 
+    /// the priority are dropped before any which are, most recently added 
+    /// first. Metrics already beyond the limit are dropped when the priority 
+    /// is set. By default, all metrics are sent, and the API rejects a 
+    /// request for too many.
+    pub fn set_metric_priority(&mut self, order: Vec<MetricKind>) {
+        self.config_mut().metric_priority = Some(order);
+        self.prune_metrics();
     }
 
-    fn construct_url(&self, latest_by: &Option<Metric>, page: &u32) -> String {
-        let mut url = String::from(API_URL);
-        url.push_str(
-            format!(
-                "?filters={}&structure=[{}]&format=json&page={}",
-                self.filters_str(),
-                self.metrics_str(),
-                page
-            )
-            .as_str(),
+    fn push_metric(&mut self, kind: MetricKind) {
+        self.config_mut().metrics.push(kind);
+        self.prune_metrics();
+    }
+
+    /// Drops the lowest-priority metrics beyond the limit, if a priority is 
+    /// set.
+    fn prune_metrics(&mut self) {
+        let priority = match &self.config.metric_priority {
+            Some(priority) if self.config.metrics.len() > MAX_STRUCTURE_METRICS => priority,
+            _ => return,
+        };
+        let rank = |m: &MetricKind| priority.iter().position(|p| p == m).unwrap_or(priority.len());
+
+        let mut order: Vec<usize> = (0..self.config.metrics.len()).collect();
+        order.sort_by_key(|i| rank(&self.config.metrics[*i]));
+        let dropped = order.split_off(MAX_STRUCTURE_METRICS);
+
+        log::warn!(
+            "Dropping {} to keep within the API's limit of {} metrics",
+            dropped
+                .iter()
+                .map(|i| self.config.metrics[*i].name())
+                .collect::<Vec<_>>()
+                .join(", "),
+            MAX_STRUCTURE_METRICS
         );
+        let mut i = 0;
+        self.config_mut().metrics.retain(|_| {
+            i += 1;
+            !dropped.contains(&(i - 1))
+        });
+    }
 
-        if let Option::Some(m) = latest_by {
-            url.push_str(format!("&latestBy={}", metric_to_str(m)).as_str());
-        }
+    /// Sets the Transport used to send the request, in place of the default 
+    /// `HttpTransport`.
+    pub fn set_transport(&mut self, transport: Arc<dyn Transport>) {
+        self.config_mut().transport = Some(transport);
+    }
 
-        url
+    /// Sends the request with the provided reqwest client, for configuration 
+    /// (such as timeouts, proxies, or TLS roots) not otherwise exposed by 
+    /// this library. This replaces any Transport set with `set_transport`.
+    pub fn with_client(&mut self, client: reqwest::blocking::Client) {
+        self.config_mut().transport = Some(Arc::new(HttpTransport::with_client(client)));
     }
 
-    fn filters_str(&self) -> String {
-        let mut pairs = String::new();
-
-        let mut multiple_filters = false;
-        for filter in &self.filters {
-            // if we're past the first filter, we should add a semi-colon before appending our new filter.
-            if multiple_filters {
-                pairs.push(';');
-            }
-            multiple_filters = true;
-
-            let value = match &filter.value {
-                FilterValue::AreaType(t) => match t {
-                    AreaType::Overview => String::from("overview"),
-                    AreaType::Nation => String::from("nation"),
-                    AreaType::Region => String::from("region"),
-                    AreaType::NHSRegion => String::from("nhsRegion"),
-                    AreaType::UTLA => String::from("utla"),
-                    AreaType::LTLA => String::from("ltla"),
-                },
-                FilterValue::AreaName(n) => n.to_string(),
-                FilterValue::AreaCode(c) => c.to_string(),
-                FilterValue::Date(d) => d.format("%Y-%m-%d"),
-            };
+    /// Sets whether a request spanning multiple pages may return partial 
+    /// data (default: false).
+    /// 
+    /// By default, an error fetching any page causes the whole request to 
+    /// fail, so a successful result is always complete. If partial data is 
+    /// allowed, an error fetching any page after the first instead ends the 
+    /// request early, returning the days from the pages already fetched and 
+    /// logging a warning; the result is then silently missing the older 
+    /// days, so should only be used where incomplete data is acceptable. An 
+    /// error fetching the first page is always returned.
+    pub fn set_partial_ok(&mut self, partial_ok: bool) {
+        self.config_mut().partial_ok = partial_ok;
+    }
 
-            pairs.push_str(format!("{}={}", filter.metric, value).as_str());
-        }
+    /// Sets whether the metrics are listed in the request's structure sorted 
+    /// by API name, rather than in the order they were added (default: 
+    /// false), so that requests for the same metrics have the same URL 
+    /// however they were built, for caching and comparing URLs.
+    /// 
+    /// The days returned still list the metrics in the order they were 
+    /// added.
+    pub fn set_canonical_metric_order(&mut self, canonical: bool) {
+        self.config_mut().canonical_order = canonical;
+    }
 
-        pairs
+    /// Sets whether the request is checked more strictly before it is sent 
+    /// (default: false), to catch mistakes which would otherwise only show 
+    /// up as `Error::NoData`.
+    /// 
+    /// In strict mode, a request with a Date Filter after today's date (in 
+    /// the UK, whose days the API follows), for which there can't yet be any data, fails with 
+    /// `Error::FutureDate`, and one with an AreaCode Filter whose code isn't 
+    /// of the form used for areas of the request's AreaType (such as an LTLA 
+    /// code for a nation) fails with `Error::InconsistentFilters`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.config_mut().strict = strict;
     }
 
-    fn metrics_str(&self) -> String {
-        let mut s = String::new();
+    /// Sets whether the request keeps the cookies set by the API's responses, 
+    /// sending them with the request's later pages (default: false), for 
+    /// APIs behind gateways which issue a session cookie that must be echoed 
+    /// back. Requires the `cookies` feature.
+    /// 
+    /// Each fetch starts a new session, with no cookies. The cookies are 
+    /// kept by wrapping the request's Transport in a 
+    /// `transport::CookieTransport`.
+    #[cfg(feature = "cookies")]
+    pub fn set_cookie_store(&mut self, enabled: bool) {
+        self.config_mut().cookie_store = enabled;
+    }
 
-        let mut multiple_metrics = false;
-        for metric in &self.metrics {
-            if multiple_metrics {
-                s.push_str(", ");
-            }
-            multiple_metrics = true;
+    /// Sets whether the request records the status, timing, and size of each 
+    /// page it fetches (default: false), for profiling with 
+    /// `last_diagnostics`.
+    /// 
+    /// Clones of the request made while diagnostics are enabled also record 
+    /// their pages, each in its own record, which each of its fetches 
+    /// replaces as it finishes. The concurrent fetches made by methods such as `ratio_to` and 
+    /// `Client::get_for_areas` are recorded together as a single fetch, in 
+    /// the order the fetches were made in.
+    pub fn set_diagnostics(&mut self, enabled: bool) {
+        self.diagnostics = if enabled {
+            Some(Arc::new(std::sync::Mutex::new(vec![])))
+        } else {
+            None
+        };
+    }
 
-            s.push_str(format!("%22{}%22", metric_to_str(metric)).as_str());
+    /// Returns the diagnostics recorded for each page fetched by the most 
+    /// recent fetch, in the order they were fetched, or nothing if 
+    /// diagnostics aren't enabled with `set_diagnostics`.
+    pub fn last_diagnostics(&self) -> Vec<PageDiagnostic> {
+        match &self.diagnostics {
+            Some(d) => d.lock().unwrap().clone(),
+            None => vec![],
         }
+    }
 
-        s
+    /// Returns the time the data was last updated, as given by the 
+    /// `lastUpdate` (or `release`) field of the most recent fetch's response, 
+    /// if it had one, for knowing the vintage of the data without a separate 
+    /// request.
+    /// 
+    /// The time is returned as the API gave it, typically an ISO-8601 
+    /// timestamp. Each clone of the request has its own record, which each 
+    /// of its fetches replaces as it finishes. Of the concurrent fetches made by methods such 
+    /// as `ratio_to`, the first to give a time is recorded, which for 
+    /// `ratio_to` is the fetch for the request's own area.
+    pub fn last_update(&self) -> Option<String> {
+        self.last_update.lock().unwrap().clone()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Executes the request and returns the requested Metrics in a Vector, 
+    /// which is itself encapsulated in another Vector storing the Metric 
+    /// Vectors (called Datums) for each day.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get(&self) -> Result<Data, Error> {
+        self.get_from(None)
+    }
 
-    #[test]
+    /// Executes the request as for `get`, using the provided response as the 
+    /// first page rather than fetching it again, if one is provided, and 
+    /// fetching the remaining pages through the Transport it was fetched 
+    /// through.
+    fn get_from(&self, first: Option<(transport::Response, &dyn Transport)>) -> Result<Data, Error> {
+        match self.get_unwrapped(first) {
+            Err(e) if self.config.error_context => Err(self.with_context(e)),
+            data => data,
+        }
+    }
+
+    fn get_unwrapped(&self, first: Option<(transport::Response, &dyn Transport)>) -> Result<Data, Error> {
+        let executed = match first {
+            Some(first) => self.execute_from(first),
+            None => self.execute(Option::None),
+        };
+        let mut data = match executed {
+            Err(Error::NoData) if self.config.suggest_area_names => return Err(self.did_you_mean()),
+            data => data?,
+        };
+        for (primary, fallback) in &self.config.fallbacks {
+            self.apply_fallback(&mut data, primary, fallback)?;
+        }
+        self.finish(&mut data);
+        Ok(data)
+    }
+
+    /// Fills the days on which the primary metric has no value from the 
+    /// request with the fallback metric in its place, as set with 
+    /// `add_metric_with_fallback`.
+    fn apply_fallback(&self, data: &mut Data, primary: &MetricKind, fallback: &MetricKind) -> Result<(), Error> {
+        let missing = |day: &Datum| data::find(day, primary).and_then(Metric::as_f64).is_none();
+        if !data.iter().any(missing) {
+            return Ok(());
+        }
+
+        let mut req = self.derived();
+        req.config_mut().fallbacks.clear();
+        for metric in req.config_mut().metrics.iter_mut().filter(|m| *m == primary) {
+            *metric = fallback.clone();
+        }
+
+        // Reports the fallback's value as the primary metric.
+        let as_primary = |metric: &Metric| {
+            let value = metric
+                .as_i64()
+                .map(json::JsonValue::from)
+                .or_else(|| metric.as_f64().map(json::JsonValue::from))
+                .unwrap_or(json::JsonValue::Null);
+            primary.parse(&value).ok()
+        };
+
+        let mut by_date: BTreeMap<Date, Datum> = BTreeMap::new();
+        for day in req.execute(None)? {
+            if let Some(date) = data::date_of(&day) {
+                by_date.entry(date).or_insert(day);
+            }
+        }
+        for day in data.iter_mut() {
+            let found = data::date_of(day).and_then(|date| by_date.remove(&date));
+            let value = found.as_ref().and_then(|f| data::find(f, fallback)).and_then(as_primary);
+            if let (true, Some(value)) = (missing(day), value) {
+                for metric in day.iter_mut().filter(|m| m.kind() == *primary) {
+                    *metric = value.clone();
+                }
+            }
+        }
+
+        // Days which only the fallback covers.
+        if !by_date.is_empty() {
+            for day in by_date.into_values() {
+                data.push(
+                    day.iter()
+                        .map(|m| {
+                            if m.kind() == *fallback {
+                                as_primary(m).unwrap_or_else(|| m.clone())
+                            } else {
+                                m.clone()
+                            }
+                        })
+                        .collect(),
+                );
+            }
+            data.sort_by_key(|day| std::cmp::Reverse(data::date_of(day)));
+        }
+
+        Ok(())
+    }
+
+    /// Lists the areas of the requested AreaType with names close to that of 
+    /// the request's AreaName Filter, for a request which found no data. 
+    /// Returns `NoData` if there is no AreaName Filter, or the areas can't be 
+    /// listed.
+    fn did_you_mean(&self) -> Error {
+        let tried = match self.filters.iter().find_map(|f| match &f.value {
+            FilterValue::AreaName(name) => Some(name.clone()),
+            _ => None,
+        }) {
+            Some(name) => name,
+            None => return Error::NoData,
+        };
+
+        let mut req = self.derived();
+        req.filters.retain(|f| !matches!(f.value, FilterValue::AreaName(_)));
+        let areas = match req.areas() {
+            Ok(areas) => areas,
+            Err(_) => return Error::NoData,
+        };
+
+        // Allow roughly one typo in every three letters.
+        let tolerance = (tried.chars().count() / 3).max(1);
+        let mut close: Vec<(usize, String)> = areas
+            .into_iter()
+            .map(|(_, name)| (levenshtein(&tried.to_lowercase(), &name.to_lowercase()), name))
+            .filter(|(distance, _)| *distance <= tolerance)
+            .collect();
+        close.sort();
+
+        Error::NoDataDidYouMean {
+            tried,
+            suggestions: close.into_iter().take(3).map(|(_, name)| name).collect(),
+        }
+    }
+
+    /// Executes the request and returns the requested Metrics in a Vector, 
+    /// which is itself encapsulated in another Vector storing the Metric 
+    /// Vectors (called Datums) for each day. 
+    /// 
+    /// For this function, only one day's data will be provided, and will be 
+    /// that of the latest day available for the supplied Metric; thus, the 
+    /// outermost Vector will only contain one element.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_latest_by_metric(&self, metric: Metric) -> Result<Data, Error> {
+        self.execute(Option::Some(metric.kind()))
+    }
+
+    /// Checks that each of the request's metrics returns data, with a single 
+    /// small fetch of the latest day, to catch misspelt or unavailable 
+    /// metrics before a long-running job.
+    /// 
+    /// If the API rejects the request outright (for instance, because of an 
+    /// unknown metric), each metric is then fetched alone so that the error 
+    /// can be attributed to the metrics which caused it.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error other than rejecting the metrics.
+    pub fn validate(&self) -> Result<ValidationReport, Error> {
+        let mut req = self.derived();
+        req.config_mut().named_structure = false;
+        req.config_mut().single_page = true;
+
+        let metrics = match req.latest_values() {
+            Ok(present) => self
+                .config
+                .metrics
+                .iter()
+                .cloned()
+                .zip(present.into_iter().map(MetricStatus::from))
+                .collect(),
+            Err(Error::ApiError { .. }) => {
+                let mut metrics = vec![];
+                for metric in &self.config.metrics {
+                    req.config_mut().metrics = vec![metric.clone()];
+                    let status = match req.latest_values() {
+                        Ok(present) => MetricStatus::from(present[0]),
+                        Err(e @ Error::ApiError { .. }) => MetricStatus::Failed(e),
+                        Err(e) => return Err(e),
+                    };
+                    metrics.push((metric.clone(), status));
+                }
+                metrics
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(ValidationReport { metrics })
+    }
+
+    /// Fetches the latest day, returning whether each metric has a value.
+    fn latest_values(&self) -> Result<Vec<bool>, Error> {
+        let mut present = vec![false; self.config.metrics.len()];
+        let columns = self.columns();
+        let result = self.paginate_raw(&Some(MetricKind::Date), |_, _, resp| {
+            for (i, p) in present.iter_mut().enumerate() {
+                *p = !resp["data"][0][columns[i]].is_null();
+            }
+            Ok(false)
+        });
+
+        match result {
+            Ok(()) | Err(Error::NoData) => Ok(present),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Executes the request repeatedly until the latest day has a value for 
+    /// the provided metric, or the timeout elapses, for polling around the 
+    /// daily release, when some metrics are briefly null while others have 
+    /// been published.
+    /// 
+    /// The metric and the `date` metric are added to the request if they 
+    /// aren't already present. Requests are sent a tenth of the timeout 
+    /// apart (but at most a minute apart), and a request refused by the rate 
+    /// limiter is retried at the next attempt. Once the timeout elapses, the 
+    /// days from the last attempt are returned, even if the metric is still 
+    /// null for the latest of them.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error, including `Error::TooManyRequests` if 
+    /// the last attempt was rate limited.
+    pub fn wait_for_metric(&self, metric: MetricKind, timeout: std::time::Duration) -> Result<Data, Error> {
+        let mut req = self.derived();
+        req.ensure_metric(MetricKind::Date);
+        req.ensure_metric(metric.clone());
+
+        let interval = (timeout / 10).min(std::time::Duration::from_secs(60));
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            let result = req.get();
+            if let Ok(data) = &result {
+                if latest_has_value(data, &metric) {
+                    return result;
+                }
+            }
+            match result {
+                Ok(_) | Err(Error::TooManyRequests(_)) if std::time::Instant::now() + interval < deadline => {
+                    std::thread::sleep(interval)
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Executes the request as with `get`, but returning the days of each 
+    /// page separately, paired with the URL which the page was fetched from.
+    /// 
+    /// This allows each day to be traced back to the exact request which 
+    /// produced it, for instance for an audit trail.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_pages(&self) -> Result<Vec<(String, Data)>, Error> {
+        let mut pages = vec![];
+        self.paginate(&None, |url, page| {
+            pages.push((url.to_string(), page));
+            true
+        })?;
+
+        Ok(pages)
+    }
+
+    /// Executes the request, passing each day to the callback as it is 
+    /// parsed rather than collecting them, for processing results too large 
+    /// to hold in memory. The callback may stop the request early by 
+    /// returning `ControlFlow::Break`, in which case no further pages are 
+    /// fetched.
+    /// 
+    /// Days are passed in the order the API returns them, and `set_order` 
+    /// and `set_drop_incomplete_trailing` don't apply.
+    /// 
+    /// ## Memory
+    /// 
+    /// Only one page is held at a time: each page is fetched once every day 
+    /// of the previous page has been passed to the callback, and each day is 
+    /// parsed as it is passed and dropped after, so memory use is bounded by 
+    /// the size of a single page however many pages the request spans. 
+    /// 
+    /// A `Format::JsonL` response isn't paginated, so its whole body is 
+    /// held, but each line is only parsed as its day is passed to the 
+    /// callback, so only one day is held parsed at a time.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error. Days passed to the callback before the 
+    /// error are not affected.
+    pub fn for_each<F>(&self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&Datum) -> std::ops::ControlFlow<()>,
+    {
+        if self.config.format == Format::JsonL {
+            return self.for_each_line(f);
+        }
+
+        self.paginate_raw(&None, |_, _, resp| {
+            for day in resp["data"].members() {
+                let datum: Datum = self.parse_day(day)?.into_iter().map(|(metric, _)| metric).collect();
+                if f(&datum).is_break() {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })
+    }
+
+    /// Executes a `Format::JsonL` request for `for_each`, parsing each line 
+    /// of the response only as its day is passed to the callback.
+    fn for_each_line<F>(&self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&Datum) -> std::ops::ControlFlow<()>,
+    {
+        self.check()?;
+
+        let mut record = FetchRecord::default();
+        let res = self.send_page(self.transport().as_ref(), &self.construct_url(&None, &1), &mut record);
+        self.record(record);
+        let res = res?;
+        if matches!(self.config.max_bytes, Some(max) if res.body.len() > max) {
+            return Err(Error::ResponseTooLarge);
+        }
+
+        for day in jsonl_days(json_body(&res)?) {
+            let datum: Datum = self.parse_day(&day?)?.into_iter().map(|(metric, _)| metric).collect();
+            if f(&datum).is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetches the request repeatedly, every `interval`, passing its days to 
+    /// the callback whenever they have changed since the last fetch, such as 
+    /// to keep a local copy of the data up to date. The days are always 
+    /// passed to the callback on the first fetch.
+    /// 
+    /// Each poll first requests only the first page, conditionally on the 
+    /// `ETag` and `Last-Modified` headers of the previous poll's response, 
+    /// and the rest of the request is only fetched (as with `get`, reusing 
+    /// the first page) if the API reports that the data has changed: that 
+    /// is, it doesn't respond with `304 Not Modified`, and the first page's 
+    /// body differs from the last. 
+    /// 
+    /// The request is polled `iterations` times, or forever if `None`, 
+    /// waiting for the interval between each poll.
+    /// 
+    /// With `set_cookie_store`, every poll and page is sent in one session, 
+    /// so cookies set by the polled first page are sent with the rest.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error for any poll, ending the polling.
+    pub fn poll<F>(&self, interval: std::time::Duration, iterations: Option<usize>, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(Data),
+    {
+        self.check()?;
+        let transport = self.transport();
+
+        let url = self.url();
+        let mut etag: Option<String> = None;
+        let mut last_modified: Option<String> = None;
+        let mut last_body: Option<Vec<u8>> = None;
+        let mut polls = 0;
+        loop {
+            let mut headers = self.headers();
+            if let Some(etag) = &etag {
+                headers.push(("If-None-Match", etag));
+            }
+            if let Some(last_modified) = &last_modified {
+                headers.push(("If-Modified-Since", last_modified));
+            }
+
+            let res = transport.get(&url, &headers)?;
+            if res.status != 304 {
+                check_status(&res)?;
+                if last_body.as_ref() != Some(&res.body) {
+                    etag = header(&res, "etag").map(String::from);
+                    last_modified = header(&res, "last-modified").map(String::from);
+                    last_body = Some(res.body.clone());
+                    f(self.get_from(Some((res, transport.as_ref())))?);
+                }
+            }
+
+            polls += 1;
+            if iterations.is_some_and(|n| polls >= n) {
+                return Ok(());
+            }
+            std::thread::sleep(interval);
+        }
+    }
+
+    /// Fetches only the provided page of the request (counting from 1), 
+    /// returning its days along with the numbers of the previous and next 
+    /// pages, if there are any, for paging in either direction from an 
+    /// arbitrary page.
+    /// 
+    /// As for `get_pages`, `set_order` and `set_drop_incomplete_trailing` 
+    /// don't apply, so the days are as the API returns them.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_page(&self, page: u32) -> Result<(Data, Option<u32>, Option<u32>), Error> {
+        self.check()?;
+        let transport = match &self.config.transport {
+            Some(t) => t.clone(),
+            None => Arc::new(HttpTransport::new()),
+        };
+
+        let mut record = FetchRecord::default();
+        let fetched = self.fetch_page(transport.as_ref(), &self.construct_url(&None, &page), &mut record);
+        self.record(record);
+        let (_, resp, _) = fetched?;
+        Ok((
+            self.parse_data(&resp)?,
+            page_number(&resp["pagination"]["previous"]),
+            page_number(&resp["pagination"]["next"]),
+        ))
+    }
+
+    /// Executes the request, deserializing each day directly into the 
+    /// caller's type with `serde`, bypassing `Metric` entirely. Requires the 
+    /// `serde` feature.
+    /// 
+    /// Each day is deserialized from an object keyed by the API names of the 
+    /// requested metrics (e.g., `{"date": "2021-01-01", "newCasesByPublishDate": 
+    /// 120}`), regardless of `set_named_structure`. Days are returned in the 
+    /// order the API returns them, and `set_order` and 
+    /// `set_drop_incomplete_trailing` don't apply.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error, or `Error::ParseError` if a day could not 
+    /// be deserialized.
+    #[cfg(feature = "serde")]
+    pub fn get_as<T: serde::de::DeserializeOwned>(&self) -> Result<Vec<T>, Error> {
+        let mut req = self.derived();
+        req.config_mut().named_structure = true;
+
+        let mut days = vec![];
+        req.paginate_raw(&None, |_, _, resp| {
+            for day in resp["data"].members() {
+                let text = day.dump();
+                days.push(serde_json::from_str(&text).map_err(|e| {
+                    Error::ParseError(format!("Could not deserialize day: {} (day: {})", e, snippet(&text)))
+                })?);
+            }
+            Ok(true)
+        })?;
+
+        Ok(days)
+    }
+
+    /// Executes the request as with `get`, but pairing each Metric with the 
+    /// raw JSON value it was parsed from, for checking the parsing when 
+    /// values look wrong.
+    /// 
+    /// The raw value is exactly as the API sent it, so is `null` where a 
+    /// default set with `set_null_default` was used.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_verbose(&self) -> Result<Vec<Vec<(Metric, json::JsonValue)>>, Error> {
+        let mut days = vec![];
+        self.paginate_raw(&None, |_, _, resp| {
+            for day in resp["data"].members() {
+                let datum = self.parse_day(day)?;
+                days.push(datum.into_iter().map(|(metric, raw)| (metric, raw.clone())).collect());
+            }
+            Ok(true)
+        })?;
+
+        Ok(days)
+    }
+
+    /// Executes the request as with `get`, but requesting the largest pages 
+    /// the API allows so that the complete history is fetched in as few 
+    /// round trips as possible.
+    /// 
+    /// Each page may be large, so this can transfer a lot of data at once; 
+    /// it is intended for full-history downloads.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_all(&self) -> Result<Data, Error> {
+        let mut req = self.derived();
+        req.config_mut().page_size = Some(MAX_PAGE_SIZE);
+        req.get()
+    }
+
+    /// Executes the request for a single numeric metric, returning each day's 
+    /// value paired with its date.
+    /// 
+    /// The `date` metric is requested automatically alongside the provided 
+    /// metric, and any other Metrics added to this request are ignored. Days 
+    /// for which the API reported no value have a `value` of `None`, as do 
+    /// all days if the metric is not numeric.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn fetch_single_i32(&self, metric: MetricKind) -> Result<Vec<DatedValue>, Error> {
+        let mut req = self.derived();
+        req.config_mut().metrics = vec![MetricKind::Date];
+        req.ensure_metric(metric.clone());
+
+        Ok(dated_values(&req.get()?, &metric))
+    }
+
+    /// Fetches the percentage of people in the named area of the request's 
+    /// AreaType who have had the provided dose, for each date, in 
+    /// chronological order.
+    /// 
+    /// Days for which the API reported no value are left out, and any other 
+    /// Metrics added to this request are ignored.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn vaccination_uptake(&self, area: &str, dose: Dose) -> Result<Vec<(Date, f64)>, Error> {
+        let metric = dose.uptake_metric();
+        let mut req = self.with_area_name(area);
+        req.config_mut().metrics = vec![MetricKind::Date];
+        req.ensure_metric(metric.clone());
+
+        let mut uptake: Vec<(Date, f64)> = req
+            .get()?
+            .iter()
+            .filter_map(|day| Some((data::date_of(day)?, data::find(day, &metric)?.as_f64()?)))
+            .collect();
+        uptake.sort_by_key(|(date, _)| *date);
+        Ok(uptake)
+    }
+
+    /// Fetches a numeric metric for the request's area and for another 
+    /// area of the same AreaType, concurrently, returning the request's 
+    /// area's value as a ratio of the other's for each date in either, in 
+    /// chronological order.
+    /// 
+    /// The ratio is `None` where either area has no value for the date, or 
+    /// the other area's value is zero. Any other Metrics added to this 
+    /// request are ignored.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error for either area.
+    pub fn ratio_to(&self, other_area: &str, metric: MetricKind) -> Result<Vec<(Date, Option<f64>)>, Error> {
+        let mut req = self.clone();
+        req.config_mut().metrics = vec![MetricKind::Date];
+        req.ensure_metric(metric.clone());
+        let other = req.with_area_name(other_area).clone();
+
+        let (data, other_data) = std::thread::scope(|s| {
+            let handle = s.spawn(|| other.get());
+            (req.get(), handle.join().unwrap())
+        });
+        self.record_clones(&[req, other]);
+
+        let by_date = |days: Data| -> BTreeMap<Date, Option<f64>> {
+            days.iter()
+                .filter_map(|day| Some((data::date_of(day)?, data::find(day, &metric).and_then(Metric::as_f64))))
+                .collect()
+        };
+        let (values, other) = (by_date(data?), by_date(other_data?));
+        let dates: std::collections::BTreeSet<&Date> = values.keys().chain(other.keys()).collect();
+
+        Ok(dates
+            .into_iter()
+            .map(|date| match (values.get(date).copied().flatten(), other.get(date).copied().flatten()) {
+                (Some(a), Some(b)) if b != 0.0 => (*date, Some(a / b)),
+                _ => (*date, None),
+            })
+            .collect())
+    }
+
+    /// Fetches a daily new metric, such as `NewCasesByPublishDate`, and 
+    /// returns its seven-day rolling rate per 100,000 people for each date, 
+    /// given the population of the request's area (the dashboard's headline 
+    /// figure).
+    /// 
+    /// Rates are only returned for dates whose trailing seven days all have 
+    /// values, as described for `series::rolling_rate`, in chronological 
+    /// order. Any other Metrics added to this request are ignored.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn rolling_rate_7day(&self, metric: MetricKind, population: u32) -> Result<Vec<(Date, f64)>, Error> {
+        let series: Vec<(Date, i32)> = self
+            .fetch_single_i32(metric)?
+            .into_iter()
+            .filter_map(|d| Some((d.date, d.value?)))
+            .collect();
+
+        Ok(series::rolling_rate(&series, 7, population))
+    }
+
+    /// Returns the earliest date on which the numeric metric has a value 
+    /// for the request's areas, or `None` if it never has one, to find where 
+    /// the metric's data begins.
+    /// 
+    /// The metric's full history is fetched, and any other Metrics added to 
+    /// this request are ignored.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn earliest_date_for(&self, metric: MetricKind) -> Result<Option<Date>, Error> {
+        let mut req = self.derived();
+        req.config_mut().metrics = vec![MetricKind::Date];
+        req.ensure_metric(metric.clone());
+        req.config_mut().page_size = Some(MAX_PAGE_SIZE);
+
+        Ok(req
+            .get()?
+            .iter()
+            .filter(|day| data::find(day, &metric).and_then(Metric::as_f64).is_some())
+            .filter_map(data::date_of)
+            .min())
+    }
+
+    /// Executes the request, returning only the days strictly after `last`.
+    /// 
+    /// As the API returns the newest days first, pages are only fetched 
+    /// until one reaches a day on or before `last`, so polling for new days 
+    /// transfers far less than a full `get`. The `date` metric is added to 
+    /// the request if it isn't already present.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_since(&self, last: Date) -> Result<Data, Error> {
+        let mut req = self.derived();
+        req.ensure_metric(MetricKind::Date);
+
+        let mut data = vec![];
+        req.paginate(&None, |_, page| {
+            let mut reached_last = false;
+            for day in page {
+                if day.iter().any(|m| matches!(m, Metric::Date(d) if *d > last)) {
+                    data.push(day);
+                } else {
+                    reached_last = true;
+                }
+            }
+            !reached_last
+        })?;
+
+        Ok(data)
+    }
+
+    /// Reconstructs a UK total for the numeric metric by fetching it for 
+    /// each of the four nations (concurrently) and summing them by date, 
+    /// returning the totals chronologically.
+    /// 
+    /// Any area Filters on this request are replaced, but other Filters (such 
+    /// as a Date) apply to each nation. The total is `None` for dates on which 
+    /// any nation has no value.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error for any nation.
+    pub fn uk_total_from_nations(&self, metric: MetricKind) -> Result<Vec<(Date, Option<i32>)>, Error> {
+        let clones: Vec<Request> = Nation::ALL
+            .iter()
+            .map(|nation| {
+                let mut req = self.clone();
+                req.filters
+                    .retain(|f| !matches!(f.value, FilterValue::AreaType(_) | FilterValue::AreaName(_) | FilterValue::AreaCode(_)));
+                req.filters.insert(0, Filter::new(FilterValue::AreaType(AreaType::Nation)));
+                req.add_filter(Filter::new((*nation).into()));
+                req
+            })
+            .collect();
+        let results: Vec<Result<Vec<DatedValue>, Error>> = std::thread::scope(|s| {
+            let handles: Vec<_> = clones
+                .iter()
+                .map(|req| {
+                    let metric = metric.clone();
+                    s.spawn(move || req.fetch_single_i32(metric))
+                })
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        self.record_clones(&clones);
+
+        let mut totals: BTreeMap<Date, Vec<Option<i32>>> = BTreeMap::new();
+        for result in results {
+            for day in result? {
+                totals.entry(day.date).or_default().push(day.value);
+            }
+        }
+
+        Ok(totals
+            .into_iter()
+            .map(|(date, values)| {
+                let total = if values.len() == Nation::ALL.len() {
+                    values.into_iter().sum::<Option<i32>>()
+                } else {
+                    None
+                };
+                (date, total)
+            })
+            .collect())
+    }
+
+    /// Ranks the areas of the provided AreaType by their latest value of the 
+    /// numeric metric, returning the names and values of the `top` highest, 
+    /// highest first, as for a leaderboard of the areas with the most cases.
+    /// 
+    /// The latest day is fetched with `latestBy`, so is the latest date with 
+    /// a value for the metric. Any area Filters on this request are replaced, 
+    /// but other Filters apply, and any other Metrics added to this request 
+    /// are ignored. Areas without a value are left out, and areas with equal 
+    /// values are ranked by name.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn rank_areas(&self, metric: MetricKind, area_type: AreaType, top: usize) -> Result<Vec<(String, i32)>, Error> {
+        let mut req = self.derived();
+        req.filters
+            .retain(|f| !matches!(f.value, FilterValue::AreaType(_) | FilterValue::AreaName(_) | FilterValue::AreaCode(_)));
+        req.filters.insert(0, Filter::new(FilterValue::AreaType(area_type)));
+        req.config_mut().metrics = vec![MetricKind::AreaName];
+        req.ensure_metric(metric.clone());
+
+        let mut ranked: Vec<(String, i32)> = req
+            .execute(Some(metric.clone()))?
+            .iter()
+            .filter_map(|day| {
+                let name = day.iter().find_map(|m| match m {
+                    Metric::AreaName(name) => Some(name.clone()),
+                    _ => None,
+                })?;
+                Some((name, data::find(day, &metric)?.as_i32()?))
+            })
+            .collect();
+        ranked.sort_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.cmp(b)));
+        ranked.truncate(top);
+
+        Ok(ranked)
+    }
+
+    /// Fetches the request for every area of each of the provided area types 
+    /// (such as each nation and each region, for drilling down), with one 
+    /// request per area type sent concurrently, returning each area type's 
+    /// days keyed by its name as used by the API (such as `nation`).
+    /// 
+    /// The request's own area type and any area name or code filters are 
+    /// replaced; its other filters and metrics apply to every area type.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error for any of the area types.
+    pub fn get_for_area_types(&self, types: &[AreaType]) -> Result<HashMap<String, Data>, Error> {
+        let clones: Vec<Request> = types
+            .iter()
+            .map(|area_type| {
+                let mut req = self.clone();
+                req.filters.retain(|f| {
+                    !matches!(f.value, FilterValue::AreaType(_) | FilterValue::AreaName(_) | FilterValue::AreaCode(_))
+                });
+                req.filters.insert(0, Filter::new(FilterValue::AreaType(area_type.clone())));
+                req
+            })
+            .collect();
+        let results: Vec<(String, Result<Data, Error>)> = std::thread::scope(|s| {
+            let handles: Vec<_> = types
+                .iter()
+                .zip(&clones)
+                .map(|(area_type, req)| (area_type.name().to_string(), s.spawn(move || req.get())))
+                .collect();
+
+            handles.into_iter().map(|(name, h)| (name, h.join().unwrap())).collect()
+        });
+        self.record_clones(&clones);
+
+        results.into_iter().map(|(name, data)| Ok((name, data?))).collect()
+    }
+
+    /// Fetches more metrics for the request's area than the API accepts in 
+    /// a single request, by splitting them into groups small enough to be 
+    /// fetched together, fetching the groups concurrently, and merging the 
+    /// days by date.
+    /// 
+    /// Each day is returned with its `date` followed by the provided metrics 
+    /// which had a value for the day, in the provided order, and days are 
+    /// ordered as set with `set_order`. The request's own metrics are 
+    /// ignored. The request should cover a single area, as days are merged 
+    /// by date alone.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error for any of the groups.
+    pub fn get_many_metrics(&self, metrics: &[MetricKind]) -> Result<Data, Error> {
+        let metrics: Vec<&MetricKind> = metrics.iter().filter(|m| **m != MetricKind::Date).collect();
+
+        let clones: Vec<Request> = metrics
+            .chunks(MAX_STRUCTURE_METRICS - 1)
+            .map(|chunk| {
+                let mut req = self.clone();
+                req.config_mut().metrics = vec![MetricKind::Date];
+                for metric in chunk {
+                    req.ensure_metric((*metric).clone());
+                }
+                req
+            })
+            .collect();
+        let results: Vec<Result<Data, Error>> = std::thread::scope(|s| {
+            let handles: Vec<_> = clones.iter().map(|req| s.spawn(move || req.get())).collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        self.record_clones(&clones);
+
+        let mut days: BTreeMap<Date, Datum> = BTreeMap::new();
+        for result in results {
+            for day in result? {
+                if let Some(date) = data::date_of(&day) {
+                    days.entry(date)
+                        .or_insert_with(|| vec![Metric::Date(date)])
+                        .extend(day.into_iter().filter(|m| m.kind() != MetricKind::Date));
+                }
+            }
+        }
+
+        let mut data: Data = days.into_values().collect();
+        if self.config.order == Order::ReverseChronological {
+            data.reverse();
+        }
+        Ok(data)
+    }
+
+    /// Executes the request, returning only the `days` newest days.
+    /// 
+    /// As the API returns the newest days first, pages are only fetched 
+    /// until `days` days have been returned, so a recent window transfers far 
+    /// less than a full `get`.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_recent(&self, days: usize) -> Result<Data, Error> {
+        let mut data = vec![];
+        if days == 0 {
+            return Ok(data);
+        }
+
+        self.paginate(&None, |_, page| {
+            data.extend(page);
+            data.len() < days
+        })?;
+        data.truncate(days);
+
+        Ok(data)
+    }
+
+    /// Executes the request, returning only the days on the provided dates 
+    /// (such as every Monday), in the order set with `set_order`.
+    /// 
+    /// For up to 4 dates, each date is fetched with its own Date Filter, 
+    /// concurrently; for more, the whole series is fetched once (in the 
+    /// largest pages) and filtered. The `date` metric is added to the request 
+    /// if it isn't already present. Dates without data are left out.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_on_dates(&self, dates: &[Date]) -> Result<Data, Error> {
+        match self.get_on_dates_unwrapped(dates) {
+            Err(e) if self.config.error_context => Err(self.with_context(e)),
+            data => data,
+        }
+    }
+
+    fn get_on_dates_unwrapped(&self, dates: &[Date]) -> Result<Data, Error> {
+        let mut req = self.derived();
+        req.ensure_metric(MetricKind::Date);
+        req.config_mut().drop_trailing = 0;
+        // A date without data is left out rather than being reported, so the 
+        // clones' errors are matched as they are, and context is only added 
+        // once, to the error returned.
+        req.config_mut().error_context = false;
+        req.config_mut().suggest_area_names = false;
+
+        let wanted = |day: &Datum| data::date_of(day).is_some_and(|d| dates.contains(&d));
+        let mut data: Data = if dates.len() <= MAX_PER_DATE_REQUESTS {
+            let clones: Vec<Request> = dates
+                .iter()
+                .map(|date| {
+                    let mut req = req.clone();
+                    req.add_filter(Filter::new(FilterValue::Date(*date)));
+                    req
+                })
+                .collect();
+            let results: Vec<Result<Data, Error>> = std::thread::scope(|s| {
+                let handles: Vec<_> = clones.iter().map(|req| s.spawn(move || req.get())).collect();
+
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            req.record_clones(&clones);
+
+            let mut data = vec![];
+            for result in results {
+                match result {
+                    Ok(days) => data.extend(days),
+                    Err(Error::NoData) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            data
+        } else {
+            req.config_mut().page_size = Some(MAX_PAGE_SIZE);
+            req.execute(None)?
+        };
+
+        data.retain(wanted);
+        data.sort_by_key(|day| std::cmp::Reverse(data::date_of(day)));
+        data.dedup_by_key(|day| data::date_of(day));
+        req.finish(&mut data);
+        Ok(data)
+    }
+
+    /// Lists every area of the provided AreaType, returning `(areaCode, 
+    /// areaName)` pairs in the order provided by the API, with each area 
+    /// code appearing once.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn list_areas(area_type: AreaType) -> Result<Vec<(String, String)>, Error> {
+        Request::new(area_type, Metric::AreaCode(String::new())).areas()
+    }
+
+    fn areas(&self) -> Result<Vec<(String, String)>, Error> {
+        let mut req = self.derived();
+        req.config_mut().metrics = vec![MetricKind::AreaCode, MetricKind::AreaName];
+
+        // Requesting only the latest day gives one row per area. NHS regions 
+        // only report healthcare metrics, so can't be listed by case counts.
+        let latest_by = match self.area_type() {
+            Some(AreaType::NHSRegion) => MetricKind::NewAdmissions,
+            _ => MetricKind::NewCasesByPublishDate,
+        };
+
+        let mut seen = std::collections::HashSet::new();
+        let mut areas = vec![];
+        for day in req.execute(Some(latest_by))? {
+            if let [Metric::AreaCode(code), Metric::AreaName(name)] = day.as_slice() {
+                if seen.insert(code.to_string()) {
+                    areas.push((code.to_string(), name.to_string()));
+                }
+            }
+        }
+
+        Ok(areas)
+    }
+
+    /// Lists the names of the metrics the API reports for areas of the given 
+    /// type, from the dashboard's metric discovery endpoint.
+    /// 
+    /// The names may be parsed with `MetricKind::from_str` to build requests 
+    /// for them; names unknown to this library become `MetricKind::Generic`.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error, or `Error::ParseError` if the response 
+    /// isn't a list of metrics.
+    pub fn available_metrics(area_type: AreaType) -> Result<Vec<String>, Error> {
+        Request::new(area_type, Metric::AreaCode(String::new())).metric_names()
+    }
+
+    fn metric_names(&self) -> Result<Vec<String>, Error> {
+        let area_type = self.area_type().map_or("overview", |t| t.name());
+        let transport = match &self.config.transport {
+            Some(t) => t.clone(),
+            None => Arc::new(HttpTransport::new()),
+        };
+
+        let res = transport.get(&format!("{}/{}", METRICS_URL, area_type), &self.headers())?;
+        check_status(&res)?;
+        let body = String::from_utf8_lossy(&res.body);
+        let resp = json::parse(&body)
+            .map_err(|e| Error::ParseError(format!("Error parsing JSON: {} (body: {})", e, snippet(&body))))?;
+
+        // Entries are either bare names or objects naming the metric.
+        if !resp.is_array() {
+            return Err(Error::ParseError(format!("Response is not a list of metrics: {}", snippet(&body))));
+        }
+        resp.members()
+            .map(|entry| {
+                entry
+                    .as_str()
+                    .or_else(|| entry["metric"].as_str())
+                    .map(String::from)
+                    .ok_or_else(|| Error::ParseError(format!("Unexpected metric entry: {}", entry.dump())))
+            })
+            .collect()
+    }
+
+    /// Returns the request's configuration for modification, copying it 
+    /// first if it's shared with a clone.
+    fn config_mut(&mut self) -> &mut Config {
+        Arc::make_mut(&mut self.config)
+    }
+
+    /// Downloads the dashboard's pre-generated CSV of the full history of 
+    /// every area of the given type, which is far faster than paging through 
+    /// the API for whole-dataset pulls.
+    /// 
+    /// The downloads are snapshots, regenerated with each daily release, so 
+    /// may briefly lag the API, and their columns are fixed by the dashboard 
+    /// rather than chosen by a request. The CSV is returned as served.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the server returned an error, or `Error::ParseError` if the 
+    /// download isn't valid UTF-8.
+    pub fn bulk_download_csv(area_type: AreaType) -> Result<String, Error> {
+        Request::new(area_type, Metric::AreaCode(String::new())).download_csv()
+    }
+
+    fn download_csv(&self) -> Result<String, Error> {
+        let area_type = self.area_type().map_or("overview", |t| t.name());
+        let transport = match &self.config.transport {
+            Some(t) => t.clone(),
+            None => Arc::new(HttpTransport::new()),
+        };
+
+        let res = transport.get(&format!("{}/{}_latest.csv", DOWNLOADS_URL, area_type), &[("Accept", "text/csv")])?;
+        check_status(&res)?;
+        String::from_utf8(res.body).map_err(|e| Error::ParseError(format!("Download is not valid UTF-8: {}", e)))
+    }
+
+    /// Returns the AreaType the request is filtered to.
+    fn area_type(&self) -> Option<&AreaType> {
+        self.filters.iter().find_map(|f| match &f.value {
+            FilterValue::AreaType(t) => Some(t),
+            _ => None,
+        })
+    }
+
+    /// Wraps the error in `Error::WithContext`, describing the request.
+    fn with_context(&self, source: Error) -> Error {
+        let area = self
+            .filters
+            .iter()
+            .find_map(|f| match &f.value {
+                FilterValue::AreaName(name) => Some(name.clone()),
+                FilterValue::AreaCode(code) => Some(code.to_string()),
+                _ => None,
+            })
+            .or_else(|| self.area_type().map(|t| t.name().to_string()))
+            .unwrap_or_default();
+
+        Error::WithContext {
+            area,
+            metrics: self.config.metrics.iter().map(|m| m.name().to_string()).collect(),
+            source: Box::new(source),
+        }
+    }
+
+    /// Sends the request for its first page over HTTP, returning the 
+    /// `reqwest` response without reading its body, for full control over 
+    /// the response (such as streaming the body or reading every header).
+    /// 
+    /// Nothing is done with the response: its status isn't checked, and any 
+    /// further pages must be requested by the caller, such as by following 
+    /// the body's `pagination.next` link. The request is always sent with a 
+    /// new `HttpTransport`, so any Transport or client set on this request 
+    /// isn't used.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the request fails 
+    /// the checks made before sending it, or the reqwest library returned an 
+    /// error.
+    pub fn send_raw(&self) -> Result<reqwest::blocking::Response, Error> {
+        self.check()?;
+        HttpTransport::new().send(&self.url(), &self.headers())
+    }
+
+    /// Returns the URL of the first page that `get` would fetch.
+    pub fn url(&self) -> String {
+        self.construct_url(&None, &1)
+    }
+
+    /// Returns the URLs that `get` would fetch, without sending any request.
+    /// 
+    /// Only the first page's URL can be known in advance, as whether there 
+    /// are further pages depends on the API's responses.
+    pub fn dry_run(&self) -> Vec<String> {
+        vec![self.url()]
+    }
+
+    fn execute(&self, latest_by: Option<MetricKind>) -> Result<Data, Error> {
+        let mut data = vec![];
+        self.paginate(&latest_by, |_, page| {
+            data.extend(page);
+            true
+        })?;
+
+        Ok(data)
+    }
+
+    /// Fetches the pages of the request after the first, which has already 
+    /// been fetched as the provided response through the provided Transport, 
+    /// returning the days of every page.
+    fn execute_from(&self, first: (transport::Response, &dyn Transport)) -> Result<Data, Error> {
+        self.check()?;
+
+        let mut data = vec![];
+        let mut record = FetchRecord::default();
+        let result = self.paginate_recorded(&None, &mut record, Some(first), |_, _, resp| {
+            data.extend(self.parse_data(resp)?);
+            Ok(true)
+        });
+        self.record(record);
+        result.map(|_| data)
+    }
+
+    /// Executes the request, writing the days to the writer as CSV as each 
+    /// page is fetched, rather than holding them all in memory.
+    /// 
+    /// The header row names the requested metrics and is written with the 
+    /// first page; dates are written as ISO-8601. The writer is flushed after 
+    /// each page. Days are written in the order the API returns them, 
+    /// regardless of `set_order`.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error, or `Error::IoError` if writing failed. 
+    /// Any pages fetched before the error will already have been written.
+    pub fn write_csv<W: std::io::Write>(&self, w: &mut W) -> Result<(), Error> {
+        let mut header = true;
+        let mut write_err = None;
+        self.paginate(&None, |_, page| {
+            let mut write_page = || -> std::io::Result<()> {
+                if header {
+                    let names = self.config.metrics.iter().map(|m| self.key(m).to_string());
+                    w.write_all(export::csv_header(names).as_bytes())?;
+                    header = false;
+                }
+                for day in &page {
+                    w.write_all(export::csv_row(day, &export::DateFormat::Iso8601).as_bytes())?;
+                }
+                w.flush()
+            };
+            match write_page() {
+                Ok(()) => true,
+                Err(e) => {
+                    write_err = Some(e);
+                    false
+                }
+            }
+        })?;
+
+        match write_err {
+            Some(e) => Err(Error::IoError(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Executes the request, writing the days as CSV to the file at the 
+    /// provided path, as with `write_csv`. The file is replaced if it exists, 
+    /// and any missing parent directories are created.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error, or `Error::IoError` if the file couldn't 
+    /// be created or written. The file may be left partly written.
+    pub fn save_csv(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(Error::IoError)?;
+        }
+        let file = std::fs::File::create(path).map_err(Error::IoError)?;
+
+        self.write_csv(&mut std::io::BufWriter::new(file))
+    }
+
+    /// Executes the request and returns the days keyed by their dates, for 
+    /// looking up days by date and iterating over them chronologically.
+    /// 
+    /// The `date` metric is added to the request if it isn't already present. 
+    /// If several days share a date (such as when the request covers several 
+    /// areas), only the first returned by the API is kept.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_by_date(&self) -> Result<BTreeMap<Date, Datum>, Error> {
+        let mut req = self.derived();
+        req.ensure_metric(MetricKind::Date);
+
+        let mut days = BTreeMap::new();
+        for day in req.get()? {
+            if let Some(date) = data::date_of(&day) {
+                days.entry(date).or_insert(day);
+            }
+        }
+
+        Ok(days)
+    }
+
+    /// Executes the request and returns the days grouped by area, keyed by 
+    /// area code, as with `data::group_by_area`.
+    /// 
+    /// The `areaCode` metric is added to the request if it isn't already 
+    /// present.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_by_area(&self) -> Result<BTreeMap<String, Data>, Error> {
+        let mut req = self.derived();
+        req.ensure_metric(MetricKind::AreaCode);
+
+        Ok(data::group_by_area(&req.get()?))
+    }
+
+    /// Executes the request and returns the raw body of each page, exactly as 
+    /// the API sent it.
+    /// 
+    /// Each page is still checked to be a valid response, as its pagination 
+    /// is needed to find the next page.
+    /// 
+    /// ## Errors
+    /// 
+    /// This function may return an Error enum variant if the reqwest library 
+    /// or the API returned an error.
+    pub fn get_bytes(&self) -> Result<Vec<Vec<u8>>, Error> {
+        let mut pages = vec![];
+        self.paginate_raw(&None, |_, body, _| {
+            pages.push(body);
+            Ok(true)
+        })?;
+
+        Ok(pages)
+    }
+
+    /// Drops the trailing days requested with 
+    /// `set_drop_incomplete_trailing`, and puts the remaining days into the 
+    /// order requested with `set_order`.
+    pub(crate) fn finish(&self, data: &mut Data) {
+        if self.config.drop_trailing > 0 {
+            let mut dates: Vec<Date> = data.iter().filter_map(data::date_of).collect();
+            dates.sort_unstable_by(|a, b| b.cmp(a));
+            dates.dedup();
+            if let Some(cutoff) = dates.get(self.config.drop_trailing - 1).or(dates.last()) {
+                let cutoff = *cutoff;
+                data.retain(|day| data::date_of(day).map_or(true, |d| d < cutoff));
+            }
+        }
+
+        if self.config.order == Order::Chronological {
+            data.sort_by_key(data::date_of);
+        }
+    }
+
+    /// Fetches each page of the request in turn, passing its URL and days to 
+    /// `on_page`, until the last page is reached or `on_page` returns false.
+    fn paginate<F>(&self, latest_by: &Option<MetricKind>, mut on_page: F) -> Result<(), Error>
+    where
+        F: FnMut(&str, Data) -> bool,
+    {
+        self.paginate_raw(latest_by, |url, _, resp| Ok(on_page(url, self.parse_data(resp)?)))
+    }
+
+    /// Fetches each page of the request in turn, passing its URL, raw body, 
+    /// and parsed body to `on_page`, until the last page is reached or 
+    /// `on_page` returns false.
+    fn paginate_raw<F>(&self, latest_by: &Option<MetricKind>, on_page: F) -> Result<(), Error>
+    where
+        F: FnMut(&str, Vec<u8>, &json::JsonValue) -> Result<bool, Error>,
+    {
+        self.check()?;
+
+        let mut record = FetchRecord::default();
+        let result = self.paginate_recorded(latest_by, &mut record, None, on_page);
+        self.record(record);
+        result
+    }
+
+    /// Fetches the pages for `paginate_raw`, recording each page fetched. If 
+    /// the first page's response is provided, it is used rather than 
+    /// fetching the first page, and the remaining pages are fetched through 
+    /// the Transport it was fetched through, so that they share its session.
+    fn paginate_recorded<F>(
+        &self,
+        latest_by: &Option<MetricKind>,
+        record: &mut FetchRecord,
+        first: Option<(transport::Response, &dyn Transport)>,
+        mut on_page: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&str, Vec<u8>, &json::JsonValue) -> Result<bool, Error>,
+    {
+        let own;
+        let (mut first, transport) = match first {
+            Some((res, transport)) => (Some(res), transport),
+            None => {
+                own = self.transport();
+                (None, own.as_ref())
+            }
+        };
+        let mut page = 1;
+        let mut url = self.construct_url(latest_by, &page);
+        let mut total_bytes = 0;
+
+        loop {
+            let fetched = match first.take() {
+                Some(res) => self.read_page(res, record),
+                None => self.fetch_page(transport, &url, record),
+            };
+            let fetched = fetched.and_then(|(body, resp, linked)| {
+                total_bytes += body.len();
+                match self.config.max_bytes {
+                    Some(max) if total_bytes > max => Err(Error::ResponseTooLarge),
+                    _ => Ok((body, resp, linked)),
+                }
+            });
+            let (body, resp, linked) = match fetched {
+                Ok(r) => r,
+                Err(e) if self.config.partial_ok && page > 1 => {
+                    log::warn!(
+                        "Returning partial data ({} pages): fetching page {} failed: {:?}",
+                        page - 1,
+                        page,
+                        e
+                    );
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+
+            if !on_page(&url, body, &resp)? {
+                break;
+            }
+
+            if self.config.single_page || resp["pagination"]["next"].is_null() {
+                break;
+            }
+            page += 1;
+            url = match linked {
+                Some(next) => next,
+                None => self.construct_url(latest_by, &page),
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Returns the Transport a fetch of the request sends through.
+    fn transport(&self) -> Arc<dyn Transport> {
+        let transport = match &self.config.transport {
+            Some(t) => t.clone(),
+            None => Arc::new(HttpTransport::new()),
+        };
+        #[cfg(feature = "cookies")]
+        let transport: Arc<dyn Transport> = if self.config.cookie_store {
+            Arc::new(transport::CookieTransport::new(transport))
+        } else {
+            transport
+        };
+        transport
+    }
+
+    /// Records a finished fetch as the request's most recent, replacing the 
+    /// record of any earlier fetch.
+    fn record(&self, record: FetchRecord) {
+        if let Some(diagnostics) = &self.diagnostics {
+            *diagnostics.lock().unwrap() = record.diagnostics;
+        }
+        *self.last_update.lock().unwrap() = record.last_update;
+    }
+
+    /// Returns a copy of the request for a fetch made on its behalf, which 
+    /// shares its record (unlike a clone), so that the fetch is recorded as 
+    /// the request's own.
+    pub(crate) fn derived(&self) -> Request {
+        Request {
+            filters: self.filters.clone(),
+            config: self.config.clone(),
+            diagnostics: self.diagnostics.clone(),
+            last_update: self.last_update.clone(),
+        }
+    }
+
+    /// Records the fetches made by the provided clones of the request, for 
+    /// several fetches made concurrently on its behalf, in the order 
+    /// provided, as the request's most recent fetch. The update time 
+    /// recorded is that of the first clone which has one.
+    pub(crate) fn record_clones(&self, clones: &[Request]) {
+        self.record(FetchRecord {
+            diagnostics: clones.iter().flat_map(Request::last_diagnostics).collect(),
+            last_update: clones.iter().find_map(Request::last_update),
+        });
+    }
+
+    /// Checks the request for every problem which can be found without 
+    /// sending it, returning all of them, rather than only the first as 
+    /// when the request is sent, for reporting every mistake in a request 
+    /// built from configuration at once.
+    /// 
+    /// As well as the checks made before every request, this makes the 
+    /// checks of strict mode (see `set_strict`) whether or not it is 
+    /// enabled, and checks that the request has at least one metric and no 
+    /// more than the API accepts (five).
+    /// 
+    /// ## Errors
+    /// 
+    /// Returns each problem found as an Error enum variant, in the order 
+    /// they were checked.
+    pub fn validate_all(&self) -> Result<(), Vec<Error>> {
+        let mut problems = self.problems(true);
+        if self.config.metrics.is_empty() {
+            problems.push(Error::InvalidRequest(String::from("The request has no metrics")));
+        } else if self.config.metrics.len() > MAX_STRUCTURE_METRICS {
+            problems.push(Error::InvalidRequest(format!(
+                "The request has {} metrics, but the API accepts at most {}",
+                self.config.metrics.len(),
+                MAX_STRUCTURE_METRICS
+            )));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Checks the request before it is sent, warning of any metrics which 
+    /// will be empty.
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        if let Some(problem) = self.problems(self.config.strict).into_iter().next() {
+            return Err(problem);
+        }
+
+        if let Some(area_type) = self.area_type() {
+            for metric in self.config.metrics.iter().filter(|m| !m.available_for(area_type)) {
+                log::warn!(
+                    "{} is not reported for {} areas, so will be empty",
+                    metric.name(),
+                    area_type.name()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the problems with the request's filters, including those only 
+    /// checked in strict mode if `strict` is set.
+    fn problems(&self, strict: bool) -> Vec<Error> {
+        let mut problems = vec![];
+        #[cfg(feature = "validate")]
+        if let Err(e) = areas::validate(&self.filters) {
+            problems.push(e);
+        }
+
+        if strict {
+            let today = uk_date(time::OffsetDateTime::now_utc());
+            for filter in &self.filters {
+                match &filter.value {
+                    FilterValue::Date(date) if *date > today => problems.push(Error::FutureDate(*date)),
+                    FilterValue::AreaCode(code) => match self.area_type() {
+                        Some(area_type) if !code.could_be(area_type) => {
+                            problems.push(Error::InconsistentFilters(format!(
+                                "{} is not the code of a {} area",
+                                code,
+                                area_type.name()
+                            )))
+                        }
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+        }
+        problems
+    }
+
+    /// Returns the headers sent with each page request.
+    pub(crate) fn headers(&self) -> Vec<(&str, &str)> {
+        let mut headers = vec![
+            ("Accepts", "application/json; application/xml; text/csv; application/vnd.PHE-COVID19.v1+json; application/vnd.PHE-COVID19.v1+xml"),
+            ("Content-Type", "application/json"),
+        ];
+        if let Some(lang) = &self.config.accept_language {
+            headers.push(("Accept-Language", lang));
+        }
+        headers
+    }
+
+    /// Fetches the page, returning its raw body alongside the parsed body, 
+    /// and the URL of the next page if it was linked in a `Link` header.
+    fn fetch_page(
+        &self,
+        transport: &dyn Transport,
+        url: &str,
+        record: &mut FetchRecord,
+    ) -> Result<(Vec<u8>, json::JsonValue, Option<String>), Error> {
+        let res = self.send_page(transport, url, record)?;
+        self.read_page(res, record)
+    }
+
+    /// Parses a fetched page as for `fetch_page`.
+    fn read_page(
+        &self,
+        res: transport::Response,
+        record: &mut FetchRecord,
+    ) -> Result<(Vec<u8>, json::JsonValue, Option<String>), Error> {
+        let (resp, linked) = self.parse_page(&res)?;
+        if let Some(time) = resp["lastUpdate"].as_str().or_else(|| resp["release"].as_str()) {
+            record.last_update.get_or_insert_with(|| time.to_string());
+        }
+        Ok((res.body, resp, linked))
+    }
+
+    /// Sends the request for a page, recording its diagnostics if enabled.
+    fn send_page(
+        &self,
+        transport: &dyn Transport,
+        url: &str,
+        record: &mut FetchRecord,
+    ) -> Result<transport::Response, Error> {
+        let start = std::time::Instant::now();
+        let res = transport.get(url, &self.headers())?;
+        if self.diagnostics.is_some() {
+            record.diagnostics.push(PageDiagnostic {
+                url: url.to_string(),
+                status: res.status,
+                duration: start.elapsed(),
+                bytes: res.body.len(),
+            });
+        }
+        Ok(res)
+    }
+
+    /// Checks the status of a page response and parses its body.
+    pub(crate) fn parse_response(&self, res: transport::Response) -> Result<json::JsonValue, Error> {
+        self.parse_page(&res).map(|(resp, _)| resp)
+    }
+
+    /// Checks the status of a page response and parses its body, returning 
+    /// the URL of the next page if it was linked in a `Link` header rather 
+    /// than in the body.
+    fn parse_page(&self, res: &transport::Response) -> Result<(json::JsonValue, Option<String>), Error> {
+        let body = json_body(res)?;
+
+        let mut resp = match self.config.format {
+            Format::Json => parse_lenient(body)
+                .map_err(|e| Error::ParseError(format!("Error parsing JSON: {} (body: {})", e, snippet(body))))?,
+            Format::JsonL => parse_jsonl(body)?,
+        };
+
+        // Parsing keeps the last of any duplicated key, so the days are only 
+        // scanned for duplicates if another policy is set.
+        if self.config.named_structure && self.config.format == Format::Json {
+            let duplicates = match self.config.duplicate_keys {
+                DuplicateKeys::Last => vec![],
+                _ => duplicate_keys(body),
+            };
+            for (day, key, first) in duplicates {
+                if self.config.duplicate_keys == DuplicateKeys::Error {
+                    return Err(Error::ParseError(format!("Day {} names {} more than once", day, key)));
+                }
+                resp["data"][day][key.as_str()] = parse_lenient(first)
+                    .map_err(|e| Error::ParseError(format!("Error parsing JSON: {} (body: {})", e, snippet(body))))?;
+            }
+        }
+
+        // Some deployments page with `Link` headers rather than in the body, 
+        // so any next page linked there is recorded as if in the body, and 
+        // its URL returned so that the link itself is followed.
+        let mut linked = None;
+        if let Some(links) = header(res, "link") {
+            if !resp["pagination"].is_object() {
+                resp["pagination"] = json::object! { "next" => json::JsonValue::Null };
+            }
+            if resp["pagination"]["next"].is_null() {
+                if let Some(next) = link_next(links) {
+                    let base = self.config.base_url.as_deref().unwrap_or(API_URL);
+                    resp["pagination"]["next"] = next.as_str().into();
+                    linked = Some(resolve_link(base, &next)?);
+                }
+            }
+        }
+
+        // Check the shape of the response up front, so that a change to the 
+        // API is reported clearly rather than surfacing as missing data.
+        if !resp["data"].is_array() {
+            return Err(Error::IncompatibleApiVersion(format!(
+                "Response has no data array: {}",
+                snippet(body)
+            )));
+        }
+        if !resp["pagination"].is_object() {
+            return Err(Error::IncompatibleApiVersion(format!(
+                "Response has no pagination object: {}",
+                snippet(body)
+            )));
+        }
+
+        Ok((resp, linked))
+    }
+
+    pub(crate) fn parse_data(&self, resp: &json::JsonValue) -> Result<Data, Error> {
+        resp["data"]
+            .members()
+            .map(|day| Ok(self.parse_day(day)?.into_iter().map(|(metric, _)| metric).collect()))
+            .collect()
+    }
+
+    /// Parses a day of a response, pairing each Metric with the raw value it 
+    /// was parsed from.
+    fn parse_day<'a>(&self, day: &'a json::JsonValue) -> Result<Vec<(Metric, &'a json::JsonValue)>, Error> {
+        let mut datum = vec![];
+        let columns = self.columns();
+        for (i, metric) in self.config.metrics.iter().enumerate() {
+            let value = if self.config.named_structure {
+                &day[self.key(metric)]
+            } else if columns[i] < day.len() {
+                &day[columns[i]]
+            } else {
+                // The API returned fewer values than were requested; the 
+                // missing metrics are read as null, as a missing key is in a 
+                // named structure, so that every Datum keeps the request's 
+                // shape.
+                &json::JsonValue::Null
+            };
+            let default = self.config.null_defaults.iter().find(|(m, _)| m == metric);
+            let parsed = match default {
+                Some((_, default)) if value.is_null() => metric.parse(&(*default).into())?,
+                _ => metric.parse(value)?,
+            };
+            datum.push((parsed, value));
+        }
+
+        Ok(datum)
+    }
+
+    pub(crate) fn construct_url(&self, latest_by: &Option<MetricKind>, page: &u32) -> String {
+        let mut url = self.config.base_url.clone().unwrap_or_else(|| String::from(API_URL));
+        url.push_str(
+            format!(
+                "?filters={}&structure={}&format={}&page={}",
+                self.filters_str(),
+                self.metrics_str(),
+                self.config.format.name(),
+                page
+            )
+            .as_str(),
+        );
+
+        if let Option::Some(m) = latest_by {
+            url.push_str(format!("&latestBy={}", m.name()).as_str());
+        }
+
+        if let Option::Some(size) = self.config.page_size {
+            url.push_str(format!("&pageSize={}", size).as_str());
+        }
+
+        url
+    }
+
+    fn filters_str(&self) -> String {
+        self.filters
+            .iter()
+            .map(Filter::to_string)
+            .collect::<Vec<String>>()
+            .join(";")
+    }
+
+    /// Returns the request's filters in their API form, sorted so that the 
+    /// order they were added in doesn't matter.
+    fn canonical_filters(&self) -> Vec<String> {
+        let mut filters: Vec<String> = self.filters.iter().map(Filter::to_string).collect();
+        filters.sort();
+        filters
+    }
+
+    /// Returns the request's metrics in the order they are listed in its 
+    /// structure.
+    fn structure(&self) -> Vec<&MetricKind> {
+        let mut metrics: Vec<&MetricKind> = self.config.metrics.iter().collect();
+        if self.config.canonical_order {
+            metrics.sort_by_key(|m| m.name());
+        }
+        metrics
+    }
+
+    /// Returns the column of the structure holding each of the request's 
+    /// metrics, in the order they were added.
+    fn columns(&self) -> Vec<usize> {
+        let mut columns: Vec<usize> = (0..self.config.metrics.len()).collect();
+        if self.config.canonical_order {
+            let mut order = columns.clone();
+            order.sort_by_key(|i| self.config.metrics[*i].name());
+            for (column, i) in order.into_iter().enumerate() {
+                columns[i] = column;
+            }
+        }
+        columns
+    }
+
+    fn metrics_str(&self) -> String {
+        let mut s = String::new();
+
+        let mut multiple_metrics = false;
+        for metric in self.structure() {
+            if multiple_metrics {
+                s.push_str(", ");
+            }
+            multiple_metrics = true;
+
+            if self.config.named_structure {
+                s.push_str(format!("%22{}%22:%22{}%22", self.key(metric), metric.name()).as_str());
+            } else {
+                s.push_str(format!("%22{}%22", metric.name()).as_str());
+            }
+        }
+
+        if self.config.named_structure {
+            format!("{{{}}}", s)
+        } else {
+            format!("[{}]", s)
+        }
+    }
+}
+
+/// Requests are equal if they request the same metrics, in the same order, 
+/// with the same filters, in any order, so that they can key a cache of 
+/// results. Other options, such as the transport, aren't compared.
+impl PartialEq for Request {
+    fn eq(&self, other: &Request) -> bool {
+        self.config.metrics == other.config.metrics && self.canonical_filters() == other.canonical_filters()
+    }
+}
+impl Eq for Request {}
+impl std::hash::Hash for Request {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.config.metrics.hash(state);
+        self.canonical_filters().hash(state);
+    }
+}
+
+/// Fetches the given metrics for areas of the given type matching all of the 
+/// given filters, in a single call.
+/// 
+/// This is shorthand for building a `Request` and calling `get` on it; use 
+/// `Request` directly for anything more involved.
+/// 
+/// ```no_run
+/// use covid19_uk_rs::{query, AreaType, FilterValue, MetricKind};
+/// 
+/// let data = query(AreaType::Nation, &[MetricKind::Date, MetricKind::CumulativeCasesByPublishDate], &[FilterValue::AreaName(String::from("england"))]);
+/// ```
+pub fn query(area: AreaType, metrics: &[MetricKind], filters: &[FilterValue]) -> Result<Data, Error> {
+    let mut req = Request::with_metrics(area, metrics);
+    for filter in filters {
+        req.add_filter(Filter::new(filter.clone()));
+    }
+
+    req.get()
+}
+
+/// Fetches numeric metrics for a single area into rows of a struct generated 
+/// for the query, with a `date` field and an `Option<i32>` field for each 
+/// metric, so that no Metric variants need be matched.
+/// 
+/// The area is given as an `AreaType` variant and the area's name, and each 
+/// metric as a field name and a `MetricKind` variant. A Transport may also be 
+/// given, as with `Request::set_transport`. The query evaluates to a 
+/// `Result` of a `Vec` of rows, in the order returned by the API.
+/// 
+/// ```no_run
+/// let rows = covid19_uk_rs::covid_query! {
+///     area: Nation("england"),
+///     metrics: {
+///         cases: CumulativeCasesByPublishDate,
+///         deaths: CumulativeDeathsWithin28DaysByPublishDate,
+///     }
+/// }
+/// .unwrap();
+/// 
+/// for row in rows {
+///     println!("{}: {:?} cases, {:?} deaths", row.date, row.cases, row.deaths);
+/// }
+/// ```
+#[macro_export]
+macro_rules! covid_query {
+    (
+        area: $area_type:ident($area_name:expr),
+        metrics: { $($field:ident: $metric:ident),+ $(,)? }
+        $(, transport: $transport:expr)?
+        $(,)?
+    ) => {{
+        #[derive(Debug, Clone, PartialEq)]
+        struct Row {
+            date: $crate::Date,
+            $($field: Option<i32>,)+
+        }
+
+        let mut req = $crate::Request::new(
+            $crate::AreaType::$area_type,
+            $crate::Metric::Date($crate::Date::try_from_ymd(2020, 1, 1).unwrap()),
+        );
+        req.add_filter($crate::Filter::new($crate::FilterValue::AreaName(
+            ::std::string::String::from($area_name),
+        )));
+        $(req.add_metric($crate::Metric::$metric($crate::MetricValue::Null));)+
+        $(req.set_transport($transport);)?
+
+        req.get().map(|data| {
+            data.iter()
+                .filter_map(|day| {
+                    Some(Row {
+                        date: day.iter().find_map(|m| match m {
+                            $crate::Metric::Date(d) => Some(*d),
+                            _ => None,
+                        })?,
+                        $($field: day
+                            .iter()
+                            .find(|m| m.kind() == $crate::MetricKind::$metric)
+                            .and_then($crate::Metric::as_i32),)+
+                    })
+                })
+                .collect::<Vec<Row>>()
+        })
+    }};
+}
+
+/// Parses a JSON body, retrying without any trailing commas (as in `[1, 2,]`) 
+/// if it fails to parse, as these have been seen from the API. The error 
+/// from the original body is returned if both fail.
+fn parse_lenient(body: &str) -> json::Result<json::JsonValue> {
+    json::parse(body).or_else(|e| json::parse(&strip_trailing_commas(body)).map_err(|_| e))
+}
+
+/// Removes any commas directly preceding (ignoring whitespace) the end of an 
+/// array or object, outside of strings.
+fn strip_trailing_commas(body: &str) -> String {
+    let mut stripped = String::with_capacity(body.len());
+    let mut chars = body.char_indices().peekable();
+    let mut in_string = false;
+    while let Some((i, c)) = chars.next() {
+        if in_string {
+            match c {
+                '\\' => {
+                    stripped.push(c);
+                    if let Some((_, escaped)) = chars.next() {
+                        stripped.push(escaped);
+                    }
+                    continue;
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' && body[i + 1..].trim_start().starts_with([']', '}']) {
+            continue;
+        }
+        stripped.push(c);
+    }
+    stripped
+}
+
+/// Finds the keys named more than once by any of the days of a JSON 
+/// response's `data` array, as the index of the day, the key, and the text 
+/// of its first value. Anything which isn't a well-formed object is skipped.
+fn duplicate_keys(body: &str) -> Vec<(usize, String, &str)> {
+    let mut duplicates = vec![];
+    let members = Scanner::at(body, 0).members().unwrap_or_default();
+    for (_, (start, _)) in members.into_iter().filter(|(key, _)| key == "data") {
+        let days = Scanner::at(body, start).elements().unwrap_or_default();
+        for (i, (start, _)) in days.into_iter().enumerate() {
+            let mut seen: Vec<(String, &str)> = vec![];
+            for (key, (start, end)) in Scanner::at(body, start).members().unwrap_or_default() {
+                match seen.iter().find(|(k, _)| *k == key) {
+                    Some((_, first)) => {
+                        if !duplicates.iter().any(|(d, k, _)| *d == i && *k == key) {
+                            duplicates.push((i, key, *first));
+                        }
+                    }
+                    None => seen.push((key, body[start..end].trim_end())),
+                }
+            }
+        }
+    }
+    duplicates
+}
+
+/// Steps through the text of a JSON value, finding the spans of its 
+/// members without parsing them, for the details `json::parse` doesn't keep.
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+}
+impl<'a> Scanner<'a> {
+    fn at(text: &'a str, pos: usize) -> Scanner<'a> {
+        Scanner { text, pos }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        while self.text.as_bytes().get(self.pos)?.is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    /// Steps over the string starting at the position.
+    fn string(&mut self) -> Option<()> {
+        self.pos += 1;
+        loop {
+            match self.text.as_bytes().get(self.pos)? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// Steps over the value starting at the position, returning its span.
+    fn value(&mut self) -> Option<(usize, usize)> {
+        self.peek()?;
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.text.as_bytes().get(self.pos)? {
+                b'"' => self.string()?,
+                b'{' | b'[' => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                b'}' | b']' | b',' if depth == 0 => break,
+                b'}' | b']' => {
+                    depth -= 1;
+                    self.pos += 1;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        Some((start, self.pos))
+    }
+
+    /// Returns the key and the span of the value of each member of the 
+    /// object starting at the position, in order.
+    fn members(&mut self) -> Option<Vec<(String, (usize, usize))>> {
+        if self.peek()? != b'{' {
+            return None;
+        }
+        self.pos += 1;
+        let mut members = vec![];
+        loop {
+            match self.peek()? {
+                b'}' => return Some(members),
+                b',' => self.pos += 1,
+                b'"' => {
+                    let start = self.pos;
+                    self.string()?;
+                    let key = json::parse(&self.text[start..self.pos]).ok()?.as_str()?.to_string();
+                    if self.peek()? != b':' {
+                        return None;
+                    }
+                    self.pos += 1;
+                    members.push((key, self.value()?));
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Returns the span of each element of the array starting at the 
+    /// position, in order.
+    fn elements(&mut self) -> Option<Vec<(usize, usize)>> {
+        if self.peek()? != b'[' {
+            return None;
+        }
+        self.pos += 1;
+        let mut elements = vec![];
+        loop {
+            match self.peek()? {
+                b']' => return Some(elements),
+                b',' => self.pos += 1,
+                _ => elements.push(self.value()?),
+            }
+        }
+    }
+}
+
+/// Parses a JSON lines body, with one day per line, into the form of a 
+/// single-page JSON response.
+fn parse_jsonl(body: &str) -> Result<json::JsonValue, Error> {
+    let mut data = json::JsonValue::new_array();
+    for day in jsonl_days(body) {
+        data.push(day?).unwrap();
+    }
+
+    Ok(json::object! {
+        "data" => data,
+        "pagination" => json::object! { "next" => json::JsonValue::Null },
+    })
+}
+
+/// Parses each day of a JSON lines body in turn, as it is iterated.
+fn jsonl_days(body: &str) -> impl Iterator<Item = Result<json::JsonValue, Error>> + '_ {
+    body.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).map(|(i, line)| {
+        json::parse(line).map_err(|e| {
+            Error::ParseError(format!("Error parsing JSON on line {}: {} (line: {})", i + 1, e, snippet(line)))
+        })
+    })
+}
+
+/// Whether the latest day of the Data has a value for the metric.
+fn latest_has_value(data: &Data, metric: &MetricKind) -> bool {
+    let null = metric.parse(&json::JsonValue::Null).ok();
+    data.iter()
+        .filter_map(|day| {
+            let date = day.iter().find_map(|m| match m {
+                Metric::Date(d) => Some(*d),
+                _ => None,
+            })?;
+            Some((date, day))
+        })
+        .max_by_key(|(date, _)| *date)
+        .and_then(|(_, day)| day.iter().find(|m| m.kind() == *metric))
+        .is_some_and(|m| Some(m) != null.as_ref())
+}
+
+/// Returns the value of the response's header with the provided name, 
+/// ignoring case.
+fn header<'a>(res: &'a transport::Response, name: &str) -> Option<&'a str> {
+    res.headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Returns the target of the `rel="next"` link in a `Link` header, such as 
+/// `</v1/data?page=2>; rel="next", </v1/data?page=1>; rel="prev"`.
+fn link_next(links: &str) -> Option<String> {
+    links.split(',').find_map(|link| {
+        let mut parts = link.split(';');
+        let target = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+        parts
+            .filter_map(|p| p.trim().strip_prefix("rel="))
+            .any(|rel| rel.trim_matches('"').split_whitespace().any(|r| r.eq_ignore_ascii_case("next")))
+            .then(|| target.to_string())
+    })
+}
+
+/// Checks the status of a response and returns its body, which must be JSON.
+fn json_body(res: &transport::Response) -> Result<&str, Error> {
+    check_status(res)?;
+
+    let body = std::str::from_utf8(&res.body).map_err(|e| {
+        Error::ParseError(format!(
+            "Response is not valid UTF-8: {} (body: {})",
+            e,
+            snippet(&String::from_utf8_lossy(&res.body))
+        ))
+    })?;
+    // Some caches prefix the body with a byte order mark.
+    let body = body.strip_prefix('\u{feff}').unwrap_or(body);
+
+    // Failures behind some proxies come back as a successful HTML page.
+    let content_type = header(res, "content-type").map(str::to_lowercase);
+    let is_json = match content_type {
+        Some(t) => t.contains("json"),
+        None => body.trim_start().starts_with(['{', '[']),
+    };
+    if !is_json {
+        return Err(Error::ParseError(format!("Response is not JSON: {}", snippet(body))));
+    }
+    Ok(body)
+}
+
+/// Resolves a link target against the URL it was linked from, so that a 
+/// relative target such as `/v1/data?page=2` is fetched from the same host.
+fn resolve_link(base: &str, target: &str) -> Result<String, Error> {
+    reqwest::Url::parse(base)
+        .and_then(|base| base.join(target))
+        .map(String::from)
+        .map_err(|e| Error::ParseError(format!("Invalid link to the next page {}: {}", target, e)))
+}
+
+/// Reads the page number from a pagination link, such as 
+/// `/v1/data?filters=areaType=nation&page=2`.
+fn page_number(link: &json::JsonValue) -> Option<u32> {
+    link.as_str()?
+        .split(['?', '&'])
+        .find_map(|p| p.strip_prefix("page="))?
+        .parse()
+        .ok()
+}
+
+/// Maps an unsuccessful response status to the corresponding Error.
+fn check_status(res: &transport::Response) -> Result<(), Error> {
+    let status_code = res.status;
+    if status_code != 200 {
+        if status_code == 204 {
+            return Result::Err(Error::NoData);
+        } else if status_code == 429 {
+            return Result::Err(Error::TooManyRequests(rate_limit_hint(&String::from_utf8_lossy(
+                &res.body,
+            ))));
+        } else if status_code >= 500 {
+            return Result::Err(Error::APIServerError);
+        } else if (400..500).contains(&status_code) {
+            if status_code == 412 {
+                return Result::Err(Error::PreconditionFailed);
+            }
+            let message = api_error_message(&String::from_utf8_lossy(&res.body));
+            if status_code == 413 || message.to_lowercase().contains("maximum number of records") {
+                return Result::Err(Error::QueryTooBroad(message));
+            }
+            return Result::Err(Error::ApiError {
+                status: status_code,
+                message,
+            });
+        } else if status_code == 304 {
+            return Result::Err(Error::NotModified);
+        } else {
+            return Result::Err(Error::ApiError {
+                status: status_code,
+                message: String::from_utf8_lossy(&res.body).into_owned(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the message from an API error response body, falling back to the 
+/// whole body if it isn't in the expected form.
+fn api_error_message(body: &str) -> String {
+    if let Ok(resp) = json::parse(body) {
+        for key in &["response", "message"] {
+            if let Some(message) = resp[*key].as_str() {
+                return message.to_string();
+            }
+        }
+    }
+
+    body.to_string()
+}
+
+/// Extracts the reset time or message from a rate-limited (429) response 
+/// body, if it is JSON and has one.
+fn rate_limit_hint(body: &str) -> Option<String> {
+    let resp = json::parse(body).ok()?;
+    ["reset", "retryAfter", "retry_after", "message", "response"]
+        .iter()
+        .map(|key| &resp[*key])
+        .find(|value| value.is_string() || value.is_number())
+        .map(|value| value.to_string())
+}
+
+/// Returns the edit distance between two strings: the fewest single 
+/// character insertions, deletions, and substitutions turning one into the 
+/// other.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Returns the date in the UK at the provided moment, as the API's days 
+/// follow UK time: Greenwich Mean Time, or British Summer Time (an hour 
+/// ahead) from 01:00 UTC on the last Sunday of March until 01:00 UTC on the 
+/// last Sunday of October.
+fn uk_date(now: time::OffsetDateTime) -> Date {
+    let now = now.to_offset(time::UtcOffset::UTC);
+    let change = |month| {
+        let mut date = Date::try_from_ymd(now.year(), month, 31).unwrap();
+        while date.weekday() != time::Weekday::Sunday {
+            date = date.previous_day();
+        }
+        time::PrimitiveDateTime::new(date, time::Time::try_from_hms(1, 0, 0).unwrap()).assume_utc()
+    };
+
+    if now >= change(3) && now < change(10) {
+        now.to_offset(time::UtcOffset::hours(1)).date()
+    } else {
+        now.date()
+    }
+}
+
+/// Returns the start of a response body, for inclusion in error messages.
+fn snippet(body: &str) -> String {
+    const LENGTH: usize = 100;
+
+    match body.char_indices().nth(LENGTH) {
+        Some((i, _)) => format!("{}...", &body[..i]),
+        None => body.to_string(),
+    }
+}
+
+/// A single day's value for a numeric metric, as returned by 
+/// `Request::fetch_single_i32`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatedValue {
+    pub date: Date,
+    /// `None` where the API reported no value for the day.
+    pub value: Option<i32>,
+}
+
+/// Whether a metric returned data, as reported by `Request::validate`.
+#[derive(Debug)]
+pub enum MetricStatus {
+    /// The metric had a value on the latest day.
+    Present,
+    /// The metric was returned, but had no value on the latest day.
+    Empty,
+    /// The API rejected the metric.
+    Failed(Error),
+}
+impl From<bool> for MetricStatus {
+    fn from(present: bool) -> MetricStatus {
+        if present {
+            MetricStatus::Present
+        } else {
+            MetricStatus::Empty
+        }
+    }
+}
+
+/// The outcome of fetching a single page, as recorded by a Request with 
+/// diagnostics enabled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageDiagnostic {
+    pub url: String,
+    /// The HTTP status code of the response.
+    pub status: u16,
+    /// The time taken to receive the whole response.
+    pub duration: std::time::Duration,
+    /// The size of the response body, in bytes.
+    pub bytes: usize,
+}
+
+/// What a single fetch of a request records of the pages it fetches, kept 
+/// apart from the request until the fetch finishes, so that clones of the 
+/// request fetching concurrently don't mix their records.
+#[derive(Debug, Default)]
+struct FetchRecord {
+    diagnostics: Vec<PageDiagnostic>,
+    last_update: Option<String>,
+}
+
+/// The status of each of a request's metrics, as returned by 
+/// `Request::validate`, in the order the metrics were added.
+#[derive(Debug)]
+pub struct ValidationReport {
+    pub metrics: Vec<(MetricKind, MetricStatus)>,
+}
+impl ValidationReport {
+    /// Returns true if every metric had a value.
+    pub fn is_ok(&self) -> bool {
+        self.problems().is_empty()
+    }
+
+    /// Returns the metrics which were empty or rejected.
+    pub fn problems(&self) -> Vec<&MetricKind> {
+        self.metrics
+            .iter()
+            .filter(|(_, status)| !matches!(status, MetricStatus::Present))
+            .map(|(metric, _)| metric)
+            .collect()
+    }
+}
+
+/// Pairs each day's value for the metric with its date, as requested by 
+/// `fetch_single_i32`.
+fn dated_values(data: &Data, metric: &MetricKind) -> Vec<DatedValue> {
+    data.iter()
+        .filter_map(|day| {
+            Some(DatedValue {
+                date: data::date_of(day)?,
+                value: data::find(day, metric).and_then(Metric::as_i32),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use transport::{MockTransport, PagedTransport, Response, RoutedTransport};
+
+    /// A logger recording warnings, for checking what was logged.
+    struct TestLogger {
+        warnings: std::sync::Mutex<Vec<String>>,
+    }
+    impl log::Log for TestLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.warnings.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: TestLogger = TestLogger {
+        warnings: std::sync::Mutex::new(Vec::new()),
+    };
+
+    /// Installs the test logger, so that warnings can be checked with 
+    /// `warnings`. Only one logger may be set for the test binary, so it is 
+    /// shared by the tests of each module.
+    pub(crate) fn capture_warnings() {
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Warn);
+    }
+
+    /// Returns the warnings logged so far which contain the text; as tests 
+    /// run in parallel, the text should be unique to the test.
+    pub(crate) fn warnings(containing: &str) -> Vec<String> {
+        LOGGER
+            .warnings
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|w| w.contains(containing))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
     fn api_request() {
-        let mut req = Request::new(AreaType::Nation, Metric::CumulativeCasesByPublishDate(0));
+        let mut req = Request::new(AreaType::Nation, Metric::CumulativeCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("england"))));
+        req.get().unwrap();
+    }
+
+    #[test]
+    fn dated_values_with_null_day() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        let resp = json::parse(r#"{
+            "length": 3,
+            "data": [
+                ["2020-10-03", 12872],
+                ["2020-10-02", null],
+                ["2020-10-01", 6914]
+            ],
+            "pagination": {"next": null}
+        }"#).unwrap();
+
+        assert_eq!(dated_values(&req.parse_data(&resp).unwrap(), &MetricKind::NewCasesByPublishDate), vec![
+            DatedValue { date: Date::try_from_ymd(2020, 10, 3).unwrap(), value: Some(12872) },
+            DatedValue { date: Date::try_from_ymd(2020, 10, 2).unwrap(), value: None },
+            DatedValue { date: Date::try_from_ymd(2020, 10, 1).unwrap(), value: Some(6914) },
+        ]);
+    }
+
+    fn failing_second_page() -> Arc<MockTransport> {
+        Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"["2020-10-03", 3], ["2020-10-02", 2]"#, true),
+            Response::new(500, "Internal Server Error"),
+        ]))
+    }
+
+    #[test]
+    fn page_error_fails_request() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(failing_second_page());
+
+        match req.get() {
+            Err(Error::APIServerError) => {}
+            r => panic!("expected APIServerError, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn page_error_returns_partial_data() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        let transport = failing_second_page();
+        req.set_transport(transport.clone());
+        req.set_partial_ok(true);
+
+        let data = req.get().unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[1][1], Metric::NewCasesByPublishDate(MetricValue::Int(2)));
+        assert_eq!(transport.urls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn list_areas() {
+        let mut req = Request::new(AreaType::Nation, Metric::AreaCode(String::new()));
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["E92000001", "England"], ["N92000002", "Northern Ireland"], ["E92000001", "England"], ["S92000003", "Scotland"]"#,
+            false,
+        )]));
+        req.set_transport(transport.clone());
+
+        assert_eq!(req.areas().unwrap(), vec![
+            (String::from("E92000001"), String::from("England")),
+            (String::from("N92000002"), String::from("Northern Ireland")),
+            (String::from("S92000003"), String::from("Scotland")),
+        ]);
+        let urls = transport.urls.lock().unwrap();
+        assert!(urls[0].contains("structure=[%22areaCode%22, %22areaName%22]"));
+        assert!(urls[0].contains("&latestBy=newCasesByPublishDate"));
+    }
+
+    #[test]
+    fn area_clones_share_configuration() {
+        let mut small = Request::new(AreaType::LTLA, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        let mut large = small.clone();
+        for kind in KNOWN_METRICS {
+            large.ensure_metric(kind.clone());
+        }
+        for (kind, value) in KNOWN_METRICS.iter().zip(0..) {
+            large.set_null_default(kind.clone(), value);
+        }
+        small.set_accept_language("en");
+
+        // Each clone copies only its filters, however large the shared 
+        // configuration is.
+        for req in &[small, large] {
+            let hackney = req.with_area_name("Hackney");
+            assert!(Arc::ptr_eq(&req.config, &hackney.config));
+            assert_eq!(hackney.filters.len(), req.filters.len() + 1);
+            assert!(hackney.url().contains("areaName=Hackney"));
+        }
+
+        // Modifying a clone leaves the original untouched.
+        let req = Request::new(AreaType::LTLA, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let mut hackney = req.with_area_name("Hackney");
+        hackney.set_max_bytes(10);
+        assert!(!Arc::ptr_eq(&req.config, &hackney.config));
+        assert_eq!(req.config.max_bytes, None);
+    }
+
+    #[test]
+    fn page_diagnostics() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let pages = [
+            MockTransport::page(r#"[2], [1]"#, true),
+            MockTransport::page(r#"[0]"#, false),
+        ];
+        let sizes: Vec<usize> = pages.iter().map(|p| p.body.len()).collect();
+        req.set_transport(Arc::new(MockTransport::new(pages.to_vec())));
+        req.set_diagnostics(true);
+
+        let start = std::time::Instant::now();
+        req.get().unwrap();
+        let elapsed = start.elapsed();
+
+        let diagnostics = req.last_diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        for (i, d) in diagnostics.iter().enumerate() {
+            assert_eq!(d.url, req.construct_url(&None, &(i as u32 + 1)));
+            assert_eq!(d.status, 200);
+            assert_eq!(d.bytes, sizes[i]);
+            assert!(d.duration <= elapsed);
+        }
+        assert!(diagnostics[0].duration + diagnostics[1].duration <= elapsed);
+
+        // Fetching again replaces the record.
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page("[0]", false)])));
+        req.get().unwrap();
+        assert_eq!(req.last_diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn last_update_time() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(
+            200,
+            r#"{"data": [[1]], "lastUpdate": "2021-06-01T15:08:32.000000Z", "pagination": {"next": null}}"#,
+        )])));
+        assert_eq!(req.last_update(), None);
+        req.get().unwrap();
+        assert_eq!(req.last_update().as_deref(), Some("2021-06-01T15:08:32.000000Z"));
+
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page("[0]", false)])));
+        req.get().unwrap();
+        assert_eq!(req.last_update(), None);
+    }
+
+    #[test]
+    fn concurrent_last_update() {
+        let page = |rows: &str, time: &str| {
+            Response::new(200, format!(r#"{{"data": [{}], "lastUpdate": "{}", "pagination": {{"next": null}}}}"#, rows, time))
+        };
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("wales"))));
+        req.set_transport(Arc::new(RoutedTransport::new(vec![
+            ("areaName=wales", page(r#"["2020-10-01", 5]"#, "2020-10-02T16:00:00Z")),
+            ("areaName=england", page(r#"["2020-10-01", 50]"#, "2020-10-02T15:00:00Z")),
+        ])));
+
+        for _ in 0..20 {
+            req.ratio_to("england", MetricKind::NewCasesByPublishDate).unwrap();
+            assert_eq!(req.last_update().as_deref(), Some("2020-10-02T16:00:00Z"));
+        }
+    }
+
+    #[test]
+    fn cloned_requests_recorded_apart() {
+        let page = |time: &str| {
+            Response::new(200, format!(r#"{{"data": [[1]], "lastUpdate": "{}", "pagination": {{"next": null}}}}"#, time))
+        };
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(RoutedTransport::new(vec![
+            ("areaName=wales", page("2020-10-02T16:00:00Z")),
+            ("areaName=england", page("2020-10-02T15:00:00Z")),
+        ])));
+        req.set_diagnostics(true);
+
+        for _ in 0..20 {
+            let areas = [req.with_area_name("wales"), req.with_area_name("england")];
+            std::thread::scope(|s| {
+                for area in &areas {
+                    s.spawn(move || area.get().unwrap());
+                }
+            });
+
+            assert_eq!(areas[0].last_update().as_deref(), Some("2020-10-02T16:00:00Z"));
+            assert_eq!(areas[1].last_update().as_deref(), Some("2020-10-02T15:00:00Z"));
+            assert!(areas[0].last_diagnostics()[0].url.contains("areaName=wales"));
+            assert_eq!(areas[1].last_diagnostics().len(), 1);
+            assert_eq!(req.last_update(), None);
+        }
+    }
+
+    #[test]
+    fn poll_for_changes() {
+        let page = |rows: &str, next: bool, etag: &str| Response {
+            status: 200,
+            headers: vec![(String::from("ETag"), etag.to_string())],
+            body: MockTransport::page(rows, next).body,
+        };
+        let transport = Arc::new(MockTransport::new(vec![
+            page("[2]", true, "\"a\""),
+            MockTransport::page("[1]", false),
+            Response::new(304, ""),
+            page("[2]", true, "\"a\""),
+            page("[3]", true, "\"b\""),
+            MockTransport::page("[1]", false),
+        ]));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(transport.clone());
+
+        let mut fetched = vec![];
+        req.poll(std::time::Duration::ZERO, Some(4), |data| fetched.push(data)).unwrap();
+        assert_eq!(
+            fetched,
+            vec![
+                vec![
+                    vec![Metric::NewCasesByPublishDate(MetricValue::Int(2))],
+                    vec![Metric::NewCasesByPublishDate(MetricValue::Int(1))]
+                ],
+                vec![
+                    vec![Metric::NewCasesByPublishDate(MetricValue::Int(3))],
+                    vec![Metric::NewCasesByPublishDate(MetricValue::Int(1))]
+                ],
+            ]
+        );
+
+        // The first page of each poll is reused, so only later pages are 
+        // fetched again when the data changes.
+        let urls = transport.urls.lock().unwrap();
+        let pages: Vec<&str> = urls.iter().map(|u| &u[u.rfind("page=").unwrap()..]).collect();
+        assert_eq!(pages, vec!["page=1", "page=2", "page=1", "page=1", "page=1", "page=2"]);
+
+        let headers = transport.headers.lock().unwrap();
+        assert!(!headers[0].iter().any(|(n, _)| n == "If-None-Match"));
+        for i in &[2, 3, 4] {
+            assert!(headers[*i].contains(&(String::from("If-None-Match"), String::from("\"a\""))));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "cookies")]
+    fn poll_session_cookie() {
+        let mut first = MockTransport::page("[2]", true);
+        first.headers.push((String::from("Set-Cookie"), String::from("session=abc123; Path=/")));
+        let transport = Arc::new(MockTransport::new(vec![first, MockTransport::page("[1]", false)]));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(transport.clone());
+        req.set_cookie_store(true);
+
+        let mut fetched = vec![];
+        req.poll(std::time::Duration::ZERO, Some(1), |data| fetched.push(data)).unwrap();
+        assert_eq!(fetched[0].len(), 2);
+
+        // The polled first page's cookie is sent with the later pages.
+        let headers = transport.headers.lock().unwrap();
+        assert_eq!(headers.len(), 2);
+        assert!(headers[1].contains(&(String::from("Cookie"), String::from("session=abc123"))));
+    }
+
+    #[test]
+    #[cfg(feature = "cookies")]
+    fn session_cookie() {
+        let mut first = MockTransport::page("[2]", true);
+        first.headers.push((String::from("Set-Cookie"), String::from("session=abc123; Path=/; HttpOnly")));
+        let pages = vec![first, MockTransport::page("[1]", false)];
+
+        let transport = Arc::new(MockTransport::new(pages.clone()));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(transport.clone());
+        req.set_cookie_store(true);
+        assert_eq!(req.get().unwrap().len(), 2);
+
+        let headers = transport.headers.lock().unwrap();
+        assert!(!headers[0].iter().any(|(n, _)| n == "Cookie"));
+        assert!(headers[1].contains(&(String::from("Cookie"), String::from("session=abc123"))));
+
+        // Without the cookie store, the cookie isn't sent back.
+        let transport = Arc::new(MockTransport::new(pages));
+        req.set_transport(transport.clone());
+        req.set_cookie_store(false);
+        req.get().unwrap();
+        assert!(!transport.headers.lock().unwrap()[1].iter().any(|(n, _)| n == "Cookie"));
+    }
+
+    #[test]
+    fn for_each_breaks_early() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let rows = (0..6).rev().map(|i| format!("[{}]", i)).collect();
+        let transport = Arc::new(PagedTransport::new(rows, 2));
+        req.set_transport(transport.clone());
+
+        let mut seen = vec![];
+        req.for_each(|day| {
+            seen.push(day[0].as_i32().unwrap());
+            if seen.len() == 3 {
+                std::ops::ControlFlow::Break(())
+            } else {
+                std::ops::ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+
+        assert_eq!(seen, vec![5, 4, 3]);
+        // The third page was never fetched.
+        assert_eq!(transport.urls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn for_each_holds_one_page() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let rows = (0..5000).rev().map(|i| format!("[{}]", i)).collect();
+        let transport = Arc::new(PagedTransport::new(rows, 100));
+        req.set_transport(transport.clone());
+
+        // The days fetched but not yet passed to the callback never exceed a 
+        // page.
+        let mut seen = 0;
+        let mut most_pending = 0;
+        req.for_each(|_| {
+            seen += 1;
+            let fetched = transport.urls.lock().unwrap().len() * 100;
+            most_pending = most_pending.max(fetched - seen + 1);
+            std::ops::ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        assert_eq!(seen, 5000);
+        assert_eq!(transport.urls.lock().unwrap().len(), 50);
+        assert_eq!(most_pending, 100);
+    }
+
+    #[test]
+    fn for_each_streams_lines() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_format(Format::JsonL);
+        // Each line is only parsed once the previous day has been passed on,
+        // so stopping early never reaches the malformed last line.
+        let body = (0..1000).rev().map(|i| format!("[{}]\n", i)).collect::<String>() + "[oops";
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(200, body.as_str())])));
+
+        let mut seen = vec![];
+        req.for_each(|day| {
+            seen.push(day[0].as_i32());
+            if seen.len() == 3 {
+                std::ops::ControlFlow::Break(())
+            } else {
+                std::ops::ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+        assert_eq!(seen, vec![Some(999), Some(998), Some(997)]);
+
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(200, body.as_str())])));
+        let mut count = 0;
+        match req.for_each(|_| {
+            count += 1;
+            std::ops::ControlFlow::Continue(())
+        }) {
+            Err(Error::ParseError(s)) => assert!(s.contains("line 1001")),
+            r => panic!("expected ParseError, got {:?}", r),
+        }
+        assert_eq!(count, 1000);
+    }
+
+    #[test]
+    fn middle_page() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let rows = (0..6).rev().map(|i| format!("[{}]", i)).collect();
+        let transport = Arc::new(PagedTransport::new(rows, 2));
+        req.set_transport(transport.clone());
+
+        let (data, previous, next) = req.get_page(2).unwrap();
+        assert_eq!(data, vec![
+            vec![Metric::NewCasesByPublishDate(MetricValue::Int(3))],
+            vec![Metric::NewCasesByPublishDate(MetricValue::Int(2))],
+        ]);
+        assert_eq!((previous, next), (Some(1), Some(3)));
+        assert_eq!(transport.urls.lock().unwrap().len(), 1);
+
+        assert_eq!(req.get_page(1).unwrap().1, None);
+        assert_eq!(req.get_page(3).unwrap().2, None);
+    }
+
+    #[test]
+    fn seven_day_rate() {
+        let mut req = Request::new(AreaType::LTLA, Metric::AreaName(String::new()));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("Hackney"))));
+        // Nine days, newest first, with no value for the oldest.
+        let rows: Vec<String> = (1..=9)
+            .rev()
+            .map(|d| {
+                let value = if d == 1 { String::from("null") } else { (d * 10).to_string() };
+                format!(r#"["2020-10-{:02}", {}]"#, d, value)
+            })
+            .collect();
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(&rows.join(", "), false)])));
+
+        // 2-8 October total 350, and 3-9 October 420, for 280,000 people.
+        assert_eq!(req.rolling_rate_7day(MetricKind::NewCasesByPublishDate, 280_000).unwrap(), vec![
+            (Date::try_from_ymd(2020, 10, 8).unwrap(), 125.0),
+            (Date::try_from_ymd(2020, 10, 9).unwrap(), 150.0),
+        ]);
+    }
+
+    #[test]
+    fn waits_for_published_metric() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"[null, "2020-10-02"], [5, "2020-10-01"]"#, false),
+            MockTransport::page(r#"[7, "2020-10-02"], [5, "2020-10-01"]"#, false),
+        ]));
+        req.set_transport(transport.clone());
+
+        let start = std::time::Instant::now();
+        let data = req
+            .wait_for_metric(MetricKind::NewCasesByPublishDate, std::time::Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(data[0][0], Metric::NewCasesByPublishDate(MetricValue::Int(7)));
+        assert_eq!(transport.urls.lock().unwrap().len(), 2);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+    }
+
+    #[test]
+    fn equivalent_requests() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let hash = |req: &Request| {
+            let mut hasher = DefaultHasher::new();
+            req.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let mut a = Request::new(AreaType::LTLA, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        a.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        a.add_filter(Filter::new(FilterValue::AreaName(String::from("Hackney"))));
+        a.add_filter(Filter::new(FilterValue::Date(Date::try_from_ymd(2021, 1, 15).unwrap())));
+
+        let mut b = Request::with_metrics(AreaType::LTLA, &[MetricKind::Date, MetricKind::NewCasesByPublishDate]);
+        b.add_filter(Filter::new(FilterValue::Date(Date::try_from_ymd(2021, 1, 15).unwrap())));
+        b.add_filter(Filter::new(FilterValue::AreaName(String::from("Hackney"))));
+        b.set_partial_ok(true);
+
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+
+        b.add_metric(Metric::NewDeathsWithin28DaysByPublishDate(MetricValue::Null));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn available_metrics() {
+        let mut req = Request::new(AreaType::Nation, Metric::AreaCode(String::new()));
+        let transport = Arc::new(MockTransport::new(vec![Response::new(
+            200,
+            r#"[{"metric": "newCasesByPublishDate"}, {"metric": "hospitalCases"}, "cumPeopleVaccinatedFirstDoseByPublishDate"]"#,
+        )]));
+        req.set_transport(transport.clone());
+
+        let names = req.metric_names().unwrap();
+        assert_eq!(names, vec![
+            "newCasesByPublishDate",
+            "hospitalCases",
+            "cumPeopleVaccinatedFirstDoseByPublishDate",
+        ]);
+        assert_eq!(names[0].parse::<MetricKind>(), Ok(MetricKind::NewCasesByPublishDate));
+        assert_eq!(transport.urls.lock().unwrap()[0], format!("{}/nation", METRICS_URL));
+    }
+
+    #[test]
+    fn get_since_stops_at_last() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"[5, "2020-10-05"], [4, "2020-10-04"]"#, true),
+            MockTransport::page(r#"[3, "2020-10-03"], [2, "2020-10-02"]"#, true),
+            MockTransport::page(r#"[1, "2020-10-01"]"#, false),
+        ]));
+        req.set_transport(transport.clone());
+
+        let data = req.get_since(Date::try_from_ymd(2020, 10, 3).unwrap()).unwrap();
+        assert_eq!(data, vec![
+            vec![Metric::NewCasesByPublishDate(MetricValue::Int(5)), Metric::Date(Date::try_from_ymd(2020, 10, 5).unwrap())],
+            vec![Metric::NewCasesByPublishDate(MetricValue::Int(4)), Metric::Date(Date::try_from_ymd(2020, 10, 4).unwrap())],
+        ]);
+        assert_eq!(transport.urls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn nation_request() {
+        let req = Request::nation(Nation::NorthernIreland, Metric::NewCasesByPublishDate(MetricValue::Null));
+        assert_eq!(req.filters_str(), "areaType=nation;areaName=northern ireland");
+    }
+
+    #[test]
+    fn html_error_page() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let mut res = Response::new(200, "<html><body><h1>502 Bad Gateway</h1></body></html>");
+        res.headers.push((String::from("Content-Type"), String::from("text/html; charset=utf-8")));
+        req.set_transport(Arc::new(MockTransport::new(vec![res])));
+
+        match req.get() {
+            Err(Error::ParseError(s)) => assert!(s.contains("<h1>502 Bad Gateway</h1>")),
+            r => panic!("expected ParseError, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn helpers_do_not_duplicate_metrics() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"["2020-10-05", 5]"#, false),
+            MockTransport::page(r#"["2020-10-05", 5]"#, false),
+        ]));
+        req.set_transport(transport.clone());
+
+        req.fetch_single_i32(MetricKind::NewCasesByPublishDate).unwrap();
+        req.get_since(Date::try_from_ymd(2020, 10, 1).unwrap()).unwrap();
+
+        for url in transport.urls.lock().unwrap().iter() {
+            assert!(url.contains("structure=[%22date%22, %22newCasesByPublishDate%22]&"));
+        }
+    }
+
+    #[test]
+    fn named_structure() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::CumulativeCasesByPublishDate(MetricValue::Null));
+        req.set_named_structure(true);
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"{"cumCasesByPublishDate": 502978, "date": "2020-10-03"}"#,
+            false,
+        )]));
+        req.set_transport(transport.clone());
+
+        assert_eq!(req.get().unwrap(), vec![vec![
+            Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap()),
+            Metric::CumulativeCasesByPublishDate(MetricValue::Int(502978)),
+        ]]);
+        assert!(transport.urls.lock().unwrap()[0].contains(
+            "&structure={%22date%22:%22date%22, %22cumCasesByPublishDate%22:%22cumCasesByPublishDate%22}&"
+        ));
+    }
+
+    #[test]
+    fn short_day() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::CumulativeCasesByPublishDate(MetricValue::Null));
+        let page = r#"["2020-10-03", 12, 400], ["2020-10-02", 3]"#;
+        req.set_transport(Arc::new(MockTransport::new(vec![
+            MockTransport::page(page, false),
+            MockTransport::page(page, false),
+        ])));
+
+        assert_eq!(req.get().unwrap(), vec![
+            vec![
+                Metric::Date(Date::try_from_ymd(2020, 10, 3).unwrap()),
+                Metric::NewCasesByPublishDate(MetricValue::Int(12)),
+                Metric::CumulativeCasesByPublishDate(MetricValue::Int(400)),
+            ],
+            vec![
+                Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap()),
+                Metric::NewCasesByPublishDate(MetricValue::Int(3)),
+                Metric::CumulativeCasesByPublishDate(MetricValue::Null),
+            ],
+        ]);
+
+        // The missing value is written as an empty cell, so later columns 
+        // stay in place.
+        let mut csv = vec![];
+        req.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "date,newCasesByPublishDate,cumCasesByPublishDate\n2020-10-03,12,400\n2020-10-02,3,\n"
+        );
+    }
+
+    #[test]
+    fn dry_run_matches_execute() {
+        let mut req = Request::nation(Nation::England, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page("[1]", false)]));
+        req.set_transport(transport.clone());
+
+        let urls = req.dry_run();
+        req.get().unwrap();
+        assert_eq!(urls, *transport.urls.lock().unwrap());
+        assert_eq!(
+            urls[0],
+            "https://api.coronavirus.data.gov.uk/v1/data?filters=areaType=nation;areaName=england&structure=[%22newCasesByPublishDate%22]&format=json&page=1"
+        );
+    }
+
+    #[test]
+    fn metric_availability() {
+        assert!(!MetricKind::HospitalCases.available_for(&AreaType::LTLA));
+        assert!(!MetricKind::NewAdmissions.available_for(&AreaType::LTLA));
+        assert!(MetricKind::HospitalCases.available_for(&AreaType::NHSRegion));
+        assert!(MetricKind::NewCasesByPublishDate.available_for(&AreaType::LTLA));
+        assert!(!MetricKind::NewCasesByPublishDate.available_for(&AreaType::NHSRegion));
+        assert!(MetricKind::Date.available_for(&AreaType::NHSRegion));
+    }
+
+    #[test]
+    fn get_all_uses_fewer_pages() {
+        let rows: Vec<String> = (1..=5).rev().map(|d| format!(r#"["2020-10-0{}", {}]"#, d, d)).collect();
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+
+        let paged = Arc::new(PagedTransport::new(rows.clone(), 2));
+        req.set_transport(paged.clone());
+        let data = req.get().unwrap();
+        assert_eq!(paged.urls.lock().unwrap().len(), 3);
+
+        let all = Arc::new(PagedTransport::new(rows, 2));
+        req.set_transport(all.clone());
+        assert_eq!(req.get_all().unwrap(), data);
+        assert_eq!(data.len(), 5);
+        let urls = all.urls.lock().unwrap();
+        assert_eq!(urls.len(), 1);
+        assert!(urls[0].ends_with("&pageSize=2500"));
+    }
+
+    #[test]
+    fn reexported_date_filter() {
+        let mut req = Request::nation(Nation::England, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::Date(crate::Date::try_from_ymd(2021, 1, 15).unwrap())));
+        assert_eq!(req.filters_str(), "areaType=nation;areaName=england;date=2021-01-15");
+    }
+
+    #[test]
+    fn pages_with_urls() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![
+            MockTransport::page("[3], [2]", true),
+            MockTransport::page("[1]", false),
+        ])));
+
+        let pages = req.get_pages().unwrap();
+        assert_eq!(pages.len(), 2);
+        assert!(pages[0].0.ends_with("&page=1"));
+        assert_eq!(pages[0].1, vec![
+            vec![Metric::NewCasesByPublishDate(MetricValue::Int(3))],
+            vec![Metric::NewCasesByPublishDate(MetricValue::Int(2))],
+        ]);
+        assert!(pages[1].0.ends_with("&page=2"));
+        assert_eq!(pages[1].1, vec![vec![Metric::NewCasesByPublishDate(MetricValue::Int(1))]]);
+    }
+
+    #[test]
+    fn link_header_pagination() {
+        let page = |rows: &str, link: &str| Response {
+            status: 200,
+            headers: vec![(String::from("Link"), link.to_string())],
+            body: format!(r#"{{"data": [{}]}}"#, rows).into_bytes(),
+        };
+        let transport = Arc::new(MockTransport::new(vec![
+            page("[3], [2]", r#"</v1/data?cursor=b>; rel="next""#),
+            page("[1]", r#"<https://mirror.example/v1/data?cursor=c>; rel="next""#),
+            page("[0]", r#"</v1/data?cursor=b>; rel="prev", </v1/data?page=1>; rel=first"#),
+        ]));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(transport.clone());
+
+        let cases: Vec<Option<i32>> = req.get().unwrap().iter().map(|day| day[0].as_i32()).collect();
+        assert_eq!(cases, vec![Some(3), Some(2), Some(1), Some(0)]);
+        let urls = transport.urls.lock().unwrap();
+        assert_eq!(urls.len(), 3);
+        assert!(urls[0].ends_with("&page=1"));
+        assert_eq!(urls[1], "https://api.coronavirus.data.gov.uk/v1/data?cursor=b");
+        assert_eq!(urls[2], "https://mirror.example/v1/data?cursor=c");
+
+        assert_eq!(link_next(r#"<https://x/?page=3>; rel="prev next""#), Some(String::from("https://x/?page=3")));
+        assert_eq!(link_next(r#"<https://x/?page=1>; rel="prev""#), None);
+    }
+
+    #[test]
+    fn prioritised_metrics() {
+        capture_warnings();
+
+        let metrics = [
+            MetricKind::Date,
+            MetricKind::AreaName,
+            MetricKind::NewCasesByPublishDate,
+            MetricKind::CumulativeCasesByPublishDate,
+            MetricKind::NewDeathsWithin28DaysByPublishDate,
+            MetricKind::CumulativeDeathsWithin28DaysByPublishDate,
+            MetricKind::HospitalCases,
+        ];
+        let mut req = Request::with_metrics(AreaType::Nation, &metrics);
+        assert_eq!(req.config.metrics.len(), 7);
+
+        req.set_metric_priority(vec![
+            MetricKind::Date,
+            MetricKind::HospitalCases,
+            MetricKind::NewCasesByPublishDate,
+            MetricKind::NewDeathsWithin28DaysByPublishDate,
+            MetricKind::AreaName,
+            MetricKind::CumulativeCasesByPublishDate,
+        ]);
+        assert_eq!(
+            req.config.metrics,
+            vec![
+                MetricKind::Date,
+                MetricKind::AreaName,
+                MetricKind::NewCasesByPublishDate,
+                MetricKind::NewDeathsWithin28DaysByPublishDate,
+                MetricKind::HospitalCases,
+            ]
+        );
+        assert_eq!(
+            warnings("Dropping cumCasesByPublishDate, cumDeaths28DaysByPublishDate"),
+            vec!["Dropping cumCasesByPublishDate, cumDeaths28DaysByPublishDate to keep within the API's limit of 5 metrics"]
+        );
+
+        req.add_metric(Metric::CumulativeCasesByPublishDate(MetricValue::Null));
+        assert_eq!(req.config.metrics.len(), 5);
+        assert!(!req.config.metrics.contains(&MetricKind::CumulativeCasesByPublishDate));
+    }
+
+    #[test]
+    fn data_for_area_types() {
+        let mut req = Request::new(AreaType::Nation, Metric::AreaName(String::new()));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("england"))));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        let transport = Arc::new(RoutedTransport::new(vec![
+            ("areaType=nation&", MockTransport::page(r#"["england", 10], ["wales", 2]"#, false)),
+            ("areaType=region&", MockTransport::page(r#"["london", 3]"#, false)),
+        ]));
+        req.set_transport(transport.clone());
+
+        let data = req.get_for_area_types(&[AreaType::Nation, AreaType::Region]).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data["nation"].len(), 2);
+        assert_eq!(
+            data["region"],
+            vec![vec![Metric::AreaName(String::from("london")), Metric::NewCasesByPublishDate(MetricValue::Int(3))]]
+        );
+        assert!(transport.urls.lock().unwrap().iter().all(|url| !url.contains("areaName=")));
+    }
+
+    #[test]
+    fn error_context() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("england"))));
+        req.add_metric(Metric::HospitalCases(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(500, "")])));
+        req.set_error_context(true);
+
+        match req.get() {
+            Err(Error::WithContext { area, metrics, source }) => {
+                assert_eq!(area, "england");
+                assert_eq!(metrics, vec!["newCasesByPublishDate", "hospitalCases"]);
+                assert!(matches!(*source, Error::APIServerError));
+            }
+            r => panic!("expected WithContext, got {:?}", r),
+        }
+
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(500, "")])));
+        req.set_error_context(false);
+        match req.get() {
+            Err(Error::APIServerError) => {}
+            r => panic!("expected APIServerError, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn raw_response() {
+        let body = r#"{"data": [[1]], "pagination": {"next": null}}"#;
+        let url = transport::serve(vec![(
+            std::time::Duration::from_millis(0),
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+        )]);
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_base_url(&url);
+
+        let res = req.send_raw().unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.headers()["content-type"], "application/json");
+        assert_eq!(res.text().unwrap(), body);
+    }
+
+    #[test]
+    fn missing_pagination() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(200, r#"{"data": [[1]]}"#)])));
+
+        match req.get() {
+            Err(Error::IncompatibleApiVersion(s)) => assert!(s.contains("pagination")),
+            r => panic!("expected IncompatibleApiVersion, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn repeated_filter_replaces() {
+        let first = crate::Date::try_from_ymd(2021, 1, 15).unwrap();
+        let second = crate::Date::try_from_ymd(2021, 2, 1).unwrap();
+        let mut req = Request::nation(Nation::England, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::Date(first)));
+        req.add_filter(Filter::new(FilterValue::Date(second)));
+
+        assert_eq!(req.filters.len(), 3);
+        assert_eq!(req.filters[2], Filter::new(FilterValue::Date(second)));
+        assert_eq!(req.filters_str(), "areaType=nation;areaName=england;date=2021-02-01");
+    }
+
+    #[test]
+    fn query_too_broad() {
+        let mut req = Request::new(AreaType::LTLA, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::NewCasesBySpecimenDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![
+            Response::new(
+                400,
+                r#"{"response": "Request exceeds the maximum number of records per query.", "status_code": 400}"#,
+            ),
+            Response::new(413, "Request Entity Too Large"),
+        ])));
+
+        match req.get() {
+            Err(Error::QueryTooBroad(message)) => {
+                assert_eq!(message, "Request exceeds the maximum number of records per query.")
+            }
+            r => panic!("expected QueryTooBroad, got {:?}", r),
+        }
+        assert!(matches!(req.get(), Err(Error::QueryTooBroad(_))));
+    }
+
+    #[test]
+    fn unexpected_statuses_returned() {
+        for status in &[100, 302, 304] {
+            let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+            req.set_transport(Arc::new(MockTransport::new(vec![Response::new(*status, "Found")])));
+
+            match (status, req.get()) {
+                (304, Err(Error::NotModified)) => {}
+                (100, Err(Error::ApiError { status: 100, message })) | (302, Err(Error::ApiError { status: 302, message })) => {
+                    assert_eq!(message, "Found")
+                }
+                (_, r) => panic!("expected an error for status {}, got {:?}", status, r),
+            }
+        }
+    }
+
+    #[test]
+    fn precondition_failed() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(412, "Precondition Failed")])));
+
+        match req.get() {
+            Err(Error::PreconditionFailed) => {}
+            r => panic!("expected PreconditionFailed, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn api_error_message_extracted() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(
+            400,
+            r#"{"response": "Invalid metric: newCasesByPublishDat", "status_code": 400}"#,
+        )])));
+
+        match req.get() {
+            Err(Error::ApiError { status, message }) => {
+                assert_eq!(status, 400);
+                assert_eq!(message, "Invalid metric: newCasesByPublishDat");
+            }
+            r => panic!("expected ApiError, got {:?}", r),
+        }
+        assert_eq!(api_error_message("Not Found"), "Not Found");
+    }
+
+    #[test]
+    fn rate_limit_hint_attached() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(
+            429,
+            r#"{"message": "Rate limit exceeded", "reset": "2021-01-01T12:00:00Z"}"#,
+        )])));
+
+        match req.get() {
+            Err(Error::TooManyRequests(hint)) => assert_eq!(hint.as_deref(), Some("2021-01-01T12:00:00Z")),
+            r => panic!("expected TooManyRequests, got {:?}", r),
+        }
+        assert_eq!(rate_limit_hint(r#"{"retryAfter": 30}"#).as_deref(), Some("30"));
+        assert_eq!(rate_limit_hint("Too Many Requests"), None);
+    }
+
+    #[test]
+    fn validate_flags_empty_metric() {
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(r#"[120, null]"#, true)]));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::NewDeathsWithin28DaysByPublishDate(MetricValue::Null));
+        req.set_transport(transport.clone());
+
+        let report = req.validate().unwrap();
+        assert!(matches!(report.metrics[0], (MetricKind::NewCasesByPublishDate, MetricStatus::Present)));
+        assert!(matches!(report.metrics[1], (MetricKind::NewDeathsWithin28DaysByPublishDate, MetricStatus::Empty)));
+        assert_eq!(report.problems(), vec![&MetricKind::NewDeathsWithin28DaysByPublishDate]);
+        assert!(!report.is_ok());
+
+        let urls = transport.urls.lock().unwrap();
+        assert_eq!(urls.len(), 1);
+        assert!(urls[0].contains("latestBy=date"));
+    }
+
+    #[test]
+    fn validate_attributes_rejection() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::generic("newCasesByPublishDat"));
+        req.set_transport(Arc::new(MockTransport::new(vec![
+            Response::new(400, r#"{"response": "Invalid metric"}"#),
+            MockTransport::page("[120]", false),
+            Response::new(400, r#"{"response": "Invalid metric"}"#),
+        ])));
+
+        let report = req.validate().unwrap();
+        assert!(matches!(report.metrics[0].1, MetricStatus::Present));
+        assert!(matches!(report.metrics[1].1, MetricStatus::Failed(Error::ApiError { status: 400, .. })));
+    }
+
+    #[test]
+    fn float_in_integer_metric() {
+        capture_warnings();
+
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::CumulativePillarOneTestsByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            "[431.5, 2718.25], [null, 7]",
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(
+            data,
+            vec![
+                vec![
+                    Metric::NewCasesByPublishDate(MetricValue::Float(431.5)),
+                    Metric::CumulativePillarOneTestsByPublishDate(MetricValue::Float(2718.25))
+                ],
+                vec![
+                    Metric::NewCasesByPublishDate(MetricValue::Null),
+                    Metric::CumulativePillarOneTestsByPublishDate(MetricValue::Int(7))
+                ],
+            ]
+        );
+        assert_eq!(data[0][0].as_i32(), None);
+        assert_eq!(
+            warnings("431.5")[0],
+            "newCasesByPublishDate expects an integer, but the API returned 431.5; keeping it as sent"
+        );
+        assert_eq!(warnings("2718.25").len(), 1);
+    }
+
+    #[test]
+    fn numeric_values_normalised() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::CumulativePillarOneTestsByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["12", 3.0]"#,
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(
+            data,
+            vec![vec![
+                Metric::NewCasesByPublishDate(MetricValue::Int(12)),
+                Metric::CumulativePillarOneTestsByPublishDate(MetricValue::Int(3))
+            ]]
+        );
+        assert_eq!(data[0][0].as_i32(), Some(12));
+        assert_eq!(data[0][1].as_f64(), Some(3.0));
+    }
+
+    #[test]
+    fn misspelt_area_suggestions() {
+        let transport = Arc::new(MockTransport::new(vec![
+            Response::new(204, ""),
+            MockTransport::page(
+                r#"["E09000012", "Hackney"], ["E09000007", "Camden"], ["E07000117", "Burnley"]"#,
+                false,
+            ),
+        ]));
+        let mut req = Request::new(AreaType::LTLA, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("hackny"))));
+        req.set_suggest_area_names(true);
+        req.set_transport(transport.clone());
+
+        match req.get() {
+            Err(Error::NoDataDidYouMean { tried, suggestions }) => {
+                assert_eq!(tried, "hackny");
+                assert_eq!(suggestions, vec!["Hackney"]);
+            }
+            r => panic!("expected NoDataDidYouMean, got {:?}", r),
+        }
+        // The areas are listed without the misspelt name.
+        assert!(!transport.urls.lock().unwrap()[1].contains("hackny"));
+
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn names_parse() {
+        for metric in KNOWN_METRICS {
+            assert_eq!(metric.name().parse::<MetricKind>(), Ok(metric.clone()));
+        }
+        assert_eq!("growthRate".parse::<MetricKind>(), Ok(MetricKind::Generic {
+                name: String::from("growthRate"),
+                value_type: ValueType::Json
+            }));
+
+        assert_eq!("nhsRegion".parse::<AreaType>().unwrap(), AreaType::NHSRegion);
+        assert!(matches!("county".parse::<AreaType>(), Err(Error::UnknownName(n)) if n == "county"));
+
+        let mut req = Request::with_metrics(AreaType::Nation, &[MetricKind::Date, MetricKind::Date, MetricKind::HospitalCases]);
+        assert_eq!(req.config.metrics, vec![MetricKind::Date, MetricKind::HospitalCases]);
+        req.set_base_url("http://localhost:8080/v1/data");
+        assert!(req.url().starts_with("http://localhost:8080/v1/data?filters=areaType=nation&"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_into_own_type() {
+        use serde::{Deserialize, Deserializer};
+
+        #[derive(Debug, PartialEq)]
+        struct Day {
+            date: String,
+            cases: Option<u32>,
+        }
+        impl<'de> Deserialize<'de> for Day {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Day, D::Error> {
+                let mut fields = std::collections::HashMap::<String, serde_json::Value>::deserialize(d)?;
+                let date = fields.remove("date").and_then(|v| v.as_str().map(String::from));
+                Ok(Day {
+                    date: date.ok_or_else(|| serde::de::Error::missing_field("date"))?,
+                    cases: fields
+                        .remove("newCasesByPublishDate")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as u32),
+                })
+            }
+        }
+
+        let days = r#"{"date": "2021-01-02", "newCasesByPublishDate": 120}, {"date": "2021-01-01", "newCasesByPublishDate": null}"#;
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(days, false), MockTransport::page(days, false)]));
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(transport.clone());
+
+        assert_eq!(
+            req.get_as::<Day>().unwrap(),
+            vec![
+                Day { date: String::from("2021-01-02"), cases: Some(120) },
+                Day { date: String::from("2021-01-01"), cases: None },
+            ]
+        );
+        assert!(transport.urls.lock().unwrap()[0].contains("structure={%22date%22:%22date%22"));
+        assert!(matches!(req.get_as::<u32>(), Err(Error::ParseError(_))));
+    }
+
+    #[test]
+    fn grouped_by_area_code() {
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"[5, "E06000001"], [7, "E06000002"], [4, "E06000001"]"#,
+            false,
+        )]));
+        let mut req = Request::new(AreaType::UTLA, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(transport.clone());
+
+        let areas = req.get_by_area().unwrap();
+        assert_eq!(areas.len(), 2);
+        assert_eq!(areas["E06000001"].len(), 2);
+        assert_eq!(areas["E06000002"][0][0], Metric::NewCasesByPublishDate(MetricValue::Int(7)));
+        assert!(transport.urls.lock().unwrap()[0].contains("%22areaCode%22"));
+
+        req.ensure_metric(MetricKind::NewCasesByPublishDate);
+        assert_eq!(req.config.metrics, vec![MetricKind::NewCasesByPublishDate]);
+    }
+
+    #[test]
+    fn filter_display() {
+        let date = Filter::new(FilterValue::Date(Date::try_from_ymd(2021, 1, 15).unwrap()));
+        assert_eq!(date.to_string(), "date=2021-01-15");
+        assert_eq!(Filter::new(FilterValue::AreaType(AreaType::NHSRegion)).to_string(), "areaType=nhsRegion");
+
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(date);
+        assert_eq!(req.filters_str(), "areaType=nation;date=2021-01-15");
+    }
+
+    #[test]
+    fn week_indexed_metric() {
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"["2021-W03", 1200], ["2021-W02", 1350]"#, false),
+            MockTransport::page(r#"["2021-03", 1200]"#, false),
+        ]));
+        let week = |w| Week::new(2021, w).unwrap();
+        let mut req = Request::nation(Nation::England, Metric::Week(week(1)));
+        req.add_metric(Metric::generic("weeklyPositivity"));
+        req.add_filter(Filter::new(week(3).into()));
+        req.set_transport(transport.clone());
+
+        let data = req.get().unwrap();
+        assert_eq!(data[0][0], Metric::Week(week(3)));
+        assert_eq!(data[1][0], Metric::Week(week(2)));
+        assert!(transport.urls.lock().unwrap()[0].contains(";week=2021-W03&"));
+        assert!(matches!(req.get(), Err(Error::ParseError(_))));
+    }
+
+    #[test]
+    fn metric_name_fallback() {
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"[20, "2021-01-03"], [null, "2021-01-02"]"#, false),
+            MockTransport::page(r#"[null, "2021-01-03"], [15, "2021-01-02"], [10, "2021-01-01"]"#, false),
+        ]));
+        let mut req = Request::new(AreaType::Nation, Metric::NewPillarOneTestsByPublishDate(MetricValue::Null));
+        req.add_metric_with_fallback(
+            MetricKind::NewPillarOneTestsByPublishDate,
+            MetricKind::Generic {
+                name: String::from("newPillarOneTests"),
+                value_type: ValueType::Json,
+            },
+        );
+        req.set_transport(transport.clone());
+
+        let d = |day| Metric::Date(Date::try_from_ymd(2021, 1, day).unwrap());
+        assert_eq!(
+            req.get().unwrap(),
+            vec![
+                vec![Metric::NewPillarOneTestsByPublishDate(MetricValue::Int(20)), d(3)],
+                vec![Metric::NewPillarOneTestsByPublishDate(MetricValue::Int(15)), d(2)],
+                vec![Metric::NewPillarOneTestsByPublishDate(MetricValue::Int(10)), d(1)],
+            ]
+        );
+        let urls = transport.urls.lock().unwrap();
+        assert_eq!(urls.len(), 2);
+        assert!(urls[1].contains("structure=[%22newPillarOneTests%22, %22date%22]"));
+    }
+
+    #[test]
+    fn raw_values_alongside_metrics() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::TransmissionRateMin(MetricValue::Null));
+        req.set_null_default(MetricKind::NewCasesByPublishDate, 0);
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["2021-01-02", 120, 0.9], ["2021-01-01", null, null]"#,
+            false,
+        )])));
+
+        let days = req.get_verbose().unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(
+            days[0],
+            vec![
+                (Metric::Date(Date::try_from_ymd(2021, 1, 2).unwrap()), json::JsonValue::from("2021-01-02")),
+                (Metric::NewCasesByPublishDate(MetricValue::Int(120)), json::JsonValue::from(120)),
+                (Metric::TransmissionRateMin(MetricValue::Float(0.9)), json::JsonValue::from(0.9)),
+            ]
+        );
+        assert_eq!(days[1][1], (Metric::NewCasesByPublishDate(MetricValue::Int(0)), json::JsonValue::Null));
+        assert_eq!(days[1][2], (Metric::TransmissionRateMin(MetricValue::Null), json::JsonValue::Null));
+    }
+
+    #[test]
+    fn aliased_metrics() {
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"{"day": "2021-01-02", "cases": 2600000}"#, false),
+            MockTransport::page(r#"{"day": "2021-01-02", "cases": 2600000}"#, false),
+        ]));
+        let mut req = Request::nation(Nation::England, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric_as(MetricKind::Date, "day");
+        req.add_metric_as(MetricKind::CumulativeCasesByPublishDate, "cases");
+        req.set_transport(transport.clone());
+
+        assert_eq!(
+            req.get().unwrap(),
+            vec![vec![
+                Metric::Date(Date::try_from_ymd(2021, 1, 2).unwrap()),
+                Metric::CumulativeCasesByPublishDate(MetricValue::Int(2600000))
+            ]]
+        );
+        assert!(transport.urls.lock().unwrap()[0]
+            .contains("structure={%22day%22:%22date%22, %22cases%22:%22cumCasesByPublishDate%22}"));
+
+        let mut csv = vec![];
+        req.write_csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "day,cases\n2021-01-02,2600000\n");
+    }
+
+    #[test]
+    fn chronological_order() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_order(Order::Chronological);
+        assert_eq!(req.config.metrics, vec![MetricKind::NewCasesByPublishDate, MetricKind::Date]);
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"[3, "2021-01-03"], [2, "2021-01-02"], [1, "2021-01-01"]"#,
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        let dates: Vec<_> = data.iter().map(|day| data::date_of(day).unwrap()).collect();
+        assert_eq!(
+            dates,
+            vec![
+                Date::try_from_ymd(2021, 1, 1).unwrap(),
+                Date::try_from_ymd(2021, 1, 2).unwrap(),
+                Date::try_from_ymd(2021, 1, 3).unwrap(),
+            ]
+        );
+        assert_eq!(data[0][0], Metric::NewCasesByPublishDate(MetricValue::Int(1)));
+    }
+
+    #[test]
+    fn alert_level() {
+        let mut req = Request::new(AreaType::LTLA, Metric::Date(Date::try_from_ymd(2020, 12, 1).unwrap()));
+        req.add_metric(Metric::AlertLevel(MetricValue::Null));
+        req.add_metric(Metric::AlertLevelName(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["2020-12-02", 3, "Very High"], ["2020-12-01", "2", null]"#,
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(data[0][1], Metric::AlertLevel(MetricValue::Int(3)));
+        assert_eq!(data[0][2], Metric::AlertLevelName(MetricValue::Text(String::from("Very High"))));
+        assert_eq!(data[1][1], Metric::AlertLevel(MetricValue::Int(2)));
+        assert_eq!(data[1][2], Metric::AlertLevelName(MetricValue::Null));
+    }
+
+    #[test]
+    fn deaths_by_death_date() {
+        let mut req = Request::with_metrics(AreaType::Nation, &[
+            MetricKind::Date,
+            MetricKind::NewDeathsWithin28DaysByDeathDate,
+            MetricKind::CumulativeDeathsWithin28DaysByDeathDate,
+            MetricKind::NewDeathsWithin60DaysByDeathDate,
+            MetricKind::CumulativeDeathsWithin60DaysByDeathDate,
+        ]);
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("england"))));
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["2021-01-19", 1290, 85461, 1398, 95139]"#,
+            false,
+        )]));
+        req.set_transport(transport.clone());
+
+        assert_eq!(req.get().unwrap()[0][1..], [
+            Metric::NewDeathsWithin28DaysByDeathDate(MetricValue::Int(1290)),
+            Metric::CumulativeDeathsWithin28DaysByDeathDate(MetricValue::Int(85461)),
+            Metric::NewDeathsWithin60DaysByDeathDate(MetricValue::Int(1398)),
+            Metric::CumulativeDeathsWithin60DaysByDeathDate(MetricValue::Int(95139)),
+        ]);
+        assert!(transport.urls.lock().unwrap()[0].contains(
+            "%22newDeaths28DaysByDeathDate%22, %22cumDeaths28DaysByDeathDate%22, \
+             %22newDeaths60DaysByDeathDate%22, %22cumDeaths60DaysByDeathDate%22"
+        ));
+    }
+
+    #[test]
+    fn future_date_filter() {
+        let tomorrow = uk_date(time::OffsetDateTime::now_utc()) + time::Duration::day();
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::Date(tomorrow)));
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(204, "")])));
+
+        assert!(matches!(req.get(), Err(Error::NoData)));
+        req.set_strict(true);
+        assert!(matches!(req.get(), Err(Error::FutureDate(d)) if d == tomorrow));
+    }
+
+    #[test]
+    fn uk_dates() {
+        let uk = |y, m, d, h, min| {
+            let date = Date::try_from_ymd(y, m, d).unwrap();
+            let time = time::Time::try_from_hms(h, min, 0).unwrap();
+            let date = uk_date(time::PrimitiveDateTime::new(date, time).assume_utc());
+            (date.year(), date.month(), date.day())
+        };
+
+        // 23:30 UTC is already the next day during British Summer Time.
+        assert_eq!(uk(2021, 6, 1, 23, 30), (2021, 6, 2));
+        assert_eq!(uk(2021, 1, 1, 23, 30), (2021, 1, 1));
+        // Summer Time ran from 01:00 UTC on 2021-03-28 to 01:00 UTC on 
+        // 2021-10-31.
+        assert_eq!(uk(2021, 3, 27, 23, 30), (2021, 3, 27));
+        assert_eq!(uk(2021, 3, 28, 23, 30), (2021, 3, 29));
+        assert_eq!(uk(2021, 10, 30, 23, 30), (2021, 10, 31));
+        assert_eq!(uk(2021, 10, 31, 23, 30), (2021, 10, 31));
+    }
+
+    #[test]
+    fn parsed_filters() {
+        for filter in &[
+            Filter::new(FilterValue::AreaType(AreaType::NHSRegion)),
+            Filter::new(FilterValue::AreaName(String::from("northern ireland"))),
+            Filter::new(FilterValue::AreaCode(AreaCode::new("E08000035").unwrap())),
+            Filter::new(FilterValue::Date(Date::try_from_ymd(2021, 1, 15).unwrap())),
+            Filter::new(FilterValue::Week(Week::new(2021, 3).unwrap())),
+        ] {
+            assert_eq!(filter.to_string().parse::<Filter>().unwrap(), *filter);
+        }
+        assert!(matches!("areaType=county".parse::<Filter>(), Err(Error::UnknownName(_))));
+        assert!(matches!("hash=abc".parse::<Filter>(), Err(Error::UnknownName(_))));
+        assert!(matches!("date=15/01/2021".parse::<Filter>(), Err(Error::ParseError(_))));
+    }
+
+    #[test]
+    fn many_metrics_merged() {
+        let metrics = [
+            MetricKind::NewCasesByPublishDate,
+            MetricKind::CumulativeCasesByPublishDate,
+            MetricKind::NewCasesBySpecimenDate,
+            MetricKind::NewDeathsWithin28DaysByPublishDate,
+            MetricKind::CumulativeDeathsWithin28DaysByPublishDate,
+            MetricKind::NewDeathsWithin28DaysByDeathDate,
+            MetricKind::NewAdmissions,
+            MetricKind::HospitalCases,
+        ];
+        let mut req = Request::new(AreaType::Nation, Metric::AreaName(String::new()));
         req.add_filter(Filter::new(FilterValue::AreaName(String::from("england"))));
+        let transport = Arc::new(RoutedTransport::new(vec![
+            (
+                "%22newCasesByPublishDate%22",
+                MockTransport::page(r#"["2020-10-02", 1, 2, 3, 4], ["2020-10-01", 10, 20, 30, 40]"#, false),
+            ),
+            (
+                "%22cumDeaths28DaysByPublishDate%22",
+                MockTransport::page(r#"["2020-10-02", 5, 6, 7, 8], ["2020-10-01", 50, 60, 70, 80]"#, false),
+            ),
+        ]));
+        req.set_transport(transport.clone());
+
+        let data = req.get_many_metrics(&metrics).unwrap();
+        assert_eq!(transport.urls.lock().unwrap().len(), 2);
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0][0], Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap()));
+        for (day, scale) in data.iter().zip(&[1, 10]) {
+            assert_eq!(day.len(), 9);
+            for (i, (metric, kind)) in day[1..].iter().zip(&metrics).enumerate() {
+                assert_eq!(metric.kind(), *kind);
+                assert_eq!(metric.as_i32(), Some((i as i32 + 1) * scale));
+            }
+        }
+    }
+
+    #[test]
+    fn second_dose_uptake() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["2021-09-03", 71.2], ["2021-09-02", null], ["2021-09-01", 70.9]"#,
+            false,
+        )]));
+        req.set_transport(transport.clone());
+
+        assert_eq!(req.vaccination_uptake("Wales", Dose::Second).unwrap(), vec![
+            (Date::try_from_ymd(2021, 9, 1).unwrap(), 70.9),
+            (Date::try_from_ymd(2021, 9, 3).unwrap(), 71.2),
+        ]);
+        let url = &transport.urls.lock().unwrap()[0];
+        assert!(url.contains("areaName=Wales"));
+        assert!(url.contains("structure=[%22date%22, %22cumVaccinationSecondDoseUptakeByPublishDatePercentage%22]"));
+    }
+
+    #[test]
+    fn ratio_to_other_area() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("wales"))));
+        req.set_transport(Arc::new(RoutedTransport::new(vec![
+            (
+                "areaName=wales",
+                MockTransport::page(r#"["2020-10-04", 30], ["2020-10-03", 10], ["2020-10-02", null], ["2020-10-01", 5]"#, false),
+            ),
+            (
+                "areaName=england",
+                MockTransport::page(r#"["2020-10-03", 40], ["2020-10-02", 20], ["2020-10-01", 0]"#, false),
+            ),
+        ])));
+
+        let d = |day| Date::try_from_ymd(2020, 10, day).unwrap();
+        assert_eq!(
+            req.ratio_to("england", MetricKind::NewCasesByPublishDate).unwrap(),
+            vec![(d(1), None), (d(2), None), (d(3), Some(0.25)), (d(4), None)]
+        );
+    }
+
+    #[test]
+    fn concurrent_diagnostics() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("wales"))));
+        req.set_transport(Arc::new(RoutedTransport::new(vec![
+            ("areaName=wales", MockTransport::page(r#"["2020-10-01", 5]"#, false)),
+            ("areaName=england", MockTransport::page(r#"["2020-10-01", 50]"#, false)),
+        ])));
+        req.set_diagnostics(true);
+
+        // The concurrent fetches are recorded together, in order, however
+        // they interleave.
+        for _ in 0..20 {
+            req.ratio_to("england", MetricKind::NewCasesByPublishDate).unwrap();
+            let diagnostics = req.last_diagnostics();
+            assert_eq!(diagnostics.len(), 2);
+            assert!(diagnostics[0].url.contains("areaName=wales"));
+            assert!(diagnostics[1].url.contains("areaName=england"));
+        }
+    }
+
+    #[test]
+    fn all_problems() {
+        let mut req = Request::with_metrics(AreaType::Nation, &[]);
+        req.add_filter(Filter::new(FilterValue::AreaCode(AreaCode::new("E09000012").unwrap())));
+        req.add_filter(Filter::new(FilterValue::Date(Date::try_from_ymd(2999, 1, 1).unwrap())));
+
+        let problems = req.validate_all().unwrap_err();
+        assert_eq!(problems.len(), 3);
+        assert!(matches!(&problems[0], Error::InconsistentFilters(m) if m.contains("E09000012")));
+        assert!(matches!(problems[1], Error::FutureDate(_)));
+        assert!(matches!(&problems[2], Error::InvalidRequest(m) if m.contains("no metrics")));
+
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        assert!(req.validate_all().is_ok());
+        for metric in KNOWN_METRICS.iter().take(5) {
+            req.add_metric(Metric::generic(metric.name()));
+        }
+        match req.validate_all() {
+            Err(problems) => assert!(matches!(&problems[..], [Error::InvalidRequest(m)] if m.contains("6 metrics"))),
+            r => panic!("expected too many metrics, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn canonical_metric_order() {
+        let mut a = Request::with_metrics(AreaType::Nation, &[
+            MetricKind::NewCasesByPublishDate,
+            MetricKind::Date,
+            MetricKind::CumulativeCasesByPublishDate,
+        ]);
+        let mut b = Request::with_metrics(AreaType::Nation, &[
+            MetricKind::CumulativeCasesByPublishDate,
+            MetricKind::NewCasesByPublishDate,
+            MetricKind::Date,
+        ]);
+        assert_ne!(a.url(), b.url());
+
+        a.set_canonical_metric_order(true);
+        b.set_canonical_metric_order(true);
+        assert_eq!(a.url(), b.url());
+        assert!(a.url().contains("structure=[%22cumCasesByPublishDate%22, %22date%22, %22newCasesByPublishDate%22]"));
+
+        // The columns arrive sorted, but each day keeps the request's order.
+        a.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"[400, "2020-10-02", 12]"#,
+            false,
+        )])));
+        assert_eq!(a.get().unwrap()[0], vec![
+            Metric::NewCasesByPublishDate(MetricValue::Int(12)),
+            Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap()),
+            Metric::CumulativeCasesByPublishDate(MetricValue::Int(400)),
+        ]);
+    }
+
+    #[test]
+    fn inconsistent_area_code() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(AreaCode::new("E09000012").unwrap().into()));
+        req.set_strict(true);
+
+        match req.get() {
+            Err(Error::InconsistentFilters(message)) => assert_eq!(message, "E09000012 is not the code of a nation area"),
+            r => panic!("expected InconsistentFilters, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn bulk_download() {
+        let csv = "areaCode,areaName,areaType,date,newCasesByPublishDate\n\
+                   E92000001,England,nation,2021-01-02,50000\n\
+                   W92000004,Wales,nation,2021-01-02,2000\n";
+        let mut req = Request::new(AreaType::Nation, Metric::AreaCode(String::new()));
+        let transport = Arc::new(MockTransport::new(vec![Response::new(200, csv)]));
+        req.set_transport(transport.clone());
+
+        assert_eq!(req.download_csv().unwrap(), csv);
+        assert_eq!(transport.urls.lock().unwrap()[0], format!("{}/nation_latest.csv", DOWNLOADS_URL));
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        let mut page = MockTransport::page("[3]", false);
+        page.body.splice(0..0, "\u{feff}".bytes());
+        req.set_transport(Arc::new(MockTransport::new(vec![page])));
+
+        assert_eq!(req.get().unwrap(), vec![vec![Metric::NewCasesByPublishDate(MetricValue::Int(3))]]);
+    }
+
+    #[test]
+    fn trailing_commas() {
+        let mut req = Request::new(AreaType::Nation, Metric::AreaName(String::new()));
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(
+            200,
+            r#"{"data": [["a, ]"], ["b",],  ], "pagination": {"next": null,}}"#,
+        )])));
+
+        assert_eq!(req.get().unwrap(), vec![
+            vec![Metric::AreaName(String::from("a, ]"))],
+            vec![Metric::AreaName(String::from("b"))],
+        ]);
+
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(200, r#"{"data": [["a"] ["b"]],}"#)])));
+        assert!(matches!(req.get(), Err(Error::ParseError(_))));
+    }
+
+    #[test]
+    fn quoted_numbers() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::CumulativeTestsByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::TransmissionRateMax(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["123", "3000000000", "1.2"], [45, 2999999000, 1.1]"#,
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(data[0], vec![
+            Metric::NewCasesByPublishDate(MetricValue::Int(123)),
+            Metric::CumulativeTestsByPublishDate(MetricValue::Int(3_000_000_000)),
+            Metric::TransmissionRateMax(MetricValue::Float(1.2)),
+        ]);
+        assert_eq!(data[1][0], Metric::NewCasesByPublishDate(MetricValue::Int(45)));
+    }
+
+    #[test]
+    fn large_cumulative_tests() {
+        let mut req = Request::new(AreaType::Overview, Metric::CumulativeTestsByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page("[3000000000]", false)])));
+
+        let data = req.get().unwrap();
+        assert_eq!(data[0][0], Metric::CumulativeTestsByPublishDate(MetricValue::Int(3_000_000_000)));
+        assert_eq!(data[0][0].as_i64(), Some(3_000_000_000));
+        assert_eq!(data[0][0].as_i32(), None);
+    }
+
+    #[test]
+    fn stream_csv() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"["2020-10-03", 3], ["2020-10-02", null]"#, true),
+            MockTransport::page(r#"["2020-10-01", 1]"#, false),
+        ])));
+
+        let mut buf = Vec::new();
+        req.write_csv(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "date,newCasesByPublishDate\n2020-10-03,3\n2020-10-02,\n2020-10-01,1\n"
+        );
+    }
+
+    #[test]
+    fn save_csv_to_file() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["2020-10-02", 2], ["2020-10-01", 1]"#,
+            false,
+        )])));
+
+        let dir = std::env::temp_dir().join(format!("covid19_uk_rs_save_csv_{}", std::process::id()));
+        let path = dir.join("nested").join("cases.csv");
+        req.save_csv(&path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(contents, "date,newCasesByPublishDate\n2020-10-02,2\n2020-10-01,1\n");
+    }
+
+    #[test]
+    fn transmission_rate_range() {
+        let mut req = Request::nation(Nation::England, Metric::TransmissionRateMin(MetricValue::Null));
+        req.add_metric(Metric::TransmissionRateMax(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page("[0.8, 1.1], [null, null]", false)])));
+
+        let data = req.get().unwrap();
+        assert_eq!(data[0], vec![Metric::TransmissionRateMin(MetricValue::Float(0.8)), Metric::TransmissionRateMax(MetricValue::Float(1.1))]);
+        assert_eq!(data[1], vec![Metric::TransmissionRateMin(MetricValue::Null), Metric::TransmissionRateMax(MetricValue::Null)]);
+        assert_eq!(data[0][1].as_f64(), Some(1.1));
+    }
+
+    #[test]
+    fn accept_language_header() {
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page("[1]", false),
+            MockTransport::page("[1]", false),
+        ]));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(transport.clone());
+        req.get().unwrap();
+        req.set_accept_language("cy-GB");
         req.get().unwrap();
+
+        let headers = transport.headers.lock().unwrap();
+        let lang = |h: &Vec<(String, String)>| h.iter().find(|(n, _)| n == "Accept-Language").map(|(_, v)| v.clone());
+        assert_eq!(lang(&headers[0]), None);
+        assert_eq!(lang(&headers[1]), Some(String::from("cy-GB")));
+    }
+
+    #[test]
+    fn unavailable_metrics_pruned() {
+        let metrics = [MetricKind::NewCasesByPublishDate, MetricKind::HospitalCases, MetricKind::NewAdmissions];
+
+        let mut req = Request::new(AreaType::LTLA, Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap()));
+        let mut nation = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap()));
+        for metric in &metrics {
+            req.add_metric_if_available(metric.clone());
+            nation.add_metric_if_available(metric.clone());
+        }
+
+        assert_eq!(req.config.metrics, vec![MetricKind::Date, MetricKind::NewCasesByPublishDate]);
+        assert_eq!(nation.config.metrics.len(), 4);
+    }
+
+    #[test]
+    fn wrapped_values() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::TransmissionRateMax(MetricValue::Null));
+        req.add_metric(Metric::NewAdmissions(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"[{"value": 123}, {"payload": 1.1}, 7]"#,
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(
+            data[0],
+            vec![
+                Metric::NewCasesByPublishDate(MetricValue::Int(123)),
+                Metric::TransmissionRateMax(MetricValue::Float(1.1)),
+                Metric::NewAdmissions(MetricValue::Int(7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_page_bytes() {
+        let first = MockTransport::page(r#"[1], ["caf\u00e9"]"#, true);
+        let second = MockTransport::page("[2]", false);
+        let expected = vec![first.body.clone(), second.body.clone()];
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![first, second])));
+
+        assert_eq!(req.get_bytes().unwrap(), expected);
+    }
+
+    #[test]
+    fn incomplete_days_dropped() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_drop_incomplete_trailing(2);
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"[4, "2020-10-04"], [3, "2020-10-03"], [2, "2020-10-02"], [1, "2020-10-01"]"#,
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(
+            data,
+            vec![
+                vec![Metric::NewCasesByPublishDate(MetricValue::Int(2)), Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap())],
+                vec![Metric::NewCasesByPublishDate(MetricValue::Int(1)), Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap())],
+            ]
+        );
+    }
+
+    #[test]
+    fn generic_metric() {
+        let name = "uniqueCasePositivityBySpecimenDateRollingSum";
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page("[12.5]", false)]));
+        let mut req = Request::new(AreaType::Nation, Metric::generic(name));
+        req.set_transport(transport.clone());
+
+        let data = req.get().unwrap();
+        assert_eq!(data[0][0], Metric::Generic(String::from(name), 12.5.into()));
+        assert_eq!(data[0][0].kind().name(), name);
+        assert_eq!(data[0][0].as_f64(), Some(12.5));
+
+        // A Metric's value is read by the same rules as its MetricValue.
+        let text = Metric::Generic(String::from(name), MetricValue::Text(String::from("12")));
+        assert_eq!(text.as_i64(), Some(12));
+        assert_eq!(text.as_i64(), MetricValue::from(&text).as_i64());
+        assert_eq!(Metric::Generic(String::from(name), 12.0.into()).as_i64(), Some(12));
+        assert!(transport.urls.lock().unwrap()[0].contains("structure=[%22uniqueCasePositivityBySpecimenDateRollingSum%22]"));
+    }
+
+    #[test]
+    fn declared_generic_types() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_generic_metric("growthRate", ValueType::Float);
+        req.add_generic_metric("tier", ValueType::Int);
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["2020-10-02", "0.25", 3], ["2020-10-01", 2, null]"#,
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(data[0][1], Metric::Generic(String::from("growthRate"), 0.25.into()));
+        assert_eq!(data[0][1].as_f64(), Some(0.25));
+        assert_eq!(data[1][1].as_f64(), Some(2.0));
+        assert_eq!(data[0][2].as_i64(), Some(3));
+        assert_eq!(data[1][2], Metric::Generic(String::from("tier"), MetricValue::Null));
+        // The kind of a parsed value carries its type, so re-parsing through 
+        // it reads the same type.
+        match data[0][1].kind() {
+            kind @ MetricKind::Generic { value_type: ValueType::Float, .. } => {
+                assert_eq!(kind.parse(&"0.5".into()).unwrap().as_f64(), Some(0.5))
+            }
+            kind => panic!("expected a float kind, got {:?}", kind),
+        }
+
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["2020-10-02", 0.5, "High"]"#,
+            false,
+        )])));
+        match req.get() {
+            Err(Error::ParseError(message)) => assert!(message.starts_with("tier was declared as Int")),
+            r => panic!("expected ParseError, got {:?}", r),
+        }
+
+        // The declared type travels with the MetricKind, so is kept when the
+        // metrics are split between requests.
+        let growth_rate = MetricKind::Generic {
+            name: String::from("growthRate"),
+            value_type: ValueType::Float,
+        };
+        req.set_transport(Arc::new(RoutedTransport::new(vec![
+            ("growthRate", MockTransport::page(r#"["2020-10-02", "0.5"]"#, false)),
+            ("newCasesByPublishDate", MockTransport::page(r#"["2020-10-02", 1, 2, 3, 4]"#, false)),
+        ])));
+        let data = req
+            .get_many_metrics(&[
+                MetricKind::NewCasesByPublishDate,
+                MetricKind::NewCasesBySpecimenDate,
+                MetricKind::NewAdmissions,
+                MetricKind::HospitalCases,
+                growth_rate.clone(),
+            ])
+            .unwrap();
+        assert_eq!(data[0][5], Metric::Generic(String::from("growthRate"), 0.5.into()));
+        assert_eq!(data[0][5].kind(), growth_rate);
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn exact_decimal_rates() {
+        use std::str::FromStr;
+
+        let mut req = Request::nation(Nation::England, Metric::TransmissionRateMin(MetricValue::Null));
+        req.add_metric(Metric::generic("growthRate"));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page("[12.34, 0.1]", false)])));
+
+        let data = req.get().unwrap();
+        assert_eq!(data[0][0].as_decimal(), Some(rust_decimal::Decimal::from_str("12.34").unwrap()));
+        assert_eq!(data[0][1].as_decimal(), Some(rust_decimal::Decimal::from_str("0.1").unwrap()));
+        assert_eq!(Metric::AreaName(String::from("england")).as_decimal(), None);
+    }
+
+    #[test]
+    fn earliest_non_null_date() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"["2020-03-03", 3], ["2020-03-02", 2]"#, true),
+            MockTransport::page(r#"["2020-03-01", 1], ["2020-02-29", null], ["2020-02-28", null]"#, false),
+        ])));
+
+        assert_eq!(
+            req.earliest_date_for(MetricKind::NewCasesByPublishDate).unwrap(),
+            Some(Date::try_from_ymd(2020, 3, 1).unwrap())
+        );
+    }
+
+    #[test]
+    fn jsonl_rows() {
+        let transport = Arc::new(MockTransport::new(vec![Response::new(
+            200,
+            "[\"2020-10-02\", 2]\n[\"2020-10-01\", null]\n\n",
+        )]));
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_format(Format::JsonL);
+        req.set_transport(transport.clone());
+
+        assert_eq!(
+            req.get().unwrap(),
+            vec![
+                vec![Metric::Date(Date::try_from_ymd(2020, 10, 2).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Int(2))],
+                vec![Metric::Date(Date::try_from_ymd(2020, 10, 1).unwrap()), Metric::NewCasesByPublishDate(MetricValue::Null)],
+            ]
+        );
+        assert!(transport.urls.lock().unwrap()[0].contains("&format=jsonl&"));
+
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(200, "[1]\n[2")])));
+        match req.get() {
+            Err(Error::ParseError(s)) => assert!(s.contains("line 2")),
+            r => panic!("expected ParseError, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(MockTransport::new(vec![Response::new(
+            200,
+            &b"{\"data\": [[\"\xff\"]], \"pagination\": {}}"[..],
+        )])));
+
+        match req.get() {
+            Err(Error::ParseError(s)) => assert!(s.starts_with("Response is not valid UTF-8")),
+            r => panic!("expected ParseError, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn max_bytes_exceeded() {
+        let pages = || {
+            Arc::new(MockTransport::new(vec![
+                MockTransport::page("[1]", true),
+                MockTransport::page(&vec!["[1]"; 100].join(", "), false),
+            ]))
+        };
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_max_bytes(200);
+
+        req.set_transport(pages());
+        assert!(matches!(req.get(), Err(Error::ResponseTooLarge)));
+
+        req.set_partial_ok(true);
+        req.set_transport(pages());
+        assert_eq!(req.get().unwrap(), vec![vec![Metric::NewCasesByPublishDate(MetricValue::Int(1))]]);
+    }
+
+    #[test]
+    fn typed_query_macro() {
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["2020-10-02", 200, 20], ["2020-10-01", 100, null]"#,
+            false,
+        )]));
+
+        let rows = covid_query! {
+            area: Nation("england"),
+            metrics: {
+                cases: CumulativeCasesByPublishDate,
+                deaths: CumulativeDeathsWithin28DaysByPublishDate,
+            },
+            transport: transport.clone(),
+        }
+        .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].date, Date::try_from_ymd(2020, 10, 2).unwrap());
+        assert_eq!((rows[0].cases, rows[0].deaths), (Some(200), Some(20)));
+        assert_eq!((rows[1].cases, rows[1].deaths), (Some(100), None));
+        assert!(transport.urls.lock().unwrap()[0].contains("areaType=nation;areaName=england"));
+    }
+
+    #[test]
+    fn days_by_date() {
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"[3, "2020-10-03"], [2, "2020-10-02"], [20, "2020-10-02"], [1, "2020-10-01"]"#,
+            false,
+        )]));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(transport.clone());
+
+        let days = req.get_by_date().unwrap();
+        let dates: Vec<_> = days.keys().cloned().collect();
+        assert_eq!(
+            dates,
+            vec![
+                Date::try_from_ymd(2020, 10, 1).unwrap(),
+                Date::try_from_ymd(2020, 10, 2).unwrap(),
+                Date::try_from_ymd(2020, 10, 3).unwrap(),
+            ]
+        );
+        assert_eq!(
+            days[&Date::try_from_ymd(2020, 10, 2).unwrap()][0],
+            Metric::NewCasesByPublishDate(MetricValue::Int(2))
+        );
+        assert!(transport.urls.lock().unwrap()[0].contains("%22date%22"));
+    }
+
+    #[test]
+    fn null_defaults() {
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_metric(Metric::NewDeathsWithin28DaysByPublishDate(MetricValue::Null));
+        req.set_null_default(MetricKind::NewCasesByPublishDate, 0);
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            "[5, 1], [null, null]",
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(
+            data[0],
+            vec![Metric::NewCasesByPublishDate(MetricValue::Int(5)), Metric::NewDeathsWithin28DaysByPublishDate(MetricValue::Int(1))]
+        );
+        assert_eq!(
+            data[1],
+            vec![Metric::NewCasesByPublishDate(MetricValue::Int(0)), Metric::NewDeathsWithin28DaysByPublishDate(MetricValue::Null)]
+        );
+    }
+
+    #[test]
+    fn days_on_dates() {
+        let rows: Vec<String> = (1..=9).rev().map(|d| format!(r#"["2020-10-0{}", {}]"#, d, d)).collect();
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        let d = |day| Date::try_from_ymd(2020, 10, day).unwrap();
+
+        // Many dates: the series is fetched once and filtered.
+        let paged = Arc::new(PagedTransport::new(rows, 2));
+        req.set_transport(paged.clone());
+        let data = req.get_on_dates(&[d(1), d(3), d(5), d(7), d(8)]).unwrap();
+        let dates: Vec<Date> = data.iter().filter_map(data::date_of).collect();
+        assert_eq!(dates, vec![d(8), d(7), d(5), d(3), d(1)]);
+        assert_eq!(paged.urls.lock().unwrap().len(), 1);
+
+        // Few dates: each is requested with a Date Filter.
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page(r#"["2020-10-02", 2]"#, false),
+            MockTransport::page(r#"["2020-10-06", 6]"#, false),
+        ]));
+        req.set_transport(transport.clone());
+        req.set_order(Order::Chronological);
+        let data = req.get_on_dates(&[d(6), d(2)]).unwrap();
+        assert_eq!(
+            data,
+            vec![
+                vec![Metric::Date(d(2)), Metric::NewCasesByPublishDate(MetricValue::Int(2))],
+                vec![Metric::Date(d(6)), Metric::NewCasesByPublishDate(MetricValue::Int(6))],
+            ]
+        );
+        let urls = transport.urls.lock().unwrap();
+        assert_eq!(urls.len(), 2);
+        assert!(urls.iter().all(|u| u.contains("date=2020-10-0")));
+    }
+
+    #[test]
+    fn days_on_dates_with_context() {
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("england"))));
+        req.set_error_context(true);
+        req.set_suggest_area_names(true);
+        let d = |day| Date::try_from_ymd(2020, 10, day).unwrap();
+
+        // A date without data is left out, without looking for suggestions.
+        let transport = Arc::new(RoutedTransport::new(vec![
+            ("date=2020-10-02", MockTransport::page(r#"["2020-10-02", 2]"#, false)),
+            ("date=2020-10-03", Response::new(204, "")),
+            ("date=2020-10-04", Response::new(500, "")),
+        ]));
+        req.set_transport(transport.clone());
+        assert_eq!(
+            req.get_on_dates(&[d(2), d(3)]).unwrap(),
+            vec![vec![Metric::Date(d(2)), Metric::NewCasesByPublishDate(MetricValue::Int(2))]]
+        );
+        assert_eq!(transport.urls.lock().unwrap().len(), 2);
+
+        // Any other error is returned with context, added once.
+        match req.get_on_dates(&[d(2), d(4)]) {
+            Err(Error::WithContext { area, source, .. }) => {
+                assert_eq!(area, "england");
+                assert!(matches!(*source, Error::APIServerError));
+            }
+            r => panic!("expected an error with context, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn recent_days() {
+        let rows: Vec<String> = (1..=9).rev().map(|d| format!(r#"["2020-10-0{}", {}]"#, d, d)).collect();
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_metric(Metric::NewCasesByPublishDate(MetricValue::Null));
+
+        let paged = Arc::new(PagedTransport::new(rows, 2));
+        req.set_transport(paged.clone());
+        let data = req.get_recent(3).unwrap();
+
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0][1], Metric::NewCasesByPublishDate(MetricValue::Int(9)));
+        assert_eq!(data[2][1], Metric::NewCasesByPublishDate(MetricValue::Int(7)));
+        // A full get would fetch 5 pages.
+        assert_eq!(paged.urls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn hospital_cases_by_age() {
+        let mut req = Request::nation(Nation::England, Metric::HospitalCasesByAge(vec![]));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"[[{"age": "0_to_5", "value": 42}, {"age": "85+", "value": 1530, "rate": 118.6}]], [[]]"#,
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(
+            data[0][0],
+            Metric::HospitalCasesByAge(vec![
+                AgeBand { age: String::from("0_to_5"), value: Some(42), rate: None },
+                AgeBand { age: String::from("85+"), value: Some(1530), rate: Some(118.6) },
+            ])
+        );
+        assert_eq!(data[1][0], Metric::HospitalCasesByAge(vec![]));
+        assert_eq!(MetricKind::HospitalCasesByAge.name(), "hospitalCasesByAge");
+        assert!(!MetricKind::HospitalCasesByAge.available_for(&AreaType::LTLA));
+    }
+
+    #[test]
+    fn cases_by_sex_and_age() {
+        let mut req = Request::nation(Nation::England, Metric::MaleCases(vec![]));
+        req.add_metric(Metric::FemaleCases(vec![]));
+        req.set_transport(Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"[[{"age": "0_to_4", "value": 120, "rate": 35.2}, {"age": "90+", "value": 80, "rate": null}],
+                [{"age": "0_to_4", "value": 110, "rate": 33.9}, {"age": "90+", "value": 150, "rate": 98.1}]],
+               [null, null]"#,
+            false,
+        )])));
+
+        let data = req.get().unwrap();
+        assert_eq!(
+            data[0][0],
+            Metric::MaleCases(vec![
+                AgeBand { age: String::from("0_to_4"), value: Some(120), rate: Some(35.2) },
+                AgeBand { age: String::from("90+"), value: Some(80), rate: None },
+            ])
+        );
+        assert_eq!(
+            data[0][1],
+            Metric::FemaleCases(vec![
+                AgeBand { age: String::from("0_to_4"), value: Some(110), rate: Some(33.9) },
+                AgeBand { age: String::from("90+"), value: Some(150), rate: Some(98.1) },
+            ])
+        );
+        assert_eq!(data[1], vec![Metric::MaleCases(vec![]), Metric::FemaleCases(vec![])]);
+    }
+
+    /// A Transport serving a page of rows for each nation, by the nation 
+    /// named in the URL.
+    #[derive(Debug)]
+    struct NationsTransport;
+    impl Transport for NationsTransport {
+        fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<Response, Error> {
+            let rows = if url.contains("areaName=england") {
+                r#"["2020-10-02", 100], ["2020-10-01", 90]"#
+            } else if url.contains("areaName=scotland") {
+                r#"["2020-10-02", 10], ["2020-10-01", 9]"#
+            } else if url.contains("areaName=wales") {
+                r#"["2020-10-02", 5], ["2020-10-01", null]"#
+            } else {
+                r#"["2020-10-02", 1]"#
+            };
+            Ok(MockTransport::page(rows, false))
+        }
+    }
+
+    #[test]
+    fn uk_total() {
+        let mut req = Request::new(AreaType::Overview, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_transport(Arc::new(NationsTransport));
+
+        assert_eq!(
+            req.uk_total_from_nations(MetricKind::NewCasesByPublishDate).unwrap(),
+            vec![
+                (Date::try_from_ymd(2020, 10, 1).unwrap(), None),
+                (Date::try_from_ymd(2020, 10, 2).unwrap(), Some(116)),
+            ]
+        );
+    }
+
+    #[test]
+    fn area_rankings() {
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page(
+            r#"["Hackney", 310], ["Camden", 95], ["Lambeth", null], ["Islington", 412], ["Barnet", 310]"#,
+            false,
+        )]));
+        let mut req = Request::new(AreaType::Nation, Metric::Date(Date::try_from_ymd(2020, 1, 1).unwrap()));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("england"))));
+        req.set_transport(transport.clone());
+
+        assert_eq!(
+            req.rank_areas(MetricKind::NewCasesBySpecimenDate, AreaType::LTLA, 3).unwrap(),
+            vec![
+                (String::from("Islington"), 412),
+                (String::from("Barnet"), 310),
+                (String::from("Hackney"), 310),
+            ]
+        );
+        let url = &transport.urls.lock().unwrap()[0];
+        assert!(url.contains("filters=areaType=ltla&"));
+        assert!(url.contains("&latestBy=newCasesBySpecimenDate"));
+    }
+
+    #[test]
+    fn send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Request>();
+        assert_send_sync::<Client>();
+        assert_send_sync::<Data>();
+        assert_send_sync::<Error>();
+    }
+
+    #[test]
+    fn cases_by_test_type() {
+        let mut req = Request::new(AreaType::LTLA, Metric::NewCasesPCROnlyBySpecimenDate(MetricValue::Null));
+        req.add_metric(Metric::NewCasesLFDOnlyBySpecimenDate(MetricValue::Null));
+        req.add_filter(Filter::new(FilterValue::AreaName(String::from("hackney"))));
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page("[42, 3], [null, null]", false)]));
+        req.set_transport(transport.clone());
+
+        let data = req.get().unwrap();
+        assert_eq!(
+            data[0],
+            vec![Metric::NewCasesPCROnlyBySpecimenDate(MetricValue::Int(42)), Metric::NewCasesLFDOnlyBySpecimenDate(MetricValue::Int(3))]
+        );
+        assert_eq!(data[1][0].as_i32(), None);
+        assert!(transport.urls.lock().unwrap()[0].contains("%22newCasesPCROnlyBySpecimenDate%22"));
+    }
+
+    #[test]
+    fn booster_metrics() {
+        let mut req = Request::nation(Nation::England, Metric::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage(MetricValue::Null));
+        req.add_metric(Metric::CumulativePeopleVaccinatedThirdInjectionByPublishDate(MetricValue::Null));
+        let transport = Arc::new(MockTransport::new(vec![MockTransport::page("[55.3, 30821519], [null, null]", false)]));
+        req.set_transport(transport.clone());
+
+        let data = req.get().unwrap();
+        assert_eq!(
+            data[0],
+            vec![
+                Metric::CumulativeVaccinationBoosterDoseUptakeByPublishDatePercentage(MetricValue::Float(55.3)),
+                Metric::CumulativePeopleVaccinatedThirdInjectionByPublishDate(MetricValue::Int(30821519)),
+            ]
+        );
+        assert_eq!(data[1][0].as_f64(), None);
+        assert!(transport.urls.lock().unwrap()[0].contains("%22cumVaccinationBoosterDoseUptakeByPublishDatePercentage%22"));
+    }
+
+    #[test]
+    fn duplicate_key_policies() {
+        let body = r#"{"data": [{"date": "2020-10-02", "newCasesByPublishDate": 1, "newCasesByPublishDate": 2}, {"date": "2020-10-01", "newCasesByPublishDate": 3}], "pagination": {"next": null}}"#;
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_named_structure(true);
+        let cases = |req: &mut Request| {
+            req.set_transport(Arc::new(MockTransport::new(vec![Response::new(200, body)])));
+            req.get().map(|data| data.iter().map(|day| day[0].as_i32().unwrap()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(cases(&mut req).unwrap(), vec![2, 3]);
+        req.set_duplicate_key_policy(DuplicateKeys::First);
+        assert_eq!(cases(&mut req).unwrap(), vec![1, 3]);
+        req.set_duplicate_key_policy(DuplicateKeys::Error);
+        match cases(&mut req) {
+            Err(Error::ParseError(message)) => assert!(message.contains("Day 0 names newCasesByPublishDate")),
+            r => panic!("expected ParseError, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn single_page() {
+        let transport = Arc::new(MockTransport::new(vec![
+            MockTransport::page("[2], [1]", true),
+            MockTransport::page("[0]", false),
+        ]));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+        req.set_single_page(true);
+        req.set_transport(transport.clone());
+
+        assert_eq!(
+            req.get().unwrap(),
+            vec![vec![Metric::NewCasesByPublishDate(MetricValue::Int(2))], vec![Metric::NewCasesByPublishDate(MetricValue::Int(1))]]
+        );
+        assert_eq!(transport.urls.lock().unwrap().len(), 1);
     }
 }
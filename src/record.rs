@@ -0,0 +1,114 @@
+//! Transports for recording real API responses as fixtures, and replaying 
+//! them offline. Requires the `record` feature.
+//!
+//! Each successful page body is stored in the fixtures directory in a file 
+//! named after a hash of the page's URL, so a request replays from the same 
+//! files it was recorded to as long as its URLs are unchanged.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::transport::{Response, Transport};
+use crate::Error;
+
+/// Returns the path of the fixture for the URL.
+/// 
+/// URLs are hashed with 64-bit FNV-1a, which (unlike the standard library's 
+/// hasher) is stable across Rust versions.
+fn fixture_path(dir: &Path, url: &str) -> PathBuf {
+    let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    dir.join(format!("{:016x}.json", hash))
+}
+
+/// A Transport which passes requests on to another Transport, writing the 
+/// body of each successful response to the fixtures directory.
+#[derive(Debug)]
+pub struct RecordingTransport {
+    inner: Arc<dyn Transport>,
+    dir: PathBuf,
+}
+impl RecordingTransport {
+    /// Creates a RecordingTransport sending requests through `inner` and 
+    /// recording to `dir`, which is created if it doesn't exist.
+    pub fn new(inner: Arc<dyn Transport>, dir: impl Into<PathBuf>) -> RecordingTransport {
+        RecordingTransport {
+            inner,
+            dir: dir.into(),
+        }
+    }
+}
+impl Transport for RecordingTransport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.get_with_timeout(url, headers, None)
+    }
+
+    fn get_with_timeout(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        timeout: Option<Duration>,
+    ) -> Result<Response, Error> {
+        let res = self.inner.get_with_timeout(url, headers, timeout)?;
+        if res.status == 200 {
+            fs::create_dir_all(&self.dir).map_err(Error::IoError)?;
+            fs::write(fixture_path(&self.dir, url), &res.body).map_err(Error::IoError)?;
+        }
+        Ok(res)
+    }
+}
+
+/// A Transport serving responses recorded by a RecordingTransport, without 
+/// reaching the API.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    dir: PathBuf,
+}
+impl ReplayTransport {
+    /// Creates a ReplayTransport serving the fixtures in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> ReplayTransport {
+        ReplayTransport { dir: dir.into() }
+    }
+}
+impl Transport for ReplayTransport {
+    /// Returns the recorded body for the URL as a successful JSON response, 
+    /// or `Error::IoError` if no fixture was recorded for it.
+    fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<Response, Error> {
+        let body = fs::read(fixture_path(&self.dir, url)).map_err(Error::IoError)?;
+        let mut res = Response::new(200, body);
+        res.headers.push((String::from("content-type"), String::from("application/json")));
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+    use crate::{AreaType, Metric, MetricValue, Request};
+
+    #[test]
+    fn record_then_replay() {
+        let dir = std::env::temp_dir().join(format!("covid19_uk_rs-record-{}", std::process::id()));
+        let mut req = Request::new(AreaType::Nation, Metric::NewCasesByPublishDate(MetricValue::Null));
+
+        req.set_transport(Arc::new(RecordingTransport::new(
+            Arc::new(MockTransport::new(vec![
+                MockTransport::page("[3], [2]", true),
+                MockTransport::page("[1]", false),
+            ])),
+            &dir,
+        )));
+        let recorded = req.get().unwrap();
+
+        req.set_transport(Arc::new(ReplayTransport::new(&dir)));
+        let replayed = req.get();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(replayed.unwrap(), recorded);
+        assert_eq!(recorded.len(), 3);
+    }
+}
@@ -0,0 +1,777 @@
+//! Analysis helpers for dated series of values, such as those produced by 
+//! `Request::fetch_single_i32`.
+//!
+//! Series are accepted in any order; the API returns days in 
+//! reverse-chronological order, so the helpers sort their input by date and 
+//! return their results in chronological order.
+
+use std::collections::BTreeMap;
+
+use time::Date;
+
+use crate::Nation;
+
+/// Returns a copy of the series sorted by date, oldest first.
+fn sorted(series: &[(Date, i32)]) -> Vec<(Date, i32)> {
+    let mut series = series.to_vec();
+    series.sort_by_key(|(date, _)| *date);
+    series
+}
+
+/// Estimates the doubling time, in days, of a cumulative series for each date.
+///
+/// For each date, an exponential is fitted (by a log-linear least-squares 
+/// regression) to that day and the preceding `window_days - 1` days, and the 
+/// doubling time implied by its growth rate is returned. The estimate is 
+/// `None` where the trailing window is incomplete, contains a non-positive 
+/// value, or where growth over the window is not positive.
+pub fn doubling_time(series: &[(Date, i32)], window_days: usize) -> Vec<(Date, Option<f64>)> {
+    let series = sorted(series);
+
+    series
+        .iter()
+        .enumerate()
+        .map(|(i, (date, _))| {
+            if window_days < 2 || i + 1 < window_days {
+                return (*date, None);
+            }
+            let window = &series[i + 1 - window_days..=i];
+            (*date, growth_rate(window).map(|rate| 2f64.ln() / rate))
+        })
+        .collect()
+}
+
+/// Fits `ln(value) = a + rate * day` over the window, returning the rate if 
+/// it is positive.
+fn growth_rate(window: &[(Date, i32)]) -> Option<f64> {
+    if window.iter().any(|(_, v)| *v <= 0) {
+        return None;
+    }
+
+    let start = window[0].0;
+    let points: Vec<(f64, f64)> = window
+        .iter()
+        .map(|(date, v)| ((*date - start).whole_days() as f64, (*v as f64).ln()))
+        .collect();
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+
+    if sxx == 0.0 {
+        return None;
+    }
+    let rate = sxy / sxx;
+    if rate > 0.0 {
+        Some(rate)
+    } else {
+        None
+    }
+}
+
+/// Finds the dates on which a cumulative series decreased, which indicates 
+/// the data was revised, returning each with the change from the previous 
+/// day (which is always negative). A change too large to be held in an `i32` 
+/// is given as `i32::MIN`.
+pub fn detect_revisions(series: &[(Date, i32)]) -> Vec<(Date, i32)> {
+    sorted(series)
+        .windows(2)
+        .filter_map(|pair| {
+            let delta = pair[1].1.saturating_sub(pair[0].1);
+            if delta < 0 {
+                Some((pair[1].0, delta))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Checks a cumulative series against its daily series (such as 
+/// `cumCasesByPublishDate` against `newCasesByPublishDate`), finding the 
+/// dates on which the cumulative total's change from the previous day isn't 
+/// the daily value, each with the discrepancy: the change less the daily 
+/// value.
+///
+/// The series are aligned by date; only dates with a daily value and a 
+/// cumulative total for both the date and the day before are checked. A 
+/// discrepancy too large to be held in an `i32` is given as `i32::MIN` or 
+/// `i32::MAX`.
+pub fn check_consistency(cumulative: &[(Date, i32)], daily: &[(Date, i32)]) -> Vec<(Date, i32)> {
+    let totals: BTreeMap<Date, i64> = cumulative.iter().map(|(date, v)| (*date, i64::from(*v))).collect();
+
+    sorted(daily)
+        .into_iter()
+        .filter_map(|(date, value)| {
+            let delta = totals.get(&date)? - totals.get(&(date - time::Duration::day()))?;
+            let discrepancy = delta - i64::from(value);
+            if discrepancy != 0 {
+                Some((date, discrepancy.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Reconciles a daily series with its cumulative series (such as 
+/// `newCasesByPublishDate` with `cumCasesByPublishDate`), giving a daily 
+/// value for each date in either wherever one can be found.
+///
+/// The daily value is used wherever there is one. Where there isn't, it is 
+/// derived as the cumulative total's change from the day before, if there 
+/// are totals for both days and the change fits in an `i32`; otherwise, the 
+/// value is `None`.
+pub fn reconcile(daily: &[(Date, Option<i32>)], cumulative: &[(Date, Option<i32>)]) -> Vec<(Date, Option<i32>)> {
+    let totals: BTreeMap<Date, i32> = cumulative.iter().filter_map(|(date, v)| Some((*date, (*v)?))).collect();
+    let mut days: BTreeMap<Date, Option<i32>> = cumulative.iter().map(|(date, _)| (*date, None)).collect();
+    for (date, value) in daily {
+        days.insert(*date, *value);
+    }
+
+    days.into_iter()
+        .map(|(date, value)| {
+            let derived = || totals.get(&date)?.checked_sub(*totals.get(&(date - time::Duration::day()))?);
+            (date, value.or_else(derived))
+        })
+        .collect()
+}
+
+/// Finds the dates whose values are implausible given the days before them, 
+/// such as a spike from a backlog reported on a single day.
+///
+/// A value is flagged if it lies more than `z` standard deviations from the 
+/// mean of the preceding `window` days' values. Days with fewer than 
+/// `window` days before them are never flagged.
+pub fn detect_outliers(series: &[(Date, i32)], window: usize, z: f64) -> Vec<Date> {
+    let series = sorted(series);
+    if window == 0 {
+        return vec![];
+    }
+
+    series
+        .windows(window + 1)
+        .filter_map(|w| {
+            let (date, value) = w[window];
+            let n = window as f64;
+            let mean = w[..window].iter().map(|(_, v)| *v as f64).sum::<f64>() / n;
+            let sd = (w[..window].iter().map(|(_, v)| (*v as f64 - mean).powi(2)).sum::<f64>() / n).sqrt();
+            if (value as f64 - mean).abs() > z * sd {
+                Some(date)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Accumulates a series of daily new values into a running cumulative 
+/// total for each date.
+///
+/// Dates missing between the first and last are included, contributing 
+/// nothing to the total; negative values (corrections) reduce it.
+pub fn cumulative(series: &[(Date, i32)]) -> Vec<(Date, i32)> {
+    let series = sorted(series);
+    let mut totals = vec![];
+    let mut total: i32 = 0;
+    for (date, value) in series {
+        if let Some((last, _)) = totals.last() {
+            let mut gap = *last + time::Duration::day();
+            while gap < date {
+                totals.push((gap, total));
+                gap += time::Duration::day();
+            }
+        }
+        total = total.saturating_add(value);
+        totals.push((date, total));
+    }
+    totals
+}
+
+/// Estimates the day-of-week reporting effect in a daily series, such as the 
+/// dip in cases reported at weekends, for use with `deseasonalize`.
+///
+/// Each day's value is compared to the mean of the trailing seven days 
+/// (itself and the six before it), and the ratios are averaged for each day 
+/// of the week, giving the factors indexed from Monday (0) to Sunday (6). 
+/// Days without a complete trailing week, or where its mean is zero, are 
+/// ignored; a day of the week with no ratios has a factor of 1.
+pub fn day_of_week_factors(series: &[(Date, i32)]) -> [f64; 7] {
+    let values: BTreeMap<Date, i32> = series.iter().cloned().collect();
+
+    let mut totals = [0.0; 7];
+    let mut counts = [0; 7];
+    for (date, value) in &values {
+        let week: Option<Vec<i32>> = (0..7)
+            .map(|d| values.get(&(*date - time::Duration::days(d))).cloned())
+            .collect();
+        let mean = match week {
+            Some(week) => week.iter().map(|v| *v as f64).sum::<f64>() / 7.0,
+            None => continue,
+        };
+        if mean == 0.0 {
+            continue;
+        }
+        let day = date.weekday().number_days_from_monday() as usize;
+        totals[day] += *value as f64 / mean;
+        counts[day] += 1;
+    }
+
+    let mut factors = [1.0; 7];
+    for day in 0..7 {
+        if counts[day] > 0 {
+            factors[day] = totals[day] / counts[day] as f64;
+        }
+    }
+    factors
+}
+
+/// Removes a day-of-week effect from a daily series, dividing each value by 
+/// the factor for its day of the week (indexed from Monday, as returned by 
+/// `day_of_week_factors`). Values whose factor is zero are left unadjusted.
+pub fn deseasonalize(series: &[(Date, i32)], factors: &[f64; 7]) -> Vec<(Date, f64)> {
+    sorted(series)
+        .into_iter()
+        .map(|(date, value)| {
+            let factor = factors[date.weekday().number_days_from_monday() as usize];
+            if factor == 0.0 {
+                (date, value as f64)
+            } else {
+                (date, value as f64 / factor)
+            }
+        })
+        .collect()
+}
+
+/// Computes the rate per 100,000 people of the total over each trailing 
+/// window of `window_days` days (the day itself and those before it), 
+/// given the population of the area, as for the dashboard's seven-day case 
+/// rates.
+///
+/// Only days whose whole trailing window is present in the series are 
+/// included; there are no rates for a population of zero.
+pub fn rolling_rate(series: &[(Date, i32)], window_days: usize, population: u32) -> Vec<(Date, f64)> {
+    let values: BTreeMap<Date, i32> = series.iter().cloned().collect();
+    if window_days == 0 || population == 0 {
+        return vec![];
+    }
+
+    values
+        .keys()
+        .filter_map(|date| {
+            let total = (0..window_days as i64)
+                .map(|d| values.get(&(*date - time::Duration::days(d))).map(|v| *v as i64))
+                .sum::<Option<i64>>()?;
+            Some((*date, total as f64 * 100_000.0 / population as f64))
+        })
+        .collect()
+}
+
+/// Computes the share of tests which were positive over each trailing 
+/// window of `window` days, as the total cases over the window divided by 
+/// the total tests, which is less noisy than the daily ratio given by 
+/// `data::positivity`.
+///
+/// The series are aligned by date, giving a share for every date in either; 
+/// the share is `None` where either series is missing a day of the window, 
+/// or no tests were made over it.
+pub fn rolling_positivity(cases: &[(Date, i32)], tests: &[(Date, i32)], window: usize) -> Vec<(Date, Option<f64>)> {
+    let aligned: BTreeMap<Date, (Option<i32>, Option<i32>)> = align_series(cases, tests)
+        .into_iter()
+        .map(|(date, c, t)| (date, (c, t)))
+        .collect();
+
+    aligned
+        .keys()
+        .map(|date| {
+            let totals = (0..window as i64)
+                .map(|d| match aligned.get(&(*date - time::Duration::days(d))) {
+                    Some((Some(c), Some(t))) => Some((*c as i64, *t as i64)),
+                    _ => None,
+                })
+                .try_fold((0, 0), |(cs, ts), day| day.map(|(c, t)| (cs + c, ts + t)));
+            let share = match totals {
+                Some((c, t)) if window > 0 && t != 0 => Some(c as f64 / t as f64),
+                _ => None,
+            };
+            (*date, share)
+        })
+        .collect()
+}
+
+/// Flags each day of the series with whether a national lockdown was in 
+/// force in the nation on that day, as listed by `Nation::lockdowns`, for 
+/// marking lockdowns on charts.
+pub fn annotate_lockdowns(series: &[(Date, i32)], nation: Nation) -> Vec<(Date, i32, bool)> {
+    let lockdowns = nation.lockdowns();
+    sorted(series)
+        .into_iter()
+        .map(|(date, value)| {
+            let in_lockdown = lockdowns.iter().any(|(start, end)| (*start..=*end).contains(&date));
+            (date, value, in_lockdown)
+        })
+        .collect()
+}
+
+/// Aligns two series (such as the same metric for two areas) on a shared 
+/// date axis covering every date in either, with each series' value for the 
+/// date, or `None` where that series has no value for it.
+pub fn align_series(a: &[(Date, i32)], b: &[(Date, i32)]) -> Vec<(Date, Option<i32>, Option<i32>)> {
+    let mut dates: BTreeMap<Date, (Option<i32>, Option<i32>)> = BTreeMap::new();
+    for (date, value) in a {
+        dates.entry(*date).or_default().0 = Some(*value);
+    }
+    for (date, value) in b {
+        dates.entry(*date).or_default().1 = Some(*value);
+    }
+
+    dates.into_iter().map(|(date, (a, b))| (date, a, b)).collect()
+}
+
+/// Expresses each day's value relative to the same calendar day (ignoring 
+/// the year) of a baseline series, as `current / baseline`, for analyses 
+/// such as excess deaths.
+///
+/// The ratio is `None` where the baseline has no value for the day, or its 
+/// value is zero.
+pub fn relative_to_baseline(current: &[(Date, i32)], baseline: &[(Date, i32)]) -> Vec<(Date, Option<f64>)> {
+    let baseline: BTreeMap<(u8, u8), i32> = baseline
+        .iter()
+        .map(|(date, value)| ((date.month(), date.day()), *value))
+        .collect();
+
+    sorted(current)
+        .into_iter()
+        .map(|(date, value)| {
+            let ratio = match baseline.get(&(date.month(), date.day())) {
+                Some(base) if *base != 0 => Some(value as f64 / *base as f64),
+                _ => None,
+            };
+            (date, ratio)
+        })
+        .collect()
+}
+
+/// What `interpolate_linear` does with the nulls before the first or after 
+/// the last present value, which have no neighbour to interpolate from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edges {
+    /// Keep the dates, with a value of `f64::NAN`.
+    Keep,
+    /// Drop the dates from the result.
+    Drop,
+}
+
+/// Fills the nulls in a series by linear interpolation, in days, between 
+/// the nearest present values either side, so that it can be charted 
+/// without gaps.
+///
+/// Nulls at either end of the series are handled according to `edges`.
+pub fn interpolate_linear(series: &[(Date, Option<i32>)], edges: Edges) -> Vec<(Date, f64)> {
+    let mut series = series.to_vec();
+    series.sort_by_key(|(date, _)| *date);
+
+    let mut result = vec![];
+    let mut previous: Option<(Date, f64)> = None;
+    let mut gap: Vec<Date> = vec![];
+    for (date, value) in series {
+        let value = match value {
+            Some(value) => value as f64,
+            None => {
+                gap.push(date);
+                continue;
+            }
+        };
+        for missing in gap.drain(..) {
+            match previous {
+                Some((start, from)) => {
+                    let t = (missing - start).whole_days() as f64 / (date - start).whole_days() as f64;
+                    result.push((missing, from + (value - from) * t));
+                }
+                None if edges == Edges::Keep => result.push((missing, f64::NAN)),
+                None => {}
+            }
+        }
+        result.push((date, value));
+        previous = Some((date, value));
+    }
+    if edges == Edges::Keep {
+        result.extend(gap.into_iter().map(|date| (date, f64::NAN)));
+    }
+    result
+}
+
+/// Smooths a series with LOESS (locally weighted linear regression), 
+/// returning a smoothed value for each date.
+///
+/// Each value is fitted from the nearest `span` fraction of the series' 
+/// points (at least two), weighted by a tricube function of their distance in 
+/// days, so `span` trades smoothness (towards 1) for responsiveness (towards 
+/// 0).
+pub fn smooth_loess(series: &[(Date, i32)], span: f64) -> Vec<(Date, f64)> {
+    let series = sorted(series);
+    if series.is_empty() {
+        return vec![];
+    }
+
+    let start = series[0].0;
+    let points: Vec<(f64, f64)> = series
+        .iter()
+        .map(|(date, v)| ((*date - start).whole_days() as f64, *v as f64))
+        .collect();
+    let k = ((span * points.len() as f64).ceil() as usize).clamp(2.min(points.len()), points.len());
+
+    series
+        .iter()
+        .zip(&points)
+        .map(|((date, _), (x, _))| {
+            let mut distances: Vec<f64> = points.iter().map(|(xi, _)| (xi - x).abs()).collect();
+            distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let max_distance = distances[k - 1];
+
+            let weighted: Vec<(f64, f64, f64)> = points
+                .iter()
+                .filter_map(|(xi, yi)| {
+                    let d = (xi - x).abs();
+                    if d > max_distance {
+                        return None;
+                    }
+                    let w = if max_distance == 0.0 {
+                        1.0
+                    } else {
+                        (1.0 - (d / max_distance).powi(3)).powi(3)
+                    };
+                    Some((*xi, *yi, w))
+                })
+                .collect();
+
+            (*date, local_fit(&weighted, *x))
+        })
+        .collect()
+}
+
+/// Evaluates at `x` the weighted least-squares line through the points, 
+/// given as `(x, y, weight)`, falling back to their weighted mean where no 
+/// line can be fitted. The point at `x` itself has weight 1, so the total 
+/// weight is never zero.
+fn local_fit(points: &[(f64, f64, f64)], x: f64) -> f64 {
+    let total: f64 = points.iter().map(|(_, _, w)| w).sum();
+
+    let mean_x = points.iter().map(|(xi, _, w)| w * xi).sum::<f64>() / total;
+    let mean_y = points.iter().map(|(_, yi, w)| w * yi).sum::<f64>() / total;
+    let sxx: f64 = points.iter().map(|(xi, _, w)| w * (xi - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|(xi, yi, w)| w * (xi - mean_x) * (yi - mean_y)).sum();
+
+    if sxx == 0.0 {
+        mean_y
+    } else {
+        mean_y + sxy / sxx * (x - mean_x)
+    }
+}
+
+/// How daily values are combined when aggregating over a period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Agg {
+    Sum,
+    Mean,
+}
+
+fn days_in_month(year: i32, month: u8) -> usize {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if time::is_leap_year(year) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// Aggregates a daily series by calendar month, returning `(year, month, 
+/// value, partial)` for each month the series covers.
+///
+/// Months which the series only partly covers (typically those at either end 
+/// of the series) are included, aggregating only the days present, and are 
+/// flagged with `partial` set to `true`.
+pub fn aggregate_monthly(series: &[(Date, i32)], agg: Agg) -> Vec<(i32, u8, f64, bool)> {
+    let mut months: Vec<(i32, u8, i64, usize)> = vec![];
+    for (date, value) in sorted(series) {
+        match months.last_mut() {
+            Some((year, month, total, days)) if *year == date.year() && *month == date.month() => {
+                *total += value as i64;
+                *days += 1;
+            }
+            _ => months.push((date.year(), date.month(), value as i64, 1)),
+        }
+    }
+
+    months
+        .into_iter()
+        .map(|(year, month, total, days)| {
+            let value = match agg {
+                Agg::Sum => total as f64,
+                Agg::Mean => total as f64 / days as f64,
+            };
+            (year, month, value, days < days_in_month(year, month))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubling_every_three_days() {
+        let start = Date::try_from_ymd(2020, 9, 1).unwrap();
+        // Reverse-chronological, as returned by the API.
+        let series: Vec<(Date, i32)> = (0..12)
+            .rev()
+            .map(|d| {
+                let value = 1000.0 * 2f64.powf(d as f64 / 3.0);
+                (start + time::Duration::days(d), value.round() as i32)
+            })
+            .collect();
+
+        let estimates = doubling_time(&series, 7);
+        assert_eq!(estimates.len(), 12);
+        for (i, (date, estimate)) in estimates.iter().enumerate() {
+            assert_eq!(*date, start + time::Duration::days(i as i64));
+            if i < 6 {
+                assert_eq!(*estimate, None);
+            } else {
+                assert!((estimate.unwrap() - 3.0).abs() < 0.01);
+            }
+        }
+
+        let flat = vec![(start, 100), (start + time::Duration::days(1), 100)];
+        assert_eq!(doubling_time(&flat, 2)[1].1, None);
+    }
+
+    #[test]
+    fn monthly_sums_and_means() {
+        let start = Date::try_from_ymd(2020, 10, 1).unwrap();
+        // All of October at 10 per day, then the first half of November at 4.
+        let series: Vec<(Date, i32)> = (0..46)
+            .map(|d| (start + time::Duration::days(d), if d < 31 { 10 } else { 4 }))
+            .collect();
+
+        assert_eq!(aggregate_monthly(&series, Agg::Sum), vec![
+            (2020, 10, 310.0, false),
+            (2020, 11, 60.0, true),
+        ]);
+        assert_eq!(aggregate_monthly(&series, Agg::Mean), vec![
+            (2020, 10, 10.0, false),
+            (2020, 11, 4.0, true),
+        ]);
+    }
+
+    #[test]
+    fn downward_correction() {
+        let series = vec![
+            (Date::try_from_ymd(2020, 10, 4).unwrap(), 130),
+            (Date::try_from_ymd(2020, 10, 3).unwrap(), 110),
+            (Date::try_from_ymd(2020, 10, 2).unwrap(), 120),
+            (Date::try_from_ymd(2020, 10, 1).unwrap(), 100),
+        ];
+
+        assert_eq!(detect_revisions(&series), vec![(Date::try_from_ymd(2020, 10, 3).unwrap(), -10)]);
+
+        let series = vec![
+            (Date::try_from_ymd(2020, 10, 2).unwrap(), -2_000_000_000),
+            (Date::try_from_ymd(2020, 10, 1).unwrap(), 2_000_000_000),
+        ];
+        assert_eq!(detect_revisions(&series), vec![(Date::try_from_ymd(2020, 10, 2).unwrap(), i32::MIN)]);
+    }
+
+    #[test]
+    fn inconsistent_cumulative() {
+        let d = |day| Date::try_from_ymd(2020, 10, day).unwrap();
+        let cumulative = vec![(d(5), 160), (d(4), 140), (d(2), 110), (d(1), 100)];
+        let daily = vec![(d(5), 20), (d(4), 25), (d(3), 5), (d(2), 10), (d(1), 100)];
+
+        assert_eq!(check_consistency(&cumulative, &daily), vec![]);
+
+        let daily = vec![(d(5), 15), (d(2), 12)];
+        assert_eq!(check_consistency(&cumulative, &daily), vec![(d(2), -2), (d(5), 5)]);
+
+        let cumulative = vec![(d(2), i32::MAX), (d(1), i32::MIN)];
+        assert_eq!(check_consistency(&cumulative, &[(d(2), -1)]), vec![(d(2), i32::MAX)]);
+        let cumulative = vec![(d(2), i32::MAX), (d(1), 0)];
+        assert_eq!(check_consistency(&cumulative, &[(d(2), i32::MAX)]), vec![]);
+    }
+
+    #[test]
+    fn reconciled_daily() {
+        let d = |day| Date::try_from_ymd(2020, 10, day).unwrap();
+        let daily = vec![(d(4), Some(20)), (d(3), None), (d(2), None), (d(1), Some(100))];
+        let cumulative = vec![(d(5), Some(160)), (d(4), Some(140)), (d(3), Some(130)), (d(2), None), (d(1), Some(100))];
+
+        assert_eq!(
+            reconcile(&daily, &cumulative),
+            vec![(d(1), Some(100)), (d(2), None), (d(3), None), (d(4), Some(20)), (d(5), Some(20))]
+        );
+
+        let cumulative = vec![(d(3), Some(130)), (d(2), Some(115))];
+        assert_eq!(reconcile(&daily, &cumulative)[2], (d(3), Some(15)));
+
+        let cumulative = vec![(d(3), Some(i32::MAX)), (d(2), Some(-1))];
+        assert_eq!(reconcile(&daily, &cumulative)[2], (d(3), None));
+    }
+
+    #[test]
+    fn backlog_spike() {
+        let start = Date::try_from_ymd(2020, 10, 1).unwrap();
+        let series: Vec<(Date, i32)> = (0..21)
+            .map(|d| {
+                let value = if d == 14 { 1000 } else { 100 + [0, 5, -4, 3, -6, 2, -2][d as usize % 7] };
+                (start + time::Duration::days(d), value)
+            })
+            .collect();
+
+        assert_eq!(detect_outliers(&series, 7, 3.0), vec![start + time::Duration::days(14)]);
+    }
+
+    #[test]
+    fn cumulative_totals() {
+        let d = |day| Date::try_from_ymd(2020, 10, day).unwrap();
+        // 3 October is missing, and 5 October corrects an earlier day.
+        let series = vec![(d(5), -4), (d(4), 20), (d(2), 5), (d(1), 10)];
+
+        assert_eq!(
+            cumulative(&series),
+            vec![(d(1), 10), (d(2), 15), (d(3), 15), (d(4), 35), (d(5), 31)]
+        );
+        assert_eq!(cumulative(&[]), vec![]);
+    }
+
+    #[test]
+    fn weekend_dip() {
+        // 5 October 2020 was a Monday.
+        let start = Date::try_from_ymd(2020, 10, 5).unwrap();
+        let series: Vec<(Date, i32)> = (0..28)
+            .map(|d| (start + time::Duration::days(d), if d % 7 < 5 { 100 } else { 50 }))
+            .collect();
+
+        let factors = day_of_week_factors(&series);
+        let mean = 600.0 / 7.0;
+        for (day, factor) in factors.iter().enumerate() {
+            let expected = if day < 5 { 100.0 / mean } else { 50.0 / mean };
+            assert!((factor - expected).abs() < 1e-9, "day {}: {}", day, factor);
+        }
+
+        // With the effect removed, every day is the weekly mean.
+        for (_, value) in deseasonalize(&series, &factors) {
+            assert!((value - mean).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn aligned_series() {
+        let d = |day| Date::try_from_ymd(2020, 10, day).unwrap();
+        let a = vec![(d(3), 30), (d(2), 20), (d(1), 10)];
+        let b = vec![(d(4), 4), (d(2), 2)];
+
+        assert_eq!(
+            align_series(&a, &b),
+            vec![
+                (d(1), Some(10), None),
+                (d(2), Some(20), Some(2)),
+                (d(3), Some(30), None),
+                (d(4), None, Some(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn rolling_positive_share() {
+        let d = |day| Date::try_from_ymd(2020, 10, day).unwrap();
+        let cases = vec![(d(5), 30), (d(4), 10), (d(3), 0), (d(2), 20), (d(1), 10)];
+        let tests = vec![(d(5), 100), (d(4), 100), (d(3), 0), (d(2), 0), (d(1), 100)];
+
+        assert_eq!(
+            rolling_positivity(&cases, &tests, 2),
+            vec![
+                (d(1), None),
+                (d(2), Some(0.3)),
+                (d(3), None),
+                (d(4), Some(0.1)),
+                (d(5), Some(0.2)),
+            ]
+        );
+
+        let partial = vec![(d(4), 100), (d(3), 100), (d(1), 100)];
+        let shares = rolling_positivity(&cases, &partial, 2);
+        assert_eq!(shares[1], (d(2), None));
+        assert_eq!(shares[3], (d(4), Some(0.05)));
+    }
+
+    #[test]
+    fn lockdown_annotations() {
+        let series = vec![
+            (Date::try_from_ymd(2020, 12, 2).unwrap(), 4),
+            (Date::try_from_ymd(2020, 12, 1).unwrap(), 3),
+            (Date::try_from_ymd(2020, 11, 5).unwrap(), 2),
+            (Date::try_from_ymd(2020, 11, 4).unwrap(), 1),
+        ];
+
+        let flags: Vec<bool> = annotate_lockdowns(&series, Nation::England).into_iter().map(|(_, _, f)| f).collect();
+        assert_eq!(flags, vec![false, true, true, false]);
+
+        let wales = annotate_lockdowns(&series, Nation::Wales);
+        assert_eq!(wales[0], (Date::try_from_ymd(2020, 11, 4).unwrap(), 1, true));
+        assert!(!wales[2].2);
+    }
+
+    #[test]
+    fn baseline_ratios() {
+        let d = |year, day| Date::try_from_ymd(year, 3, day).unwrap();
+        let current = vec![(d(2021, 4), 50), (d(2021, 3), 30), (d(2021, 2), 20), (d(2021, 1), 15)];
+        let baseline = vec![(d(2019, 1), 10), (d(2019, 2), 0), (d(2019, 3), 20)];
+
+        assert_eq!(
+            relative_to_baseline(&current, &baseline),
+            vec![
+                (d(2021, 1), Some(1.5)),
+                (d(2021, 2), None),
+                (d(2021, 3), Some(1.5)),
+                (d(2021, 4), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn interpolated_gaps() {
+        let d = |day| Date::try_from_ymd(2020, 10, day).unwrap();
+        let series = vec![(d(5), None), (d(4), Some(30)), (d(3), None), (d(2), Some(10)), (d(1), None)];
+
+        assert_eq!(
+            interpolate_linear(&series, Edges::Drop),
+            vec![(d(2), 10.0), (d(3), 20.0), (d(4), 30.0)]
+        );
+
+        let kept = interpolate_linear(&series, Edges::Keep);
+        assert_eq!(kept.len(), 5);
+        assert!(kept[0].1.is_nan() && kept[4].1.is_nan());
+        assert_eq!(kept[2], (d(3), 20.0));
+    }
+
+    #[test]
+    fn loess_follows_trend() {
+        let start = Date::try_from_ymd(2020, 10, 1).unwrap();
+        let series: Vec<(Date, i32)> = (0..30)
+            .map(|i| {
+                let noise = if i % 2 == 0 { 7 } else { -7 };
+                (start + time::Duration::days(i), 10 * i as i32 + noise)
+            })
+            .collect();
+        // The raw series isn't monotone...
+        assert!(series.windows(2).any(|w| w[1].1 < w[0].1));
+
+        let smoothed = smooth_loess(&series, 0.3);
+        assert_eq!(smoothed.len(), series.len());
+        assert_eq!(smoothed[0].0, start);
+        // ...but the smoothed trend is.
+        assert!(smoothed.windows(2).all(|w| w[1].1 > w[0].1));
+    }
+}
@@ -0,0 +1,603 @@
+//! The seam through which requests reach the API.
+//!
+//! By default, requests are sent over HTTP with `reqwest`; a custom 
+//! `Transport` may be provided to a request with `Request::set_transport`, 
+//! for instance to serve canned responses in tests.
+//!
+//! Fetching the API over HTTPS requires a TLS backend, selected with either 
+//! the `native-tls` feature (the default) or the `rustls` feature; without 
+//! one, `HttpTransport` returns `Error::TlsUnavailable` for HTTPS URLs.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::Error;
+
+/// A response to a single page request, as returned by a `Transport`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+impl Response {
+    /// Creates a Response with the provided status code and body, and no 
+    /// headers.
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Response {
+        Response {
+            status,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+}
+
+/// Fetches pages from the API.
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Sends a GET request for the provided URL with the provided headers.
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error>;
+
+    /// Sends a GET request as for `get`, giving up once the provided timeout 
+    /// elapses, if one is provided, as set with `Client::set_timeout`.
+    /// 
+    /// By default, the timeout is ignored; `HttpTransport` applies it to the 
+    /// request, and `CookieTransport` and `record::RecordingTransport` pass 
+    /// it on.
+    fn get_with_timeout(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        timeout: Option<Duration>,
+    ) -> Result<Response, Error> {
+        let _ = timeout;
+        self.get(url, headers)
+    }
+}
+
+/// The method of every request sent by `HttpTransport` and 
+/// `HttpAsyncTransport`, as the name of `Transport::get` implies, and so the 
+/// method `Client` checks before retrying a request.
+pub(crate) const METHOD: reqwest::Method = reqwest::Method::GET;
+
+/// Whether a TLS backend was selected with the crate's features.
+const TLS: bool = cfg!(any(feature = "native-tls", feature = "rustls"));
+
+/// Replaces the opaque error `reqwest` returns for an HTTPS URL when it was 
+/// built without a TLS backend.
+fn tls_error(url: &str, e: reqwest::Error) -> Error {
+    if !TLS && url.starts_with("https:") {
+        Error::TlsUnavailable
+    } else {
+        Error::RequestErr(e)
+    }
+}
+
+/// The connection pool settings of an `HttpTransport`, for tuning how many 
+/// connections are kept alive between requests.
+/// 
+/// The defaults are those of `reqwest`: no limit on the idle connections 
+/// kept for each host, each of which is closed after 90 seconds unused. A 
+/// batch fanning out over many threads may want fewer idle connections, or 
+/// a shorter timeout, to avoid holding sockets open once the batch is done.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolConfig {
+    /// The most idle connections kept alive to each host.
+    pub max_idle_per_host: usize,
+    /// How long an idle connection is kept alive, or `None` to keep idle 
+    /// connections until the transport is dropped.
+    pub idle_timeout: Option<Duration>,
+}
+impl Default for PoolConfig {
+    fn default() -> PoolConfig {
+        PoolConfig {
+            max_idle_per_host: usize::MAX,
+            idle_timeout: Some(Duration::from_secs(90)),
+        }
+    }
+}
+
+/// The default Transport, which sends requests over HTTP with `reqwest`.
+#[derive(Debug)]
+pub struct HttpTransport {
+    client: reqwest::blocking::Client,
+    pool: Option<PoolConfig>,
+}
+impl HttpTransport {
+    /// Creates an HttpTransport using the TLS backend selected with the 
+    /// crate's features.
+    /// 
+    /// ## Panics
+    /// 
+    /// This function will panic if the TLS backend can't be initialised, as 
+    /// `reqwest::blocking::Client::new` does.
+    pub fn new() -> HttpTransport {
+        HttpTransport::with_pool(PoolConfig::default())
+    }
+
+    /// Creates an HttpTransport with the provided connection pool settings, 
+    /// panicking as `new` does.
+    pub fn with_pool(pool: PoolConfig) -> HttpTransport {
+        let builder = reqwest::blocking::Client::builder()
+            .pool_max_idle_per_host(pool.max_idle_per_host)
+            .pool_idle_timeout(pool.idle_timeout);
+        #[cfg(feature = "rustls")]
+        let builder = builder.use_rustls_tls();
+        HttpTransport {
+            client: builder.build().expect("failed to initialise the TLS backend"),
+            pool: Some(pool),
+        }
+    }
+
+    /// Creates an HttpTransport sending requests with the provided client, 
+    /// for configuration (such as timeouts, proxies, or TLS roots) not 
+    /// otherwise exposed by this library.
+    pub fn with_client(client: reqwest::blocking::Client) -> HttpTransport {
+        HttpTransport { client, pool: None }
+    }
+
+    /// Returns the connection pool settings the transport was created with, 
+    /// or `None` if it was created with `with_client`, whose settings aren't 
+    /// known.
+    pub fn pool(&self) -> Option<&PoolConfig> {
+        self.pool.as_ref()
+    }
+
+    /// Sends a GET request for the provided URL with the provided headers, 
+    /// returning the response without reading its body.
+    pub fn send(&self, url: &str, headers: &[(&str, &str)]) -> Result<reqwest::blocking::Response, Error> {
+        self.send_with_timeout(url, headers, None)
+    }
+
+    /// Sends a GET request as for `send`, giving up once the provided 
+    /// timeout elapses, if one is provided, in place of the client's own.
+    fn send_with_timeout(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        timeout: Option<Duration>,
+    ) -> Result<reqwest::blocking::Response, Error> {
+        let mut req = self.client.request(METHOD, url);
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+        if let Some(timeout) = timeout {
+            req = req.timeout(timeout);
+        }
+
+        req.send().map_err(|e| tls_error(url, e))
+    }
+}
+impl Default for HttpTransport {
+    fn default() -> HttpTransport {
+        HttpTransport::new()
+    }
+}
+impl Transport for HttpTransport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.get_with_timeout(url, headers, None)
+    }
+
+    fn get_with_timeout(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        timeout: Option<Duration>,
+    ) -> Result<Response, Error> {
+        let res = self.send_with_timeout(url, headers, timeout)?;
+        let status = res.status().as_u16();
+        let headers = res
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        let body = res.bytes().map_err(Error::RequestErr)?.to_vec();
+
+        Ok(Response {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// A Transport which passes requests on to another Transport, keeping the 
+/// cookies set by its responses and sending them with each later request, 
+/// for APIs behind gateways which issue a session cookie that must be echoed 
+/// back. Requires the `cookies` feature.
+/// 
+/// Only each cookie's name and value are kept: its attributes (such as its 
+/// domain, path, and expiry) are ignored, so the transport should only be 
+/// used for a single site, and for no longer than its session.
+#[cfg(feature = "cookies")]
+#[derive(Debug)]
+pub struct CookieTransport {
+    inner: std::sync::Arc<dyn Transport>,
+    cookies: std::sync::Mutex<Vec<(String, String)>>,
+}
+#[cfg(feature = "cookies")]
+impl CookieTransport {
+    /// Creates a CookieTransport, with no cookies, sending requests through 
+    /// `inner`.
+    pub fn new(inner: std::sync::Arc<dyn Transport>) -> CookieTransport {
+        CookieTransport {
+            inner,
+            cookies: std::sync::Mutex::new(vec![]),
+        }
+    }
+}
+#[cfg(feature = "cookies")]
+impl Transport for CookieTransport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.get_with_timeout(url, headers, None)
+    }
+
+    fn get_with_timeout(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        timeout: Option<Duration>,
+    ) -> Result<Response, Error> {
+        let cookie = self
+            .cookies
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        let mut headers = headers.to_vec();
+        if !cookie.is_empty() {
+            headers.push(("Cookie", &cookie));
+        }
+
+        let res = self.inner.get_with_timeout(url, &headers, timeout)?;
+        let mut cookies = self.cookies.lock().unwrap();
+        for (_, set) in res.headers.iter().filter(|(n, _)| n.eq_ignore_ascii_case("set-cookie")) {
+            let pair = set.split(';').next().unwrap_or_default();
+            if let Some((name, value)) = pair.split_once('=') {
+                let name = name.trim();
+                cookies.retain(|(n, _)| n != name);
+                cookies.push((name.to_string(), value.trim().to_string()));
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// Fetches pages from the API asynchronously, for use by `Client::get_async`.
+#[cfg(feature = "async")]
+pub trait AsyncTransport: fmt::Debug + Send + Sync {
+    /// Sends a GET request for the provided URL with the provided headers.
+    fn get<'a>(
+        &'a self,
+        url: &'a str,
+        headers: &'a [(&'a str, &'a str)],
+    ) -> futures::future::BoxFuture<'a, Result<Response, Error>>;
+
+    /// Sends a GET request as for `get`, giving up once the provided timeout 
+    /// elapses, if one is provided, as with `Transport::get_with_timeout`.
+    /// 
+    /// By default, the timeout is ignored; `HttpAsyncTransport` applies it to 
+    /// the request.
+    fn get_with_timeout<'a>(
+        &'a self,
+        url: &'a str,
+        headers: &'a [(&'a str, &'a str)],
+        timeout: Option<Duration>,
+    ) -> futures::future::BoxFuture<'a, Result<Response, Error>> {
+        let _ = timeout;
+        self.get(url, headers)
+    }
+}
+
+/// The default AsyncTransport, which sends requests over HTTP with `reqwest`.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct HttpAsyncTransport {
+    client: reqwest::Client,
+}
+#[cfg(feature = "async")]
+impl HttpAsyncTransport {
+    /// Creates an HttpAsyncTransport using the TLS backend selected with the 
+    /// crate's features, panicking as `HttpTransport::new` does.
+    pub fn new() -> HttpAsyncTransport {
+        HttpAsyncTransport::with_pool(PoolConfig::default())
+    }
+
+    /// Creates an HttpAsyncTransport with the provided connection pool 
+    /// settings, as with `HttpTransport::with_pool`.
+    pub fn with_pool(pool: PoolConfig) -> HttpAsyncTransport {
+        let builder = reqwest::Client::builder()
+            .pool_max_idle_per_host(pool.max_idle_per_host)
+            .pool_idle_timeout(pool.idle_timeout);
+        #[cfg(feature = "rustls")]
+        let builder = builder.use_rustls_tls();
+        HttpAsyncTransport {
+            client: builder.build().expect("failed to initialise the TLS backend"),
+        }
+    }
+
+    /// Creates an HttpAsyncTransport sending requests with the provided 
+    /// client, as with `HttpTransport::with_client`.
+    pub fn with_client(client: reqwest::Client) -> HttpAsyncTransport {
+        HttpAsyncTransport { client }
+    }
+}
+#[cfg(feature = "async")]
+impl Default for HttpAsyncTransport {
+    fn default() -> HttpAsyncTransport {
+        HttpAsyncTransport::new()
+    }
+}
+#[cfg(feature = "async")]
+impl AsyncTransport for HttpAsyncTransport {
+    fn get<'a>(
+        &'a self,
+        url: &'a str,
+        headers: &'a [(&'a str, &'a str)],
+    ) -> futures::future::BoxFuture<'a, Result<Response, Error>> {
+        self.get_with_timeout(url, headers, None)
+    }
+
+    fn get_with_timeout<'a>(
+        &'a self,
+        url: &'a str,
+        headers: &'a [(&'a str, &'a str)],
+        timeout: Option<Duration>,
+    ) -> futures::future::BoxFuture<'a, Result<Response, Error>> {
+        Box::pin(async move {
+            let mut req = self.client.request(METHOD, url);
+            for (name, value) in headers {
+                req = req.header(*name, *value);
+            }
+            if let Some(timeout) = timeout {
+                req = req.timeout(timeout);
+            }
+
+            let res = req.send().await.map_err(|e| tls_error(url, e))?;
+            let status = res.status().as_u16();
+            let headers = res
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.as_str().to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                })
+                .collect();
+            let body = res.bytes().await.map_err(Error::RequestErr)?.to_vec();
+
+            Ok(Response {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+/// A Transport serving queued responses in order, recording the URL and 
+/// headers of each request it receives.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct MockTransport {
+    responses: std::sync::Mutex<std::collections::VecDeque<Response>>,
+    pub(crate) urls: std::sync::Mutex<Vec<String>>,
+    pub(crate) headers: std::sync::Mutex<Vec<Vec<(String, String)>>>,
+}
+#[cfg(test)]
+impl MockTransport {
+    pub(crate) fn new(responses: Vec<Response>) -> MockTransport {
+        MockTransport {
+            responses: std::sync::Mutex::new(responses.into()),
+            urls: std::sync::Mutex::new(vec![]),
+            headers: std::sync::Mutex::new(vec![]),
+        }
+    }
+
+    /// Builds a successful page response with the provided rows, linking to 
+    /// a next page if `next` is set.
+    pub(crate) fn page(rows: &str, next: bool) -> Response {
+        let next = if next { "\"/v1/data?page=next\"" } else { "null" };
+        Response::new(
+            200,
+            format!(r#"{{"data": [{}], "pagination": {{"next": {}}}}}"#, rows, next),
+        )
+    }
+}
+#[cfg(test)]
+impl Transport for MockTransport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.urls.lock().unwrap().push(url.to_string());
+        self.headers
+            .lock()
+            .unwrap()
+            .push(headers.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect());
+        Ok(self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("MockTransport ran out of responses"))
+    }
+}
+
+/// A Transport paging through a fixed set of rows, honouring the `page` and 
+/// `pageSize` parameters of the URL, and recording the URL of each request.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct PagedTransport {
+    rows: Vec<String>,
+    default_page_size: usize,
+    pub(crate) urls: std::sync::Mutex<Vec<String>>,
+}
+#[cfg(test)]
+impl PagedTransport {
+    pub(crate) fn new(rows: Vec<String>, default_page_size: usize) -> PagedTransport {
+        PagedTransport {
+            rows,
+            default_page_size,
+            urls: std::sync::Mutex::new(vec![]),
+        }
+    }
+
+    fn param(url: &str, name: &str) -> Option<usize> {
+        url.split(['?', '&'])
+            .find_map(|p| p.strip_prefix(name)?.strip_prefix('='))
+            .map(|v| v.parse().unwrap())
+    }
+}
+#[cfg(test)]
+impl Transport for PagedTransport {
+    fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.urls.lock().unwrap().push(url.to_string());
+
+        let page = PagedTransport::param(url, "page").unwrap_or(1);
+        let size = PagedTransport::param(url, "pageSize").unwrap_or(self.default_page_size);
+        let pages = self.rows.len().div_ceil(size);
+        let rows: Vec<&str> = self.rows.iter().skip((page - 1) * size).take(size).map(|r| r.as_str()).collect();
+        let link = |p: usize| {
+            if p >= 1 && p <= pages {
+                format!("\"/v1/data?page={}\"", p)
+            } else {
+                String::from("null")
+            }
+        };
+
+        Ok(Response::new(
+            200,
+            format!(
+                r#"{{"data": [{}], "pagination": {{"previous": {}, "next": {}}}}}"#,
+                rows.join(", "),
+                link(page - 1),
+                link(page + 1)
+            ),
+        ))
+    }
+}
+
+/// A Transport serving the response of the first route whose pattern the 
+/// URL contains, for concurrent requests whose order isn't known, recording 
+/// the URL of each request.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct RoutedTransport {
+    routes: Vec<(String, Response)>,
+    pub(crate) urls: std::sync::Mutex<Vec<String>>,
+}
+#[cfg(test)]
+impl RoutedTransport {
+    pub(crate) fn new(routes: Vec<(&str, Response)>) -> RoutedTransport {
+        RoutedTransport {
+            routes: routes.into_iter().map(|(p, r)| (p.to_string(), r)).collect(),
+            urls: std::sync::Mutex::new(vec![]),
+        }
+    }
+}
+#[cfg(test)]
+impl Transport for RoutedTransport {
+    fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.urls.lock().unwrap().push(url.to_string());
+        Ok(self
+            .routes
+            .iter()
+            .find(|(pattern, _)| url.contains(pattern.as_str()))
+            .unwrap_or_else(|| panic!("RoutedTransport has no route for {}", url))
+            .1
+            .clone())
+    }
+}
+
+/// Serves the provided raw HTTP responses on a local port, one per 
+/// connection and in order, each after its delay, returning the server's 
+/// base URL.
+#[cfg(test)]
+pub(crate) fn serve(responses: Vec<(std::time::Duration, String)>) -> String {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for (delay, response) in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            std::thread::sleep(delay);
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn custom_client_timeout() {
+        let url = serve(vec![(
+            Duration::from_millis(500),
+            String::from("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"),
+        )]);
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        match HttpTransport::with_client(client).get(&url, &[]) {
+            Err(Error::RequestErr(e)) => assert!(e.is_timeout()),
+            r => panic!("expected a timeout, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn configured_pool() {
+        let pool = PoolConfig {
+            max_idle_per_host: 4,
+            idle_timeout: Some(Duration::from_secs(5)),
+        };
+        let transport = HttpTransport::with_pool(pool.clone());
+        assert_eq!(transport.pool(), Some(&pool));
+        assert_eq!(HttpTransport::new().pool(), Some(&PoolConfig::default()));
+        assert_eq!(HttpTransport::with_client(reqwest::blocking::Client::new()).pool(), None);
+
+        let url = serve(vec![(
+            Duration::from_millis(0),
+            String::from("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"),
+        )]);
+        assert_eq!(transport.get(&url, &[]).unwrap().body, b"{}");
+    }
+
+    #[test]
+    #[cfg(any(feature = "native-tls", feature = "rustls"))]
+    fn https_with_tls() {
+        // Nothing listens on port 1, so the connection is refused, but only 
+        // once TLS is known to be available.
+        match HttpTransport::new().get("https://127.0.0.1:1/", &[]) {
+            Err(Error::RequestErr(e)) => assert!(e.is_connect()),
+            r => panic!("expected a connection error, got {:?}", r),
+        }
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+    fn https_without_tls() {
+        match HttpTransport::new().get("https://127.0.0.1:1/", &[]) {
+            Err(Error::TlsUnavailable) => {}
+            r => panic!("expected Error::TlsUnavailable, got {:?}", r),
+        }
+    }
+}
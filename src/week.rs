@@ -0,0 +1,98 @@
+//! ISO 8601 weeks, for metrics and filters indexed by week rather than date.
+
+use std::fmt;
+use std::str::FromStr;
+
+use time::{Date, Weekday};
+
+use crate::{Error, FilterValue};
+
+/// An ISO 8601 week: a week-numbering year and a week number within it, 
+/// written as `2021-W03` by the API.
+/// 
+/// Weeks run Monday to Sunday, and the first week of a year is the one 
+/// containing its first Thursday, so a week's year may differ from the 
+/// calendar year of some of its days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Week {
+    year: i32,
+    week: u8,
+}
+impl Week {
+    /// Creates a Week, checking that the week number exists in the year.
+    /// 
+    /// ## Errors
+    /// 
+    /// Returns `Error::InvalidWeek` if the year has no such week (years have 
+    /// 52 or 53 weeks).
+    pub fn new(year: i32, week: u8) -> Result<Week, Error> {
+        if week >= 1 && week <= time::util::weeks_in_year(year) {
+            Ok(Week { year, week })
+        } else {
+            Err(Error::InvalidWeek(format!("{}-W{:02}", year, week)))
+        }
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn week(&self) -> u8 {
+        self.week
+    }
+
+    /// Returns the Monday which begins the week.
+    pub fn monday(&self) -> Date {
+        Date::try_from_iso_ywd(self.year, self.week, Weekday::Monday).unwrap()
+    }
+
+    /// Returns the week containing the date.
+    pub fn of(date: Date) -> Week {
+        let (year, week) = date.iso_year_week();
+        Week { year, week }
+    }
+}
+impl FromStr for Week {
+    type Err = Error;
+
+    /// Parses a week in the form used by the API, such as `2021-W03`.
+    fn from_str(s: &str) -> Result<Week, Error> {
+        let invalid = || Error::InvalidWeek(s.to_string());
+        let (year, week) = s.split_once("-W").ok_or_else(invalid)?;
+        if week.len() != 2 {
+            return Err(invalid());
+        }
+
+        Week::new(year.parse().map_err(|_| invalid())?, week.parse().map_err(|_| invalid())?)
+    }
+}
+impl fmt::Display for Week {
+    /// Formats the week as used by the API, such as `2021-W03`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-W{:02}", self.year, self.week)
+    }
+}
+impl From<Week> for FilterValue {
+    fn from(week: Week) -> FilterValue {
+        FilterValue::Week(week)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iso_weeks() {
+        let week: Week = "2021-W03".parse().unwrap();
+        assert_eq!((week.year(), week.week()), (2021, 3));
+        assert_eq!(week.to_string(), "2021-W03");
+        assert_eq!(week.monday(), Date::try_from_ymd(2021, 1, 18).unwrap());
+        // 1 January 2021 was a Friday, so belongs to the last week of 2020.
+        assert_eq!(Week::of(Date::try_from_ymd(2021, 1, 1).unwrap()), Week::new(2020, 53).unwrap());
+
+        for invalid in &["2021-W54", "2021-W00", "2021-W53", "2021-3", "2021-W3", "W03"] {
+            assert!(matches!(invalid.parse::<Week>(), Err(Error::InvalidWeek(_))), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "cli")]
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::process::Command;
+use std::thread;
+
+/// Serves a single page of data on a local port, returning the URL of the 
+/// data endpoint and the thread recording the request line.
+fn serve_page(body: &'static str) -> (String, thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/v1/data", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = vec![];
+        let mut buf = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            match stream.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => request.extend_from_slice(&buf[..n]),
+            }
+        }
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let request = String::from_utf8_lossy(&request);
+        request.lines().next().unwrap_or_default().to_string()
+    });
+    (url, server)
+}
+
+#[test]
+fn prints_csv() {
+    let (url, server) = serve_page(
+        r#"{"data": [["2021-01-02", 2600000], ["2021-01-01", 2540000]], "pagination": {"next": null}}"#,
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_covid"))
+        .args(["--area", "nation", "--name", "England"])
+        .args(["--metric", "date", "--metric", "cumCasesByPublishDate"])
+        .args(["--format", "csv", "--base-url", &url])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "date,cumCasesByPublishDate\n2021-01-02,2600000\n2021-01-01,2540000\n"
+    );
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("GET /v1/data?filters=areaType=nation;areaName=england&"));
+}
+
+#[test]
+fn rejects_unknown_area_type() {
+    let output = Command::new(env!("CARGO_BIN_EXE_covid"))
+        .args(["--area", "county", "--metric", "date"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("county"));
+}